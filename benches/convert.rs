@@ -0,0 +1,55 @@
+//! Rough throughput measurements for the conversion functions
+//!
+//! Run with `cargo bench`. Each case converts the same input repeatedly and
+//! reports the average time and throughput.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use esperanto_text::{System, Transliterator};
+
+const ITERATIONS: u32 = 50;
+
+fn bench<F: FnMut() -> usize>(name: &str, bytes: usize, mut f: F) {
+    black_box(f());
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(f());
+    }
+    let per_iter = start.elapsed() / ITERATIONS;
+    let mb_per_s = bytes as f64 / per_iter.as_secs_f64() / 1_000_000.0;
+    println!("{:<40} {:>10.2?} {:>10.1} MB/s", name, per_iter, mb_per_s);
+}
+
+fn main() {
+    let no_matches = "The quick brown fox jumps over the lazy dog.\n".repeat(20_000);
+    let esperanto = "Ĉiuj estas senchavaj kaj taŭgaj ideoj.\n".repeat(20_000);
+    let x_system = "Cxiuj estas senchavaj kaj tauxgaj ideoj.\n".repeat(20_000);
+
+    let to_x = Transliterator::new(System::Utf8, System::X);
+    let from_x = Transliterator::new(System::X, System::Utf8);
+    let from_h = Transliterator::new(System::H, System::Utf8);
+
+    bench("utf8 -> x, no matches, convert", no_matches.len(), || {
+        to_x.convert(&no_matches).len()
+    });
+    bench(
+        "utf8 -> x, no matches, convert_cow",
+        no_matches.len(),
+        || to_x.convert_cow(&no_matches).len(),
+    );
+    bench("h -> utf8, no matches, convert", no_matches.len(), || {
+        from_h.convert(&no_matches).len()
+    });
+    bench(
+        "h -> utf8, no matches, convert_cow",
+        no_matches.len(),
+        || from_h.convert_cow(&no_matches).len(),
+    );
+    bench("utf8 -> x, esperanto", esperanto.len(), || {
+        to_x.convert(&esperanto).len()
+    });
+    bench("x -> utf8, esperanto", x_system.len(), || {
+        from_x.convert(&x_system).len()
+    });
+}
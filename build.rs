@@ -0,0 +1,222 @@
+//! Generates the exception pattern tables from the word lists in `data/`
+
+use std::env;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+/// A fragment from a word list and where in a word it may match
+struct Entry {
+    fragment: String,
+    at_start: bool,
+    at_end: bool,
+}
+
+/// Read a word list, skipping comments and blank lines
+fn read_list(path: &str) -> Vec<Entry> {
+    println!("cargo:rerun-if-changed={}", path);
+    let text = fs::read_to_string(path).unwrap_or_else(|e| panic!("reading {}: {}", path, e));
+    text.lines()
+        .map(|line| line.split('#').next().unwrap_or("").trim())
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let fragment = entry.trim_start_matches('^').trim_end_matches('$');
+            assert!(
+                !fragment.is_empty() && fragment.chars().all(|c| c.is_ascii_lowercase()),
+                "{}: entries must be lowercase ASCII: {:?}",
+                path,
+                entry
+            );
+            Entry {
+                fragment: fragment.to_owned(),
+                at_start: entry.starts_with('^'),
+                at_end: entry.ends_with('$'),
+            }
+        })
+        .collect()
+}
+
+/// Read the whitespace-separated roots in a list, sorted and without
+/// duplicates
+fn read_roots(path: &str) -> Vec<String> {
+    println!("cargo:rerun-if-changed={}", path);
+    let text = fs::read_to_string(path).unwrap_or_else(|e| panic!("reading {}: {}", path, e));
+    let mut roots: Vec<String> = text
+        .lines()
+        .flat_map(|line| line.split('#').next().unwrap_or("").split_whitespace())
+        .map(|root| {
+            assert!(
+                root.chars().all(char::is_lowercase),
+                "{}: roots must be lowercase: {:?}",
+                path,
+                root
+            );
+            root.to_owned()
+        })
+        .collect();
+    roots.sort();
+    roots.dedup();
+    roots
+}
+
+/// How many roots share each run of front coding
+const ROOT_BUCKET: usize = 16;
+
+/// Front-code sorted `roots`, giving the coded text and the byte offset of
+/// each bucket of `ROOT_BUCKET` roots
+///
+/// Each root is written as a byte from '0' up giving how many of its bytes
+/// are the same as the root before it, then the rest of its bytes. The
+/// first root in a bucket is written out whole, so a bucket can be read
+/// without the ones before it.
+fn front_code(roots: &[String]) -> (String, Vec<usize>) {
+    let mut data = String::new();
+    let mut buckets = Vec::new();
+    let mut prev = "";
+    for (i, root) in roots.iter().enumerate() {
+        if i % ROOT_BUCKET == 0 {
+            buckets.push(data.len());
+            prev = "";
+        }
+        let shared: usize = prev
+            .chars()
+            .zip(root.chars())
+            .take_while(|(a, b)| a == b)
+            .map(|(c, _)| c.len_utf8())
+            .sum();
+        assert!(shared < 16, "root shares too long a prefix: {:?}", root);
+        data.push(char::from(b'0' + shared as u8));
+        data.push_str(&root[shared..]);
+        prev = root;
+    }
+    (data, buckets)
+}
+
+fn push_entries(out: &mut String, comment: &str, entries: &[Entry]) {
+    writeln!(out, "    // {}", comment).unwrap();
+    for entry in entries {
+        writeln!(out, "    {:?},", entry.fragment).unwrap();
+    }
+}
+
+fn main() {
+    let h_exceptions = read_list("data/h-exceptions.txt");
+    let au_exceptions = read_list("data/au-exceptions.txt");
+    let x_exceptions = read_list("data/x-exceptions.txt");
+
+    let mut out = String::new();
+    out.push_str(
+        "#[cfg(feature = \"h-system\")]
+/// Patterns to match for h-system input (case-insensitive)
+///
+/// This includes all the transliterations but also a reasonably exhaustive
+/// list of word fragments that need to be left alone, rather than blindly
+/// substituting \"something+h\" with a diacritic. These longer segments will
+/// be allowed to pass through unchanged.
+///
+/// Generated by `build.rs` from the lists in `data/`.
+const FROM_H_CI: &[&str] = &[
+",
+    );
+    push_entries(&mut out, "Uses of \"h\" to leave alone", &h_exceptions);
+    push_entries(
+        &mut out,
+        "Uses of \"au\" (without circumflex) to leave alone",
+        &au_exceptions,
+    );
+    out.push_str(
+        "    // Regular letters to transliterate
+    \"ch\", \"gh\", \"hh\", \"jh\", \"sh\",
+    // In most situations this is meant to become \"aŭ\"
+    \"au\",
+];
+",
+    );
+
+    writeln!(
+        out,
+        "
+/// How many entries at the start of `FROM_H_CI` are exceptions for \"h\"
+/// and then for \"au\"
+#[cfg(feature = \"h-system\")]
+const H_EXCEPTION_COUNTS: (usize, usize) = ({}, {});",
+        h_exceptions.len(),
+        au_exceptions.len()
+    )
+    .unwrap();
+
+    out.push_str(
+        "
+/// Words that look like x-system but aren't, to leave alone (case-insensitive)
+///
+/// Generated by `build.rs` from `data/x-exceptions.txt`.
+#[cfg(feature = \"x-system\")]
+const X_EXCEPTIONS_CI: &[&str] = &[
+",
+    );
+    push_entries(&mut out, "Foreign words containing \"x\"", &x_exceptions);
+    out.push_str("];\n");
+
+    out.push_str(
+        "
+/// Exception fragments that only match at the start and/or end of a word
+///
+/// Each is the fragment, whether it must start a word and whether it must
+/// end one. Generated by `build.rs` from the lists in `data/`.
+const EXCEPTION_ANCHORS: &[(&str, bool, bool)] = &[
+",
+    );
+    let all = h_exceptions.iter().chain(&au_exceptions).chain(&x_exceptions);
+    for entry in all {
+        if entry.at_start || entry.at_end {
+            writeln!(
+                out,
+                "    ({:?}, {}, {}),",
+                entry.fragment, entry.at_start, entry.at_end
+            )
+            .unwrap();
+        }
+    }
+    out.push_str("];\n");
+
+    let particles = read_roots("data/particles.txt");
+    let mut roots = read_roots("data/roots.txt");
+    roots.extend(particles.iter().cloned());
+    roots.sort();
+    roots.dedup();
+    let (data, buckets) = front_code(&roots);
+    writeln!(
+        out,
+        "
+/// Esperanto roots for looking up whole words, sorted and front-coded
+///
+/// Generated by `build.rs` from `data/roots.txt` and `data/particles.txt`.
+/// See `ROOT_BUCKETS` for where to start reading.
+#[cfg(feature = \"big-dict\")]
+const ROOT_DATA: &str = {:?};
+
+/// The byte offset in `ROOT_DATA` of every {}th root, which is written out
+/// whole
+#[cfg(feature = \"big-dict\")]
+const ROOT_BUCKETS: &[usize] = &{:?};",
+        data, ROOT_BUCKET, buckets
+    )
+    .unwrap();
+
+    out.push_str(
+        "
+/// Words among `ROOTS` that can stand without a grammatical ending, sorted
+///
+/// Generated by `build.rs` from `data/particles.txt`.
+#[cfg(feature = \"big-dict\")]
+const PARTICLES: &[&str] = &[
+",
+    );
+    for particle in particles {
+        writeln!(out, "    {:?},", particle).unwrap();
+    }
+    out.push_str("];\n");
+
+    let dest = Path::new(&env::var("OUT_DIR").unwrap()).join("patterns.rs");
+    fs::write(dest, out).unwrap();
+}
@@ -1,45 +1,708 @@
-/// Utility to transliterate Esperanto
+//! Utility to transliterate Esperanto
 
-use std::io::{self, Read};
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use clap::error::ErrorKind;
+use clap::{Args, CommandFactory, Parser, Subcommand, ValueEnum};
+#[cfg(feature = "encodings")]
+use esperanto_text::Encoding;
+use esperanto_text::{
+    detect_system, fix_mojibake, fix_ocr, latin3_to_utf8, text_stats, utf8_to_latin3,
+    validate_orthography, LetterSet, Morse, PercentEscapes, Pipeline, System, Transliterator,
+};
+
+/// Convert Esperanto text between UTF-8 and its transliterations
+#[derive(Parser)]
+#[command(
+    name = "eotext",
+    version,
+    args_conflicts_with_subcommands = true,
+    after_help = "`eotext <FROM> <TO> [TEXT]...` is short for \
+                  `eotext --from <FROM> --to <TO> [TEXT]...`, e.g. `eotext x u \"sxangxo\"`."
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[command(flatten)]
+    convert: ConvertArgs,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Convert text from one system to another, as without a subcommand
+    Convert(ConvertArgs),
+    /// Guess which system a text is written in
+    Detect(TextArgs),
+    /// List letters that don't belong in Esperanto
+    Check(TextArgs),
+    /// Count the words and letters of a text
+    Stats(TextArgs),
+    /// Write UTF-8 text in Morse code, or read it back
+    Morse(MorseArgs),
+    /// Measure conversion speed
+    Bench(BenchArgs),
+    /// Propose h-system exceptions from correctly written UTF-8 files
+    #[cfg(feature = "learn")]
+    Learn {
+        /// A directory of UTF-8 texts
+        dir: PathBuf,
+    },
+}
+
+/// The systems, by name or by letter
+#[derive(Clone, Copy, ValueEnum)]
+enum SystemArg {
+    /// UTF-8 with diacritics, or "u"
+    #[value(name = "utf8", alias = "u", alias = "utf-8")]
+    Utf8,
+    /// x-system, like "cxu", or "x"
+    #[value(name = "x-system", alias = "x")]
+    X,
+    /// h-system, like "chu", or "h"
+    #[value(name = "h-system", alias = "h")]
+    H,
+    /// A caret after the letter, like "c^u", or "c"
+    #[value(name = "postfix-caret", alias = "c")]
+    PostfixCaret,
+    /// A caret before the letter, like "^cu", or "p"
+    #[value(name = "prefix-caret", alias = "p")]
+    PrefixCaret,
+    /// An apostrophe after the letter, like "c'u", or "a"
+    #[value(name = "apostrophe", alias = "a")]
+    Apostrophe,
+    /// The hats dropped, like "cu", as output only, or "r"
+    #[value(name = "reduced", alias = "r")]
+    Reduced,
+    /// HTML character references, like "&#265;u", or "e"
+    #[value(name = "html", alias = "e")]
+    Html,
+    /// LaTeX accent commands, like "\^{c}u", or "l"
+    #[value(name = "latex", alias = "l")]
+    Latex,
+    /// Unicode escapes in source code, like "\u0109u", or "s"
+    #[value(name = "unicode-escape", alias = "s")]
+    UnicodeEscape,
+    /// The Cyrillic script, like "чу", or "k"
+    #[value(name = "cyrillic", alias = "k")]
+    Cyrillic,
+}
+
+impl From<SystemArg> for System {
+    fn from(system: SystemArg) -> System {
+        match system {
+            SystemArg::Utf8 => System::Utf8,
+            SystemArg::X => System::X,
+            SystemArg::H => System::H,
+            SystemArg::PostfixCaret => System::PostfixCaret,
+            SystemArg::PrefixCaret => System::PrefixCaret,
+            SystemArg::Apostrophe => System::Apostrophe,
+            SystemArg::Reduced => System::Reduced,
+            SystemArg::Html => System::Html,
+            SystemArg::Latex => System::Latex,
+            SystemArg::UnicodeEscape => System::UnicodeEscape,
+            SystemArg::Cyrillic => System::Cyrillic,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum PercentArg {
+    /// Leave escapes like "%C4%89" alone
+    Keep,
+    /// Convert inside the escapes
+    Convert,
+}
+
+#[derive(Args)]
+struct ConvertArgs {
+    /// The system of the input
+    #[arg(long, value_name = "SYSTEM")]
+    from: Option<SystemArg>,
+    /// The system to write
+    #[arg(long, value_name = "SYSTEM")]
+    to: Option<SystemArg>,
+    /// Convert FILE instead of the text; may be repeated
+    #[arg(short, long = "input", value_name = "FILE", conflicts_with = "text")]
+    inputs: Vec<String>,
+    /// Write to FILE, or with several inputs each to a file of the same name in DIR
+    #[arg(short, long, value_name = "FILE or DIR")]
+    output: Option<String>,
+    /// First repair letters that were encoded twice, like "Ä‰"
+    #[arg(long)]
+    fix_mojibake: bool,
+    /// First repair letters misread by OCR, like "6" for "ĉ"
+    #[arg(long)]
+    fix_ocr: bool,
+    /// Leave URLs, email addresses and domain names unchanged
+    #[arg(long)]
+    skip_links: bool,
+    /// Also convert inside email header encoded words, like "=?UTF-8?Q?=C4=89u?="
+    #[arg(long)]
+    mime: bool,
+    /// What to do with URL escapes like "%C4%89"
+    #[arg(long, value_name = "MODE")]
+    percent: Option<PercentArg>,
+    /// Leave sentences that don't look like Esperanto unchanged
+    #[arg(long)]
+    only_esperanto: bool,
+    /// Convert only some of the letters, e.g. "cghjs" for all but "ŭ"
+    #[arg(long, value_parser = parse_letters)]
+    letters: Option<LetterSet>,
+    /// Write some hatted letters your own way, e.g. "ĥ=x,ŭ=w"
+    #[arg(long = "map", value_name = "TABLE", value_parser = parse_mapping)]
+    mapping: Option<Mapping>,
+    /// Keep h-system "au" as it is instead of reading it as "aŭ"
+    #[arg(long)]
+    no_au: bool,
+    /// Write "k" for "ĥ" in roots like "arĥivo" and "ĥemio"
+    #[arg(long)]
+    modern_k: bool,
+    /// Read ISO 8859-3 instead of UTF-8
+    #[arg(long)]
+    from_latin3: bool,
+    /// Write ISO 8859-3 instead of UTF-8
+    #[arg(long)]
+    to_latin3: bool,
+    /// Read a legacy file such as cp1250 or ibm852, or "auto" to guess
+    #[cfg(feature = "encodings")]
+    #[arg(long, value_name = "LABEL", value_parser = parse_encoding)]
+    encoding: Option<EncodingArg>,
+    /// The text to convert, or else standard input
+    #[arg(trailing_var_arg = true)]
+    text: Vec<String>,
+}
+
+/// The text for a subcommand, which is read from standard input if neither
+/// is given
+#[derive(Args)]
+struct TextArgs {
+    /// Read the text from FILE
+    #[arg(short, long, value_name = "FILE", conflicts_with = "text")]
+    input: Option<PathBuf>,
+    /// The text
+    #[arg(trailing_var_arg = true)]
+    text: Vec<String>,
+}
+
+#[derive(Args)]
+struct MorseArgs {
+    /// Read Morse code back to text
+    #[arg(short, long)]
+    decode: bool,
+    /// Put SEP between letters
+    #[arg(long, value_name = "SEP")]
+    letter: Option<String>,
+    /// Put SEP between words
+    #[arg(long, value_name = "SEP")]
+    word: Option<String>,
+    /// The text, or else standard input
+    #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+    text: Vec<String>,
+}
+
+#[derive(Args)]
+struct BenchArgs {
+    /// How much sample text to generate
+    #[arg(long, value_name = "BYTES", default_value_t = 10_000_000)]
+    size: usize,
+    /// Measure only this direction rather than all between u, x and h
+    #[arg(long, num_args = 2, value_names = ["FROM", "TO"])]
+    direction: Vec<SystemArg>,
+    /// Convert a UTF-8 file instead of sample text
+    #[arg(short, long, value_name = "FILE")]
+    input: Option<PathBuf>,
+}
 
 fn main() {
-    let args: Vec<String> = std::env::args().collect();
-    let mut text = String::new();
-    if args.len() < 3 {
-        invalid_input(&args);
-    } else if args.len() == 3 {
-        io::stdin().read_to_string(&mut text)
-            .expect("Could not read from stdin");
+    let cli = Cli::parse_from(expand_shorthand(std::env::args_os().collect()));
+    match cli.command {
+        None => convert(cli.convert),
+        Some(Command::Convert(args)) => convert(args),
+        Some(Command::Detect(args)) => detect(args),
+        Some(Command::Check(args)) => check(args),
+        Some(Command::Stats(args)) => stats(args),
+        Some(Command::Morse(args)) => morse(args),
+        Some(Command::Bench(args)) => bench(args),
+        #[cfg(feature = "learn")]
+        Some(Command::Learn { dir }) => learn(&dir),
+    }
+}
+
+/// Turn the shorthand `eotext x u ...` into `eotext --from x --to u ...`
+///
+/// The systems are the first two arguments that aren't options, so options
+/// may come before them as well as after, unless `--from` or `--to` is given.
+fn expand_shorthand(mut args: Vec<OsString>) -> Vec<OsString> {
+    let command = Cli::command();
+    let mut i = 1;
+    while let Some(arg) = args.get(i).and_then(|arg| arg.to_str()) {
+        if arg == "--" || ["--from", "--to"].contains(&arg.split('=').next().unwrap_or(arg)) {
+            break;
+        }
+        if !arg.starts_with('-') || arg == "-" {
+            if SystemArg::from_str(arg, false).is_ok() {
+                args.insert(i, "--from".into());
+                args.insert(i + 2, "--to".into());
+            }
+            break;
+        }
+        // Skip the option, and its value unless it was given with "="
+        let takes_value = command.get_arguments().any(|option| {
+            let named = match arg.strip_prefix("--") {
+                Some(long) => option.get_long() == Some(long),
+                None => arg.len() == 2 && option.get_short() == arg.chars().nth(1),
+            };
+            named && option.get_action().takes_values()
+        });
+        i += if takes_value { 2 } else { 1 };
+    }
+    args
+}
+
+/// Exit with a usage error like the ones clap reports itself
+fn fail(kind: ErrorKind, message: &str) -> ! {
+    Cli::command().error(kind, message).exit()
+}
+
+/// Convert text or files: `eotext <from> <to> ...`
+fn convert(args: ConvertArgs) {
+    let (from, to) = match (args.from, args.to) {
+        (Some(from), Some(to)) => (from, to),
+        _ => fail(
+            ErrorKind::MissingRequiredArgument,
+            "the systems to convert from and to are required, e.g. `eotext x u` or \
+             `eotext --from x-system --to utf8`",
+        ),
+    };
+    #[cfg(feature = "encodings")]
+    let encoding = args.encoding.map(|EncodingArg(encoding)| encoding);
+    #[cfg(not(feature = "encodings"))]
+    let encoding: Option<()> = None;
+    let mut pipeline = Pipeline::new()
+        .from(from.into())
+        .to(to.into())
+        .skip_links(args.skip_links)
+        .mime_encoded_words(args.mime)
+        .au_to_breve(!args.no_au)
+        .modern_k(args.modern_k)
+        // Legacy encodings without hatted letters used lookalikes like "č"
+        .normalize_confusables(encoding.is_some());
+    if let Some(percent) = args.percent {
+        pipeline = pipeline.percent_escapes(match percent {
+            PercentArg::Keep => PercentEscapes::Keep,
+            PercentArg::Convert => PercentEscapes::Convert,
+        });
+    }
+    if args.only_esperanto {
+        pipeline = pipeline.only_esperanto(0.5);
+    }
+    if let Some(letters) = args.letters {
+        pipeline = pipeline.letters(letters);
+    }
+    if let Some(Mapping(mapping)) = &args.mapping {
+        let table: Vec<(char, &str)> = mapping.iter().map(|(c, s)| (*c, s.as_str())).collect();
+        pipeline = pipeline.mapping(&table).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        });
+    }
+    let transliterator = pipeline.build();
+
+    let (fix, ocr) = (args.fix_mojibake, args.fix_ocr);
+    let (from_latin3, to_latin3) = (args.from_latin3, args.to_latin3);
+    let decode = |bytes: Vec<u8>| {
+        #[cfg(feature = "encodings")]
+        if let Some(encoding) = encoding {
+            let encoding = encoding.unwrap_or_else(|| Encoding::sniff(&bytes));
+            return Ok(encoding.decode(&bytes));
+        }
+        if from_latin3 {
+            Ok(latin3_to_utf8(&bytes))
+        } else {
+            String::from_utf8(bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+        }
+    };
+    let utf8_file = !from_latin3 && !to_latin3 && encoding.is_none();
+    // Convert a file, or standard input, to `out`
+    let convert_input = |path: Option<&str>, out: &mut dyn Write| -> io::Result<()> {
+        let text = match path {
+            Some(path) if utf8_file && !fix && !ocr => {
+                return convert_file(&transliterator, path, out);
+            }
+            Some(path) => read_input(File::open(path), decode)?,
+            None => read_input(Ok(io::stdin()), decode)?,
+        };
+        let mut output = transliterator.convert(&repair(text, fix, ocr));
+        if path.is_none() {
+            output.push('\n');
+        }
+        write_output(&output, to_latin3, out)
+    };
+
+    let inputs = &args.inputs;
+    let mut failed = false;
+    if let Some(dir) = args.output.as_deref().filter(|o| Path::new(o).is_dir()) {
+        // Each input is written to a file of the same name in the directory
+        if inputs.is_empty() {
+            fail(
+                ErrorKind::MissingRequiredArgument,
+                "writing to a directory needs the files to convert, given with -i",
+            );
+        }
+        for path in inputs {
+            let target = match Path::new(path).file_name() {
+                Some(name) => Path::new(dir).join(name),
+                None => fail(
+                    ErrorKind::InvalidValue,
+                    &format!("{} doesn't name a file to write in {}", path, dir),
+                ),
+            };
+            let result = create_output(&target, inputs).and_then(|file| {
+                let mut out = BufWriter::new(file);
+                convert_input(Some(path), &mut out)?;
+                out.flush()
+            });
+            failed |= report(path, result);
+        }
     } else {
-        text = args[3..].join(" ");
-    }
-
-    let output = match (args[1].as_ref(), args[2].as_ref()) {
-        ("u", "x") => esperanto_text::utf8_to_x_system(&text),
-        ("x", "u") => esperanto_text::x_system_to_utf8(&text),
-        ("u", "h") => esperanto_text::utf8_to_h_system(&text),
-        ("h", "u") => esperanto_text::h_system_to_utf8(&text),
-        ("x", "h") => {
-            let utf8 = esperanto_text::x_system_to_utf8(&text);
-            esperanto_text::utf8_to_h_system(&utf8)
-        },
-        ("h", "x") => {
-            let utf8 = esperanto_text::h_system_to_utf8(&text);
-            esperanto_text::utf8_to_x_system(&utf8)
-        }
-        ("h", "h") | ("u", "u") | ("x", "x") => text.clone(),
-        _ => invalid_input(&args),
+        let mut out: Box<dyn Write> = match &args.output {
+            Some(path) => match create_output(Path::new(path), inputs) {
+                Ok(file) => Box::new(BufWriter::new(file)),
+                Err(e) => {
+                    eprintln!("{}: {}", path, e);
+                    std::process::exit(1);
+                }
+            },
+            None => Box::new(BufWriter::new(io::stdout())),
+        };
+        if !args.text.is_empty() {
+            let mut output = transliterator.convert(&repair(args.text.join(" "), fix, ocr));
+            output.push('\n');
+            failed |= report("output", write_output(&output, to_latin3, &mut out));
+        } else if inputs.is_empty() {
+            failed |= report("stdin", convert_input(None, &mut out));
+        }
+        // Multiple inputs are written one after the other
+        for path in inputs {
+            failed |= report(path, convert_input(Some(path), &mut out));
+        }
+        failed |= report(args.output.as_deref().unwrap_or("stdout"), out.flush());
+    }
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+/// Write converted text to `out`, in Latin-3 if `latin3` is set
+fn write_output(text: &str, latin3: bool, out: &mut dyn Write) -> io::Result<()> {
+    if latin3 {
+        let bytes =
+            utf8_to_latin3(text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        out.write_all(&bytes)
+    } else {
+        out.write_all(text.as_bytes())
+    }
+}
+
+/// Create the output file `path`, refusing to overwrite one of the inputs
+fn create_output(path: &Path, inputs: &[String]) -> io::Result<File> {
+    if let Ok(target) = std::fs::canonicalize(path) {
+        if inputs
+            .iter()
+            .any(|input| std::fs::canonicalize(input).is_ok_and(|input| input == target))
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                "output would overwrite an input file",
+            ));
+        }
+    }
+    File::create(path)
+}
+
+/// Print the error in `result`, if any, returning whether there was one
+fn report(name: &str, result: io::Result<()>) -> bool {
+    match result {
+        Ok(()) => false,
+        Err(e) => {
+            eprintln!("{}: {}", name, e);
+            true
+        }
+    }
+}
+
+/// Read all of `input` and turn it into text with `decode`
+fn read_input<R, D>(input: io::Result<R>, decode: D) -> io::Result<String>
+where
+    R: Read,
+    D: FnOnce(Vec<u8>) -> io::Result<String>,
+{
+    let mut bytes = Vec::new();
+    input?.read_to_end(&mut bytes)?;
+    decode(bytes)
+}
+
+/// Read a file, exiting with the error if it can't be read
+fn read_file(path: &Path) -> String {
+    std::fs::read_to_string(path).unwrap_or_else(|e| {
+        eprintln!("{}: {}", path.display(), e);
+        std::process::exit(1);
+    })
+}
+
+/// Read standard input to the end
+fn read_stdin() -> String {
+    let mut text = String::new();
+    io::stdin()
+        .read_to_string(&mut text)
+        .expect("Could not read from stdin");
+    text
+}
+
+impl TextArgs {
+    /// The text for a subcommand: a file given with `-i`, the words after the
+    /// subcommand, or else standard input
+    fn read(&self) -> String {
+        match &self.input {
+            Some(path) => read_file(path),
+            None if self.text.is_empty() => read_stdin(),
+            None => self.text.join(" "),
+        }
+    }
+}
+
+/// Guess which system a text is written in: `eotext detect [text]`
+fn detect(args: TextArgs) {
+    let detection = detect_system(&args.read());
+    println!(
+        "{}  (confidence {:.2})",
+        system_letter(detection.likely),
+        detection.confidence
+    );
+}
+
+/// Report letters that don't belong in Esperanto: `eotext check [text]`
+fn check(args: TextArgs) {
+    let text = args.read();
+    let issues = validate_orthography(&text);
+    for issue in &issues {
+        println!(
+            "{}..{}  {:?}  {}",
+            issue.range.start,
+            issue.range.end,
+            issue.kind,
+            &text[issue.range.clone()]
+        );
+    }
+    if !issues.is_empty() {
+        std::process::exit(1);
+    }
+}
+
+/// Count the words and letters of a text: `eotext stats [text]`
+fn stats(args: TextArgs) {
+    let stats = text_stats(&args.read());
+    println!("words: {}", stats.total_words);
+    println!("letters: {}", stats.total_letters);
+    println!(
+        "hatted letters: {} ({:.1}%)",
+        stats.hatted_letters,
+        stats.hatted_density() * 100.0
+    );
+    let mut words: Vec<(&String, &usize)> = stats.words.iter().collect();
+    words.sort_by(|a, b| b.1.cmp(a.1));
+    for (word, count) in words.into_iter().take(10) {
+        println!("{:>8}  {}", count, word);
+    }
+}
+
+/// Convert a file by mapping it into memory and writing the output in chunks
+#[cfg(feature = "mmap")]
+fn convert_file<W: Write>(t: &Transliterator, path: &str, out: W) -> io::Result<()> {
+    let file = File::open(path)?;
+    // Safety: the map is only read. If another process truncates the file
+    // while it is being converted the program may crash, which is the usual
+    // trade-off accepted by tools that map their input.
+    let map = unsafe { memmap2::Mmap::map(&file)? };
+    let text =
+        std::str::from_utf8(&map).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    t.convert_to_writer(text, out)
+}
+
+/// Convert a file by streaming it through in windows
+#[cfg(not(feature = "mmap"))]
+fn convert_file<W: Write>(t: &Transliterator, path: &str, out: W) -> io::Result<()> {
+    t.convert_stream(File::open(path)?, out)
+}
+
+/// Sample paragraph used to generate benchmark input
+const SAMPLE: &str = "Ĉiuj homoj estas denaske liberaj kaj egalaj laŭ digno kaj rajtoj. \
+Ili posedas racion kaj konsciencon, kaj devus konduti unu al alia en spirito de frateco. \
+Eĥoŝanĝo ĉiuĵaŭde: la senchava ŝipo haltis apud la bushaltejo hieraŭ.\n";
+
+const SYSTEMS: &[System] = &[System::Utf8, System::X, System::H];
+
+/// Measure conversion throughput: `eotext bench [--size N] [--direction F T] [-i FILE]`
+fn bench(args: BenchArgs) {
+    let directions: Vec<(System, System)> = match args.direction[..] {
+        [from, to] => vec![(from.into(), to.into())],
+        _ => SYSTEMS
+            .iter()
+            .flat_map(|from| SYSTEMS.iter().map(move |to| (*from, *to)))
+            .filter(|(from, to)| from != to)
+            .collect(),
+    };
+
+    // Sample text is UTF-8, converted up front into each source system
+    let utf8 = match &args.input {
+        Some(path) => read_file(path),
+        None => SAMPLE.repeat(args.size / SAMPLE.len() + 1),
+    };
+
+    for (from, to) in directions {
+        let text = Transliterator::new(System::Utf8, from).convert(&utf8);
+        let started = Instant::now();
+        let transliterator = Transliterator::new(from, to);
+        let compiled = started.elapsed();
+        let output = transliterator.convert(&text);
+        let elapsed = started.elapsed();
+        let mb_per_s = text.len() as f64 / (elapsed - compiled).as_secs_f64() / 1_000_000.0;
+        println!(
+            "{} -> {}  {:>8.1} MB/s  ({} bytes in {:.2?}, compiled in {:.2?})",
+            system_letter(from),
+            system_letter(to),
+            mb_per_s,
+            output.len(),
+            elapsed - compiled,
+            compiled,
+        );
+    }
+}
+
+/// Write UTF-8 text in Morse code, or read it back with `-d`:
+/// `eotext morse [-d] [--letter SEP] [--word SEP] [text]`
+fn morse(args: MorseArgs) {
+    let mut morse = Morse::new();
+    if let Some(sep) = &args.letter {
+        morse = morse.letter_separator(sep);
+    }
+    if let Some(sep) = &args.word {
+        morse = morse.word_separator(sep);
+    }
+    let text = match args.text.is_empty() {
+        true => read_stdin(),
+        false => args.text.join(" "),
     };
-    println!("{}", output);
-}
-
-fn invalid_input(args: &Vec<String>) -> ! {
-    println!("Usage: {} <from> <to> [input text]", args[0]);
-    println!("where `from` and `to` are one of the following letters:");
-    println!("    u   UTF-8 input (with diacritics)");
-    println!("    x   x-system input");
-    println!("    h   h-system input");
-    println!("If no input text is specified, it is read from standard input.");
-    println!("Example: {} x u \"sxangxo\"", args[0]);
-    std::process::exit(1);
+    if args.decode {
+        println!("{}", morse.decode(text.trim_end()));
+    } else {
+        println!("{}", morse.encode(&text));
+    }
+}
+
+/// Propose h-system exceptions from a directory of UTF-8 texts: `eotext learn DIR`
+#[cfg(feature = "learn")]
+fn learn(dir: &Path) {
+    let entries = std::fs::read_dir(dir).unwrap_or_else(|e| {
+        eprintln!("{}: {}", dir.display(), e);
+        std::process::exit(1);
+    });
+    let mut texts = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if !path.is_file() {
+            continue;
+        }
+        match std::fs::read_to_string(&path) {
+            Ok(text) => texts.push(text),
+            Err(e) => eprintln!("{}: {}", path.display(), e),
+        }
+    }
+    for s in esperanto_text::suggest_exceptions(&texts) {
+        println!("{}\t{}\t{}", s.fragment, s.count, s.example);
+    }
+}
+
+fn system_letter(system: System) -> char {
+    match system {
+        System::Utf8 => 'u',
+        System::X => 'x',
+        System::H => 'h',
+        System::PostfixCaret => 'c',
+        System::PrefixCaret => 'p',
+        System::Apostrophe => 'a',
+        System::Reduced => 'r',
+        System::Html => 'e',
+        System::Latex => 'l',
+        System::UnicodeEscape => 's',
+        System::Cyrillic => 'k',
+        _ => '?',
+    }
+}
+
+/// Read a set of letters like "cgjhs", in either case
+fn parse_letters(s: &str) -> Result<LetterSet, String> {
+    let mut letters = LetterSet::NONE;
+    for c in s.chars() {
+        letters |= match c.to_ascii_lowercase() {
+            'c' => LetterSet::C,
+            'g' => LetterSet::G,
+            'h' => LetterSet::H,
+            'j' => LetterSet::J,
+            's' => LetterSet::S,
+            'u' => LetterSet::U,
+            _ => return Err(format!("\"{}\" isn't one of c, g, h, j, s and u", c)),
+        };
+    }
+    Ok(letters)
+}
+
+/// A table of replacements for hatted letters
+#[derive(Clone)]
+struct Mapping(Vec<(char, String)>);
+
+/// Read a table of replacements like "ĥ=x,ŭ=w"
+fn parse_mapping(s: &str) -> Result<Mapping, String> {
+    s.split(',')
+        .map(|entry| {
+            let (letter, replacement) = entry.split_once('=').ok_or("expected LETTER=TEXT")?;
+            let mut chars = letter.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Ok((c, replacement.to_owned())),
+                _ => Err(format!("\"{}\" isn't a single letter", letter)),
+            }
+        })
+        .collect::<Result<_, String>>()
+        .map(Mapping)
+}
+
+/// A legacy encoding, or `None` to guess it from the input
+#[cfg(feature = "encodings")]
+#[derive(Clone)]
+struct EncodingArg(Option<Encoding>);
+
+/// Read an encoding label like "cp1250", or "auto"
+#[cfg(feature = "encodings")]
+fn parse_encoding(label: &str) -> Result<EncodingArg, String> {
+    match label {
+        "auto" => Ok(EncodingArg(None)),
+        _ => Encoding::for_label(label)
+            .map(|encoding| EncodingArg(Some(encoding)))
+            .ok_or_else(|| format!("unknown encoding: {}", label)),
+    }
+}
+
+/// Apply the repairs asked for on the command line, before converting
+fn repair(mut text: String, mojibake: bool, ocr: bool) -> String {
+    if mojibake {
+        text = fix_mojibake(&text);
+    }
+    if ocr {
+        text = fix_ocr(&text);
+    }
+    text
 }
@@ -0,0 +1,131 @@
+//! Esperanto Braille in Unicode Braille patterns
+
+/// The cell for each lowercase letter, including the foreign letters
+const LETTERS: &[(char, char)] = &[
+    ('a', '⠁'), ('b', '⠃'), ('c', '⠉'), ('ĉ', '⠡'), ('d', '⠙'), ('e', '⠑'), ('f', '⠋'),
+    ('g', '⠛'), ('ĝ', '⠣'), ('h', '⠓'), ('ĥ', '⠩'), ('i', '⠊'), ('j', '⠚'), ('ĵ', '⠹'),
+    ('k', '⠅'), ('l', '⠇'), ('m', '⠍'), ('n', '⠝'), ('o', '⠕'), ('p', '⠏'), ('q', '⠟'),
+    ('r', '⠗'), ('s', '⠎'), ('ŝ', '⠱'), ('t', '⠞'), ('u', '⠥'), ('ŭ', '⠬'), ('v', '⠧'),
+    ('w', '⠺'), ('x', '⠭'), ('y', '⠽'), ('z', '⠵'),
+];
+
+/// Punctuation with a cell of its own
+const PUNCTUATION: &[(char, char)] = &[
+    (',', '⠂'), (';', '⠆'), (':', '⠒'), ('.', '⠲'), ('!', '⠖'), ('?', '⠢'), ('-', '⠤'),
+    ('\'', '⠄'),
+];
+
+/// The cells that stand for the digits 1 to 9 and 0 after a number sign
+const DIGITS: &[char] = &['⠁', '⠃', '⠉', '⠙', '⠑', '⠋', '⠛', '⠓', '⠊', '⠚'];
+
+/// Put before a capital letter
+const CAPITAL: char = '⠨';
+/// Put before a run of digits
+const NUMBER: char = '⠼';
+/// Put between a number and a letter that would otherwise read as a digit
+const LETTER: char = '⠰';
+
+/// Convert UTF-8 Esperanto to Esperanto Braille
+///
+/// Each capital letter is preceded by ⠨ and each number by ⠼, with its
+/// digits written as the letters "a" to "j". Characters that have no cell,
+/// such as spaces and line breaks, are kept as they are.
+///
+/// ```
+/// use esperanto_text::utf8_to_braille;
+///
+/// assert_eq!(utf8_to_braille("Ĉu ŝi havas 12 jarojn?"), "⠨⠡⠥ ⠱⠊ ⠓⠁⠧⠁⠎ ⠼⠁⠃ ⠚⠁⠗⠕⠚⠝⠢");
+/// ```
+pub fn utf8_to_braille(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() * 3);
+    let mut in_number = false;
+    for c in s.chars() {
+        if let Some(d) = c.to_digit(10) {
+            if !in_number {
+                out.push(NUMBER);
+                in_number = true;
+            }
+            out.push(DIGITS[(d as usize + 9) % 10]);
+            continue;
+        }
+        let lower = c.to_lowercase().next().unwrap_or(c);
+        if let Some(&(_, cell)) = LETTERS.iter().find(|(l, _)| *l == lower) {
+            if in_number && DIGITS.contains(&cell) && c == lower {
+                out.push(LETTER);
+            }
+            if c != lower {
+                out.push(CAPITAL);
+            }
+            out.push(cell);
+        } else if let Some(&(_, cell)) = PUNCTUATION.iter().find(|(p, _)| *p == c) {
+            out.push(cell);
+        } else {
+            out.push(c);
+        }
+        in_number = false;
+    }
+    out
+}
+
+/// Convert Esperanto Braille back to UTF-8
+///
+/// This reverses [`utf8_to_braille`](fn.utf8_to_braille.html). Anything
+/// that isn't a known cell is kept as it is.
+///
+/// ```
+/// use esperanto_text::braille_to_utf8;
+///
+/// assert_eq!(braille_to_utf8("⠨⠡⠥ ⠱⠊ ⠓⠁⠧⠁⠎ ⠼⠁⠃ ⠚⠁⠗⠕⠚⠝⠢"), "Ĉu ŝi havas 12 jarojn?");
+/// ```
+pub fn braille_to_utf8(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut in_number = false;
+    let mut capital = false;
+    for c in s.chars() {
+        match c {
+            NUMBER => in_number = true,
+            LETTER => in_number = false,
+            CAPITAL => {
+                capital = true;
+                in_number = false;
+            }
+            _ if in_number && DIGITS.contains(&c) => {
+                let d = DIGITS.iter().position(|d| *d == c).unwrap_or(0);
+                out.push(char::from(b'0' + ((d + 1) % 10) as u8));
+            }
+            _ => {
+                in_number = false;
+                if let Some(&(letter, _)) = LETTERS.iter().find(|(_, cell)| *cell == c) {
+                    if capital {
+                        out.extend(letter.to_uppercase());
+                    } else {
+                        out.push(letter);
+                    }
+                } else if let Some(&(p, _)) = PUNCTUATION.iter().find(|(_, cell)| *cell == c) {
+                    out.push(p);
+                } else {
+                    out.push(c);
+                }
+                capital = false;
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        for text in &[
+            "Ĝis la 3a de majo, ĈIUJ!",
+            "Ĥoro kaj ŭ-aĵo: 2024 + 10b",
+            "la 1j de la monato",
+        ] {
+            assert_eq!(braille_to_utf8(&utf8_to_braille(text)), *text);
+        }
+        assert_eq!(utf8_to_braille("3a"), "⠼⠉⠰⠁");
+    }
+}
@@ -0,0 +1,160 @@
+//! Memoizing conversions of individual words
+
+use std::collections::HashMap;
+use std::mem;
+
+use crate::token;
+use crate::Transliterator;
+
+/// A `Transliterator` that remembers how it converted recent words
+///
+/// Natural text repeats the same words constantly, so for large corpora it
+/// is often quicker to look each word up than to run the matcher over it
+/// again. Only words that could possibly change are cached; everything else
+/// is copied straight to the output.
+///
+/// Options that look beyond a single word, such as
+/// [`Pipeline::skip_links`](struct.Pipeline.html#method.skip_links) and
+/// [`Pipeline::protect_proper_nouns`](struct.Pipeline.html#method.protect_proper_nouns),
+/// can't be applied word by word, so with those the cache is bypassed.
+///
+/// The cache holds roughly `capacity` words. When it fills up, the words that
+/// have gone longest without being used are forgotten.
+///
+#[cfg_attr(feature = "h-system", doc = "```")]
+#[cfg_attr(not(feature = "h-system"), doc = "```ignore")]
+/// use esperanto_text::{CachedTransliterator, System, Transliterator};
+///
+/// let mut t = CachedTransliterator::new(Transliterator::new(System::H, System::Utf8), 10_000);
+/// assert_eq!(t.convert("chiuj kaj chiuj"), "ĉiuj kaj ĉiuj");
+/// assert_eq!(t.cached_words(), 1);
+/// ```
+pub struct CachedTransliterator {
+    inner: Transliterator,
+    capacity: usize,
+    /// Words used since the last rotation
+    recent: HashMap<String, String>,
+    /// Words used in the generation before that, dropped at the next rotation
+    older: HashMap<String, String>,
+}
+
+impl CachedTransliterator {
+    /// Wrap a converter with a cache of about `capacity` words
+    pub fn new(inner: Transliterator, capacity: usize) -> CachedTransliterator {
+        CachedTransliterator {
+            inner,
+            capacity,
+            recent: HashMap::new(),
+            older: HashMap::new(),
+        }
+    }
+
+    /// The converter used for words that aren't in the cache
+    pub fn inner(&self) -> &Transliterator {
+        &self.inner
+    }
+
+    /// The number of distinct words currently cached
+    pub fn cached_words(&self) -> usize {
+        self.recent.len() + self.older.len()
+    }
+
+    /// Forget every cached word
+    pub fn clear(&mut self) {
+        self.recent.clear();
+        self.older.clear();
+    }
+
+    /// Convert a string, using and updating the cache
+    ///
+    /// The result is always the same as `inner().convert(s)`.
+    pub fn convert(&mut self, s: &str) -> String {
+        if !self.inner.is_word_local() {
+            return self.inner.convert(s);
+        }
+        let mut result = String::with_capacity(s.len());
+        for (run, is_word) in token::runs(s) {
+            if !is_word || !self.inner.may_match(run) {
+                result.push_str(run);
+                continue;
+            }
+            result.push_str(&self.lookup(run));
+        }
+        result
+    }
+
+    fn lookup(&mut self, word: &str) -> String {
+        if let Some(converted) = self.recent.get(word) {
+            return converted.clone();
+        }
+        let converted = match self.older.remove(word) {
+            Some(converted) => converted,
+            None => self.inner.convert(word),
+        };
+        // Two generations of half the capacity approximate an LRU cache
+        // without any bookkeeping on each hit
+        if self.recent.len() >= (self.capacity / 2).max(1) {
+            self.older = mem::take(&mut self.recent);
+        }
+        if self.capacity > 0 {
+            self.recent.insert(word.to_owned(), converted.clone());
+        }
+        converted
+    }
+}
+
+#[cfg(all(test, feature = "x-system"))]
+mod tests {
+    use super::*;
+    use crate::System;
+    #[cfg(feature = "h-system")]
+    use crate::{PercentEscapes, Pipeline};
+
+    #[test]
+    fn test_cached_matches_uncached() {
+        let t = Transliterator::new(System::Utf8, System::X);
+        let input = "ĈU vi ŜATAS ĝin? Ĉu, ĉu, Ĉu! EĤOŜANĜO ĉiuĵaŭde s\u{302}i";
+        let expected = t.convert(input);
+        let mut cached = CachedTransliterator::new(t, 100);
+        assert_eq!(cached.convert(input), expected);
+        assert_eq!(cached.convert(input), expected);
+    }
+
+    #[cfg(feature = "h-system")]
+    #[test]
+    fn test_cached_matches_uncached_with_context() {
+        let pipelines = [
+            Pipeline::new().from(System::H).skip_links(true),
+            Pipeline::new().from(System::H).protect_proper_nouns(true),
+            Pipeline::new().from(System::X).only_esperanto(0.5),
+            Pipeline::new().from(System::X).escape('\\'),
+            Pipeline::new().from(System::X).percent_escapes(PercentEscapes::Keep),
+        ];
+        let input = "Vidu github.com/auth, Charles. The box was auxiliary. Cxu c\\xu %C4%89u?";
+        for pipeline in pipelines.iter() {
+            let t = pipeline.clone().build();
+            let expected = t.convert(input);
+            let mut cached = CachedTransliterator::new(t, 100);
+            assert_eq!(cached.convert(input), expected);
+        }
+    }
+
+    #[test]
+    fn test_cache_is_bounded() {
+        let mut cached =
+            CachedTransliterator::new(Transliterator::new(System::X, System::Utf8), 10);
+        let words: Vec<String> = (0..100).map(|i| format!("cxu{}", "a".repeat(i))).collect();
+        for w in &words {
+            cached.convert(w);
+        }
+        assert!(cached.cached_words() <= 10);
+    }
+
+    #[test]
+    fn test_words_without_matches_not_cached() {
+        let mut cached =
+            CachedTransliterator::new(Transliterator::new(System::X, System::Utf8), 10);
+        cached.convert("plain words only");
+        assert_eq!(cached.cached_words(), 0);
+    }
+}
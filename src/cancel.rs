@@ -0,0 +1,42 @@
+//! Stopping a long conversion part way through
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// A flag that can be set from another thread to abandon a conversion
+///
+/// Clones share the same flag, so one can be handed to the thread doing the
+/// conversion and another kept by whoever might want to stop it.
+///
+#[cfg_attr(feature = "x-system", doc = "```")]
+#[cfg_attr(not(feature = "x-system"), doc = "```ignore")]
+/// use esperanto_text::{CancelToken, System, Transliterator};
+///
+/// let token = CancelToken::new();
+/// let t = Transliterator::new(System::X, System::Utf8);
+/// assert_eq!(t.convert_cancellable("cxu", &token).unwrap(), "ĉu");
+///
+/// token.cancel();
+/// assert!(t.convert_cancellable("cxu", &token).is_err());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken {
+    flag: Arc<AtomicBool>,
+}
+
+impl CancelToken {
+    /// Create a token that has not been cancelled
+    pub fn new() -> CancelToken {
+        CancelToken::default()
+    }
+
+    /// Ask any conversion using this token to stop
+    pub fn cancel(&self) {
+        self.flag.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether `cancel` has been called on this token or any of its clones
+    pub fn is_cancelled(&self) -> bool {
+        self.flag.load(Ordering::Relaxed)
+    }
+}
@@ -0,0 +1,114 @@
+//! Reading text in any mixture of systems as a stream of UTF-8 characters
+
+use crate::decoder::{Decoder, Matches, Rule};
+use crate::letter::Letter;
+use crate::matcher::Match;
+#[cfg(feature = "h-system")]
+use crate::morphology::splits_au;
+
+/// Iterates over the characters of a text as they would be written in UTF-8
+///
+/// Hatted letters in any system, including decomposed UTF-8, come out as a
+/// single precomposed character. Alongside each character is the byte range
+/// of the input it was read from, so that positions can be mapped back.
+pub(crate) struct Canonical<'a> {
+    s: &'a str,
+    rules: &'a [Rule],
+    matches: Matches<'a>,
+    next_match: Option<Match>,
+    pos: usize,
+    /// Matches marked `Keep` are passed through character by character
+    keep_until: usize,
+    /// The "ŭ" of an "au" waiting to be emitted after the "a"
+    pending: Option<(char, usize, usize)>,
+}
+
+impl<'a> Canonical<'a> {
+    pub(crate) fn new(s: &'a str) -> Canonical<'a> {
+        let decoder = Decoder::mixed();
+        let mut matches = decoder.find_iter(s);
+        let next_match = matches.next();
+        Canonical {
+            s,
+            rules: &decoder.rules,
+            matches,
+            next_match,
+            pos: 0,
+            keep_until: 0,
+            pending: None,
+        }
+    }
+}
+
+impl<'a> Iterator for Canonical<'a> {
+    /// A character and the start and end of its source in the input
+    type Item = (char, usize, usize);
+
+    fn next(&mut self) -> Option<(char, usize, usize)> {
+        if let Some(p) = self.pending.take() {
+            return Some(p);
+        }
+        if self.pos >= self.keep_until
+            && self.next_match.as_ref().map(Match::start) == Some(self.pos)
+        {
+            if let Some(m) = std::mem::replace(&mut self.next_match, self.matches.next()) {
+                let found = &self.s[m.start()..m.end()];
+                match self.rules[m.pattern()] {
+                    Rule::Keep | Rule::Escaped | Rule::EscapedHh | Rule::PlainH(_) => {
+                        self.keep_until = m.end()
+                    }
+                    #[cfg(feature = "h-system")]
+                    Rule::Breve if found.len() == 2 && splits_au(self.s, m.start()) => {
+                        self.keep_until = m.end()
+                    }
+                    Rule::Letter(letter) => {
+                        let upper = found.chars().any(char::is_uppercase);
+                        self.pos = m.end();
+                        return Some((letter.hatted(upper), m.start(), m.end()));
+                    }
+                    Rule::Plain(c) => {
+                        let upper = found.chars().any(char::is_uppercase);
+                        self.pos = m.end();
+                        let c = if upper { c.to_ascii_uppercase() } else { c };
+                        return Some((c, m.start(), m.end()));
+                    }
+                    Rule::Breve => {
+                        let mut chars = found.chars();
+                        let a = chars.next()?;
+                        let upper = chars.next().is_some_and(char::is_uppercase);
+                        let a_end = m.start() + a.len_utf8();
+                        self.pending = Some((Letter::U.hatted(upper), a_end, m.end()));
+                        self.pos = m.end();
+                        return Some((a, m.start(), a_end));
+                    }
+                }
+            }
+        }
+        let c = self.s[self.pos..].chars().next()?;
+        let start = self.pos;
+        self.pos += c.len_utf8();
+        Some((c, start, self.pos))
+    }
+}
+
+/// The characters of `s` as they would be written in UTF-8
+pub(crate) fn chars(s: &str) -> impl Iterator<Item = char> + '_ {
+    Canonical::new(s).map(|(c, _, _)| c)
+}
+
+#[cfg(all(test, feature = "x-system", feature = "h-system"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_canonical_mixed_systems() {
+        let s: String = chars("ĉu cxu chu c\u{302}u antaux hierau senchava").collect();
+        assert_eq!(s, "ĉu ĉu ĉu ĉu antaŭ hieraŭ senchava");
+    }
+
+    #[test]
+    fn test_canonical_ranges() {
+        let ranges: Vec<(char, usize, usize)> = Canonical::new("Sxau").collect();
+        assert_eq!(ranges, vec![('Ŝ', 0, 2), ('a', 2, 3), ('ŭ', 3, 4)]);
+    }
+}
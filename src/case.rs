@@ -0,0 +1,71 @@
+//! Changing the case of text in any system
+
+use crate::canonical::Canonical;
+use crate::token::is_word_char;
+
+/// Convert text in any system to uppercase
+///
+/// Both letters of an x-system or h-system digraph are capitalised, so that
+/// all-caps text stays consistent.
+///
+/// ```
+/// use esperanto_text::to_uppercase_eo;
+///
+/// assert_eq!(to_uppercase_eo("Cxu ŝi?"), "CXU ŜI?");
+/// ```
+pub fn to_uppercase_eo(s: &str) -> String {
+    s.to_uppercase()
+}
+
+/// Convert text in any system to lowercase
+///
+/// ```
+/// use esperanto_text::to_lowercase_eo;
+///
+/// assert_eq!(to_lowercase_eo("CXU ŜI?"), "cxu ŝi?");
+/// ```
+pub fn to_lowercase_eo(s: &str) -> String {
+    s.to_lowercase()
+}
+
+/// Capitalise the first letter of each word and lowercase the rest
+///
+/// A word that begins with an x-system or h-system digraph has only the base
+/// letter capitalised, as in "Cxu" and "Chu". Apostrophes end a word, so
+/// "l'espero" becomes "L'Espero".
+///
+/// ```
+/// use esperanto_text::to_titlecase_eo;
+///
+/// assert_eq!(to_titlecase_eo("CXU ĈIUJ shatas?"), "Cxu Ĉiuj Shatas?");
+/// ```
+pub fn to_titlecase_eo(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut in_word = false;
+    for (c, start, end) in Canonical::new(s) {
+        let source = &s[start..end];
+        let word = is_word_char(c);
+        if word && !in_word {
+            let mut chars = source.chars();
+            out.extend(chars.next().into_iter().flat_map(char::to_uppercase));
+            out.push_str(&chars.as_str().to_lowercase());
+        } else {
+            out.push_str(&source.to_lowercase());
+        }
+        in_word = word;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_titlecase_eo() {
+        assert_eq!(to_titlecase_eo("GXIS la revido"), "Gxis La Revido");
+        assert_eq!(to_titlecase_eo("c\u{302}IU ĥORO"), "C\u{302}iu Ĥoro");
+        assert_eq!(to_titlecase_eo("AUTO-BUSO"), "Auto-Buso");
+        assert_eq!(to_uppercase_eo(&to_titlecase_eo("cxiuj")), "CXIUJ");
+    }
+}
@@ -0,0 +1,291 @@
+//! Comparing texts regardless of the system they are written in
+
+use std::cmp::Ordering;
+use std::collections::VecDeque;
+use std::ops::Range;
+
+use crate::canonical::{self, Canonical};
+
+/// Whether two texts are the same once both are read as UTF-8
+///
+/// Each side may use any system, or a mixture of them. No intermediate
+/// strings are allocated.
+///
+#[cfg_attr(all(feature = "x-system", feature = "h-system"), doc = "```")]
+#[cfg_attr(not(all(feature = "x-system", feature = "h-system")), doc = "```ignore")]
+/// use esperanto_text::eo_eq;
+///
+/// assert!(eo_eq("ĉu", "cxu"));
+/// assert!(eo_eq("cxu", "chu"));
+/// assert!(eo_eq("Ŝanĝo", "Shangxo"));
+/// assert!(!eo_eq("ĉu", "cu"));
+/// ```
+pub fn eo_eq(a: &str, b: &str) -> bool {
+    canonical::chars(a).eq(canonical::chars(b))
+}
+
+/// The alphabet in Esperanto order, with the foreign letters where they fall
+/// in the Latin alphabet
+const ALPHABET: &str = "abcĉdefgĝhĥijĵklmnopqrsŝtuŭvwxyz";
+
+/// Where `c` sorts, ignoring case
+///
+/// Anything that isn't a letter, such as a space or digit, comes before the
+/// alphabet, and letters from other languages come after it.
+fn collation_weight(c: char) -> (u8, u32) {
+    let lower = c.to_lowercase().next().unwrap_or(c);
+    match ALPHABET.chars().position(|l| l == lower) {
+        Some(i) => (1, i as u32),
+        None if c.is_alphabetic() => (2, u32::from(lower)),
+        None => (0, u32::from(c)),
+    }
+}
+
+/// Compare two texts in Esperanto alphabetical order
+///
+/// Each side may use any system, so "ĉ", "cx" and "ch" are all the letter
+/// that comes between "c" and "d". Texts that differ only in case put
+/// lowercase first, and texts that are otherwise the same are ordered by
+/// their bytes so that the order is total.
+///
+#[cfg_attr(all(feature = "x-system", feature = "h-system"), doc = "```")]
+#[cfg_attr(not(all(feature = "x-system", feature = "h-system")), doc = "```ignore")]
+/// use esperanto_text::eo_cmp;
+/// use std::cmp::Ordering;
+///
+/// assert_eq!(eo_cmp("ĉapelo", "cxu"), Ordering::Less);
+/// assert_eq!(eo_cmp("cxu", "dento"), Ordering::Less);
+/// assert_eq!(eo_cmp("cidro", "chapelo"), Ordering::Less);
+/// ```
+pub fn eo_cmp(a: &str, b: &str) -> Ordering {
+    let weights = |s| canonical::chars(s).map(collation_weight);
+    let cases = |s| canonical::chars(s).map(char::is_uppercase);
+    weights(a)
+        .cmp(weights(b))
+        .then_with(|| cases(a).cmp(cases(b)))
+        .then_with(|| a.cmp(b))
+}
+
+/// A key for storing text so that it can be looked up and sorted regardless
+/// of system and case
+///
+/// Keys compare equal when the texts differ only in system or case, and
+/// compare bytewise in the same order as [`eo_cmp`](fn.eo_cmp.html) apart
+/// from that, so they are suitable for a database index.
+///
+#[cfg_attr(all(feature = "x-system", feature = "h-system"), doc = "```")]
+#[cfg_attr(not(all(feature = "x-system", feature = "h-system")), doc = "```ignore")]
+/// use esperanto_text::eo_collation_key;
+///
+/// assert_eq!(eo_collation_key("ĈU"), eo_collation_key("cxu"));
+/// assert_eq!(eo_collation_key("cxu"), eo_collation_key("Chu"));
+/// assert!(eo_collation_key("ĉu") < eo_collation_key("du"));
+/// assert!(eo_collation_key("cu") < eo_collation_key("ĉu"));
+/// ```
+pub fn eo_collation_key(s: &str) -> Vec<u8> {
+    let mut key = Vec::with_capacity(s.len());
+    for c in canonical::chars(s) {
+        // One byte for each letter of the alphabet, and four for anything
+        // else, with a first byte that keeps the three groups in order
+        match collation_weight(c) {
+            (1, i) => key.push(0x10 + i as u8),
+            (group, value) => {
+                key.push(if group == 0 { 0x01 } else { 0xF0 });
+                key.extend_from_slice(&value.to_be_bytes()[1..]);
+            }
+        }
+    }
+    key
+}
+
+/// Sort texts in Esperanto alphabetical order, as compared by
+/// [`eo_cmp`](fn.eo_cmp.html)
+///
+#[cfg_attr(feature = "x-system", doc = "```")]
+#[cfg_attr(not(feature = "x-system"), doc = "```ignore")]
+/// use esperanto_text::sort_eo;
+///
+/// let mut words = vec!["ŝipo", "zebro", "cxevalo", "sako", "ĉapo", "celo"];
+/// sort_eo(&mut words);
+/// assert_eq!(words, ["celo", "ĉapo", "cxevalo", "sako", "ŝipo", "zebro"]);
+/// ```
+pub fn sort_eo<S: AsRef<str>>(texts: &mut [S]) {
+    texts.sort_by(|a, b| eo_cmp(a.as_ref(), b.as_ref()));
+}
+
+/// Edit distance between two texts, counting hatted letters as single units
+///
+/// Both sides are read as UTF-8 first, so "cx" and "ĉ" are the same letter
+/// and cost nothing, while "c" and "ĉ" differ by one substitution.
+///
+#[cfg_attr(all(feature = "x-system", feature = "h-system"), doc = "```")]
+#[cfg_attr(not(all(feature = "x-system", feature = "h-system")), doc = "```ignore")]
+/// use esperanto_text::eo_levenshtein;
+///
+/// assert_eq!(eo_levenshtein("ŝanĝo", "sxangxo"), 0);
+/// assert_eq!(eo_levenshtein("ŝanĝo", "sangxo"), 1);
+/// assert_eq!(eo_levenshtein("ŝanĝo", "shanghoj"), 1);
+/// ```
+pub fn eo_levenshtein(a: &str, b: &str) -> usize {
+    let a: Vec<char> = canonical::chars(a).collect();
+    let b: Vec<char> = canonical::chars(b).collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut cur = vec![0; b.len() + 1];
+    for (i, ca) in a.iter().enumerate() {
+        cur[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = prev[j] + if ca == cb { 0 } else { 1 };
+            cur[j + 1] = substitution.min(prev[j + 1] + 1).min(cur[j] + 1);
+        }
+        std::mem::swap(&mut prev, &mut cur);
+    }
+    prev[b.len()]
+}
+
+/// Find the first occurrence of `needle` in `haystack`, in any system
+///
+/// The returned range is the byte offsets of the match in `haystack` as it
+/// was written, so it can be used to highlight the original text. An empty
+/// needle matches nothing.
+///
+#[cfg_attr(feature = "h-system", doc = "```")]
+#[cfg_attr(not(feature = "h-system"), doc = "```ignore")]
+/// use esperanto_text::eo_find;
+///
+/// let text = "Mi ne volas shanghon.";
+/// let found = eo_find(text, "ŝanĝo").unwrap();
+/// assert_eq!(&text[found], "shangho");
+/// ```
+pub fn eo_find(haystack: &str, needle: &str) -> Option<Range<usize>> {
+    eo_find_iter(haystack, needle).next()
+}
+
+/// Iterate over every non-overlapping occurrence of `needle` in `haystack`
+///
+#[cfg_attr(all(feature = "x-system", feature = "h-system"), doc = "```")]
+#[cfg_attr(not(all(feature = "x-system", feature = "h-system")), doc = "```ignore")]
+/// use esperanto_text::eo_find_iter;
+///
+/// let text = "ĉu cxu chu";
+/// let found: Vec<&str> = eo_find_iter(text, "ĉu").map(|r| &text[r]).collect();
+/// assert_eq!(found, vec!["ĉu", "cxu", "chu"]);
+/// ```
+pub fn eo_find_iter<'a>(haystack: &'a str, needle: &str) -> EoMatches<'a> {
+    let needle: Vec<char> = canonical::chars(needle).collect();
+    EoMatches {
+        haystack: Canonical::new(haystack),
+        window: VecDeque::with_capacity(needle.len()),
+        needle,
+    }
+}
+
+/// Iterator over the byte ranges found by [`eo_find_iter`](fn.eo_find_iter.html)
+pub struct EoMatches<'a> {
+    haystack: Canonical<'a>,
+    needle: Vec<char>,
+    window: VecDeque<(char, usize, usize)>,
+}
+
+impl<'a> Iterator for EoMatches<'a> {
+    type Item = Range<usize>;
+
+    fn next(&mut self) -> Option<Range<usize>> {
+        if self.needle.is_empty() {
+            return None;
+        }
+        for item in &mut self.haystack {
+            self.window.push_back(item);
+            if self.window.len() < self.needle.len() {
+                continue;
+            }
+            if self
+                .window
+                .iter()
+                .map(|(c, _, _)| *c)
+                .eq(self.needle.iter().copied())
+            {
+                let start = self.window.front().map(|(_, start, _)| *start)?;
+                let end = self.window.back().map(|(_, _, end)| *end)?;
+                self.window.clear();
+                return Some(start..end);
+            }
+            self.window.pop_front();
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "x-system")]
+    #[test]
+    fn test_eo_eq_case_sensitive() {
+        assert!(eo_eq("CXU", "ĈU"));
+        assert!(!eo_eq("cxu", "ĈU"));
+    }
+
+    #[cfg(all(feature = "x-system", feature = "h-system"))]
+    #[test]
+    fn test_eo_eq_vocabulary_and_au() {
+        assert!(eo_eq("senchava", "senchava"));
+        assert!(!eo_eq("senchava", "senĉava"));
+        assert!(eo_eq("taugaj", "tauxgaj"));
+        assert!(eo_eq("hierau", "hieraŭ"));
+    }
+
+    #[test]
+    fn test_eo_cmp() {
+        assert_eq!(eo_cmp("ĈU", "cxu"), Ordering::Greater);
+        assert_eq!(eo_cmp("uzi", "ŭa"), Ordering::Less);
+        assert_eq!(eo_cmp("la domo", "lado"), Ordering::Less);
+        assert_eq!(eo_cmp("zorgi", "éclair"), Ordering::Less);
+        let mut words = vec!["Ĝis".to_owned(), "gxis".to_owned(), "hundo".to_owned()];
+        sort_eo(&mut words);
+        assert_eq!(words, ["gxis", "Ĝis", "hundo"]);
+    }
+
+    #[cfg(all(feature = "x-system", feature = "h-system"))]
+    #[test]
+    fn test_eo_collation_key_order() {
+        let mut words = vec!["zebro", "ĉapo", "la domo", "éclair", "lado", "celo", "ŭa"];
+        let mut by_key = words.clone();
+        sort_eo(&mut words);
+        by_key.sort_by_key(|w| eo_collation_key(w));
+        assert_eq!(words, by_key);
+        assert_eq!(eo_collation_key("Ŝi, ĉu?"), eo_collation_key("SHI, cxu?"));
+    }
+
+    #[cfg(all(feature = "x-system", feature = "h-system"))]
+    #[test]
+    fn test_eo_levenshtein() {
+        assert_eq!(eo_levenshtein("", "ĉu"), 2);
+        assert_eq!(eo_levenshtein("cxu", ""), 2);
+        assert_eq!(eo_levenshtein("ĉevalo", "chevaloj"), 1);
+        assert_eq!(eo_levenshtein("ĵaŭdo", "jaudo"), 1);
+    }
+
+    #[cfg(all(feature = "x-system", feature = "h-system"))]
+    #[test]
+    fn test_eo_find_offsets_in_original() {
+        let text = "La ŝanĝo, la sxangxo kaj la shangho.";
+        let found: Vec<Range<usize>> = eo_find_iter(text, "ŝanĝo").collect();
+        let words: Vec<&str> = found.iter().map(|r| &text[r.clone()]).collect();
+        assert_eq!(words, vec!["ŝanĝo", "sxangxo", "shangho"]);
+    }
+
+    #[cfg(feature = "x-system")]
+    #[test]
+    fn test_eo_find_needle_in_other_system() {
+        assert_eq!(eo_find("antaŭ ĉio", "cxio"), Some(7..11));
+        assert_eq!(eo_find("antaŭ ĉio", "kio"), None);
+        assert_eq!(eo_find("antaŭ ĉio", ""), None);
+    }
+
+    #[cfg(feature = "h-system")]
+    #[test]
+    fn test_eo_find_within_breve() {
+        assert_eq!(eo_find("hierau", "ŭ"), Some(5..6));
+    }
+}
@@ -0,0 +1,183 @@
+//! Composing hatted letters from keystrokes, for input methods
+
+use crate::letter::Letter;
+use crate::System;
+
+/// One character of the composed text
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Unit {
+    c: char,
+    /// Whether `c` was composed from a letter and a modifier key
+    composed: bool,
+}
+
+/// Builds up text key by key, turning letter and modifier pairs into hatted
+/// letters as they are typed
+///
+/// With `System::X`, typing "c" then "x" gives "ĉ", and typing "x" again
+/// gives back "cx" for words like "taxi". `System::H` does the same with
+/// "h", except that "ŭ" is written "u" and so isn't composed.
+/// `System::PostfixCaret` composes "c^" and also "u~", `System::PrefixCaret`
+/// composes "^c" and `System::Apostrophe` composes "c'". With any other
+/// system the keys are taken as they are. In every system, typing the
+/// modifier again after a composed letter gives back the letter and the
+/// modifier, as for "pac'" in the apostrophe system.
+///
+#[cfg_attr(feature = "x-system", doc = "```")]
+#[cfg_attr(not(feature = "x-system"), doc = "```ignore")]
+/// use esperanto_text::{Composer, System};
+///
+/// let mut composer = Composer::new(System::X);
+/// for key in "Cxu".chars() {
+///     composer.push_key(key);
+/// }
+/// assert_eq!(composer.text(), "Ĉu");
+/// composer.pop();
+/// composer.pop();
+/// assert_eq!(composer.text(), "C");
+/// assert_eq!(composer.caret(), 1);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Composer {
+    system: System,
+    units: Vec<Unit>,
+    text: String,
+}
+
+impl Composer {
+    /// Start composing in the given system, with no text
+    pub fn new(system: System) -> Composer {
+        Composer {
+            system,
+            units: Vec::new(),
+            text: String::new(),
+        }
+    }
+
+    /// The text composed so far
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// The byte offset of the caret in [`text`](#method.text), after the
+    /// last key typed
+    pub fn caret(&self) -> usize {
+        self.text.len()
+    }
+
+    /// Type a key, updating the composed text
+    pub fn push_key(&mut self, key: char) {
+        let system = self.system;
+        if let Some(last) = self.units.last_mut() {
+            // A modifier after a letter puts a hat on it
+            if let Some(letter) = Letter::from_base(last.c).filter(|_| !last.composed) {
+                if is_postfix(system, key, letter) {
+                    *last = Unit {
+                        c: letter.hatted(last.c.is_ascii_uppercase()),
+                        composed: true,
+                    };
+                    return self.update();
+                }
+            }
+            // Typing the modifier again takes the hat off and keeps the key
+            if let Some((letter, upper)) = Letter::from_hatted(last.c).filter(|_| last.composed) {
+                if system != System::PrefixCaret && is_postfix(system, key, letter) {
+                    *last = Unit {
+                        c: letter.base(upper),
+                        composed: false,
+                    };
+                    self.units.push(Unit {
+                        c: key,
+                        composed: false,
+                    });
+                    return self.update();
+                }
+            }
+            // A letter after a caret takes the caret as its hat
+            if system == System::PrefixCaret && last.c == '^' && !last.composed {
+                if let Some(letter) = Letter::from_base(key) {
+                    *last = Unit {
+                        c: letter.hatted(key.is_ascii_uppercase()),
+                        composed: true,
+                    };
+                    return self.update();
+                }
+            }
+        }
+        self.units.push(Unit {
+            c: key,
+            composed: false,
+        });
+        self.update();
+    }
+
+    /// Undo the last key, as for backspace
+    ///
+    /// A composed letter loses its hat first, so "ĉ" goes back to "c".
+    /// Returns `false` if there was nothing to undo.
+    pub fn pop(&mut self) -> bool {
+        let last = match self.units.last_mut() {
+            Some(last) => last,
+            None => return false,
+        };
+        match Letter::from_hatted(last.c).filter(|_| last.composed) {
+            Some((letter, upper)) => {
+                *last = Unit {
+                    c: letter.base(upper),
+                    composed: false,
+                }
+            }
+            None => {
+                self.units.pop();
+            }
+        }
+        self.update();
+        true
+    }
+
+    /// Take the composed text, leaving the composer empty
+    pub fn take(&mut self) -> String {
+        self.units.clear();
+        std::mem::take(&mut self.text)
+    }
+
+    fn update(&mut self) {
+        self.text = self.units.iter().map(|u| u.c).collect();
+    }
+}
+
+/// Whether `key` puts a hat on `letter` when typed after it in `system`
+fn is_postfix(system: System, key: char, letter: Letter) -> bool {
+    match system {
+        #[cfg(feature = "x-system")]
+        System::X => key == 'x' || key == 'X',
+        #[cfg(feature = "h-system")]
+        System::H => (key == 'h' || key == 'H') && letter != Letter::U,
+        System::PostfixCaret => key == '^' || key == '~' && letter == Letter::U,
+        System::Apostrophe => key == '\'' || key == '’',
+        _ => false,
+    }
+}
+
+#[cfg(all(test, feature = "x-system", feature = "h-system"))]
+mod tests {
+    use super::*;
+
+    fn compose(system: System, keys: &str) -> String {
+        let mut composer = Composer::new(system);
+        for key in keys.chars() {
+            composer.push_key(key);
+        }
+        composer.take()
+    }
+
+    #[test]
+    fn test_compose() {
+        assert_eq!(compose(System::X, "sxaux taxi cxx"), "ŝaŭ taxi cx");
+        assert_eq!(compose(System::H, "chiuhhh"), "ĉiuhh");
+        assert_eq!(compose(System::PostfixCaret, "c^u~ u^"), "ĉŭ ŭ");
+        assert_eq!(compose(System::PrefixCaret, "^C^u^^g"), "Ĉŭ^ĝ");
+        assert_eq!(compose(System::Apostrophe, "j'au'do"), "ĵaŭdo");
+        assert_eq!(compose(System::Utf8, "cx"), "cx");
+    }
+}
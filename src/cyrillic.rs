@@ -0,0 +1,40 @@
+//! Writing Esperanto in the Cyrillic script
+
+/// Each letter of the alphabet and its Cyrillic counterpart, in lowercase
+///
+/// This follows the usual convention, where "ĝ" is "џ" and "h" is "һ" so
+/// that every letter has exactly one Cyrillic form and back.
+pub(crate) const LETTERS: &[(char, char)] = &[
+    ('a', 'а'), ('b', 'б'), ('c', 'ц'), ('ĉ', 'ч'), ('d', 'д'), ('e', 'е'), ('f', 'ф'),
+    ('g', 'г'), ('ĝ', 'џ'), ('h', 'һ'), ('ĥ', 'х'), ('i', 'и'), ('j', 'й'), ('ĵ', 'ж'),
+    ('k', 'к'), ('l', 'л'), ('m', 'м'), ('n', 'н'), ('o', 'о'), ('p', 'п'), ('r', 'р'),
+    ('s', 'с'), ('ŝ', 'ш'), ('t', 'т'), ('u', 'у'), ('ŭ', 'ў'), ('v', 'в'), ('z', 'з'),
+];
+
+/// `c` written in Cyrillic, or as it is if it isn't an Esperanto letter
+pub(crate) fn to_cyrillic(c: char) -> char {
+    let lower = c.to_lowercase().next().unwrap_or(c);
+    match LETTERS.iter().find(|(latin, _)| *latin == lower) {
+        Some(&(_, cyrillic)) if c != lower => cyrillic.to_uppercase().next().unwrap_or(cyrillic),
+        Some(&(_, cyrillic)) => cyrillic,
+        None => c,
+    }
+}
+
+/// Whether writing `s` in Cyrillic would change it
+pub(crate) fn has_latin(s: &str) -> bool {
+    s.chars().any(|c| to_cyrillic(c) != c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_cyrillic() {
+        let word: String = "Ĝis Ŭ!".chars().map(to_cyrillic).collect();
+        assert_eq!(word, "Џис Ў!");
+        assert!(has_latin("ĉu"));
+        assert!(!has_latin("чу?"));
+    }
+}
@@ -0,0 +1,189 @@
+//! Writing and reading dates in Esperanto
+
+use std::convert::TryFrom;
+
+use crate::canonical;
+use crate::number::words_to_number;
+use crate::{convert, System};
+
+const MONTHS: &[&str] = &[
+    "januaro", "februaro", "marto", "aprilo", "majo", "junio", "julio", "aŭgusto", "septembro",
+    "oktobro", "novembro", "decembro",
+];
+
+const WEEKDAYS: &[&str] = &[
+    "lundo", "mardo", "merkredo", "ĵaŭdo", "vendredo", "sabato", "dimanĉo",
+];
+
+/// A day in the Gregorian calendar
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Date {
+    year: i32,
+    month: u8,
+    day: u8,
+}
+
+impl Date {
+    /// The given day, if it exists, with months numbered from 1
+    pub fn new(year: i32, month: u8, day: u8) -> Option<Date> {
+        let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+        let days = match month {
+            2 if leap => 29,
+            2 => 28,
+            4 | 6 | 9 | 11 => 30,
+            1..=12 => 31,
+            _ => return None,
+        };
+        if day == 0 || day > days {
+            return None;
+        }
+        Some(Date { year, month, day })
+    }
+
+    /// The year, which may be negative
+    pub fn year(&self) -> i32 {
+        self.year
+    }
+
+    /// The month, from 1 for January
+    pub fn month(&self) -> u8 {
+        self.month
+    }
+
+    /// The day of the month, from 1
+    pub fn day(&self) -> u8 {
+        self.day
+    }
+
+    /// The day of the week, from 0 for Monday
+    pub fn weekday(&self) -> u8 {
+        // Sakamoto's method, which counts from Sunday
+        const OFFSETS: [i32; 12] = [0, 3, 2, 5, 0, 3, 5, 1, 4, 6, 2, 4];
+        let year = if self.month < 3 { self.year - 1 } else { self.year };
+        let sunday_based = (year + year.div_euclid(4) - year.div_euclid(100)
+            + year.div_euclid(400)
+            + OFFSETS[usize::from(self.month - 1)]
+            + i32::from(self.day))
+        .rem_euclid(7);
+        ((sunday_based + 6) % 7) as u8
+    }
+}
+
+/// The UTF-8 name of a month, numbered from 1 for "januaro"
+///
+/// ```
+/// assert_eq!(esperanto_text::month_name(8), Some("aŭgusto"));
+/// ```
+pub fn month_name(month: u8) -> Option<&'static str> {
+    MONTHS.get(usize::from(month).checked_sub(1)?).copied()
+}
+
+/// The UTF-8 name of a day of the week, numbered from 0 for "lundo"
+///
+/// ```
+/// assert_eq!(esperanto_text::weekday_name(3), Some("ĵaŭdo"));
+/// ```
+pub fn weekday_name(weekday: u8) -> Option<&'static str> {
+    WEEKDAYS.get(usize::from(weekday)).copied()
+}
+
+/// Write a date as "la 15-a de marto 2025" in the given system
+///
+#[cfg_attr(feature = "x-system", doc = "```")]
+#[cfg_attr(not(feature = "x-system"), doc = "```ignore")]
+/// use esperanto_text::{format_date, Date, System};
+///
+/// let date = Date::new(2025, 8, 15).unwrap();
+/// assert_eq!(format_date(date, System::Utf8), "la 15-a de aŭgusto 2025");
+/// assert_eq!(format_date(date, System::X), "la 15-a de auxgusto 2025");
+/// ```
+pub fn format_date(date: Date, to: System) -> String {
+    let month = MONTHS[usize::from(date.month - 1)];
+    let s = format!("la {}-a de {} {}", date.day, month, date.year);
+    convert(&s, System::Utf8, to)
+}
+
+/// Write a date with its day of the week, as "sabato, la 15-a de marto 2025"
+///
+/// ```
+/// use esperanto_text::{format_date_with_weekday, Date, System};
+///
+/// let date = Date::new(2025, 3, 15).unwrap();
+/// assert_eq!(
+///     format_date_with_weekday(date, System::Utf8),
+///     "sabato, la 15-a de marto 2025"
+/// );
+/// ```
+pub fn format_date_with_weekday(date: Date, to: System) -> String {
+    let weekday = convert(WEEKDAYS[usize::from(date.weekday())], System::Utf8, to);
+    format!("{}, {}", weekday, format_date(date, to))
+}
+
+/// Read a date written like "la 15-a de marto 2025", in any system
+///
+/// A day of the week before it is allowed and ignored, as are case and the
+/// "la". The day may be digits, with or without the "-a", or an ordinal in
+/// words. Returns `None` if the text isn't a date or the day doesn't exist.
+///
+/// ```
+/// use esperanto_text::{parse_date, Date};
+///
+/// let date = Date::new(2025, 3, 15);
+/// assert_eq!(parse_date("la 15-a de marto 2025"), date);
+/// assert_eq!(parse_date("Sabato, la dek-kvina de MARTO 2025"), date);
+/// assert_eq!(parse_date("la 30-a de februaro 2025"), None);
+/// ```
+pub fn parse_date(s: &str) -> Option<Date> {
+    let text: String = canonical::chars(s).flat_map(char::to_lowercase).collect();
+    let mut words = text
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|w| !w.is_empty())
+        .peekable();
+    if words.peek().is_some_and(|w| WEEKDAYS.contains(w)) {
+        words.next();
+    }
+    if words.peek() == Some(&"la") {
+        words.next();
+    }
+    let day = words.next()?;
+    let day = match day.trim_end_matches("-a").trim_end_matches('a').parse() {
+        Ok(day) => day,
+        Err(_) if day.ends_with('a') => u8::try_from(words_to_number(day)?).ok()?,
+        Err(_) => return None,
+    };
+    if words.next()? != "de" {
+        return None;
+    }
+    let month = words.next()?;
+    let month = MONTHS.iter().position(|m| *m == month)? as u8 + 1;
+    let year = words.next()?.parse().ok()?;
+    if words.next().is_some() {
+        return None;
+    }
+    Date::new(year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_weekday() {
+        assert_eq!(Date::new(2000, 1, 1).map(|d| d.weekday()), Some(5));
+        assert_eq!(Date::new(1887, 7, 26).map(|d| d.weekday()), Some(1));
+        assert_eq!(Date::new(2024, 2, 29).map(|d| d.weekday()), Some(3));
+        assert_eq!(Date::new(2023, 2, 29), None);
+        assert_eq!(Date::new(2023, 13, 1), None);
+    }
+
+    #[cfg(all(feature = "x-system", feature = "h-system"))]
+    #[test]
+    fn test_round_trip() {
+        let date = Date::new(1887, 7, 26).unwrap();
+        for system in &[System::Utf8, System::X, System::H] {
+            let text = format_date_with_weekday(date, *system);
+            assert_eq!(parse_date(&text), Some(date), "{}", text);
+        }
+        assert_eq!(parse_date("la 26a de julio"), None);
+    }
+}
@@ -0,0 +1,565 @@
+//! Compiled patterns for reading text in a given system
+
+use std::borrow::Cow;
+use std::sync::OnceLock;
+
+use crate::cyrillic;
+use crate::html;
+use crate::letter::Letter;
+use crate::matcher::{FindIter, Match, Matcher};
+use crate::pipeline::{MatchSpeed, Options, PercentEscapes};
+use crate::token::is_word_char;
+use crate::unicode_escape;
+use crate::System;
+
+/// What to do with a pattern when it is found in the input
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Rule {
+    /// The match is a single hatted letter in some system
+    Letter(Letter),
+    /// An "au" (or "aux", or "aw") where the "u" should become "ŭ"
+    #[cfg_attr(not(any(feature = "x-system", feature = "h-system")), allow(dead_code))]
+    Breve,
+    /// The match must pass through unchanged
+    #[cfg_attr(not(feature = "h-system"), allow(dead_code))]
+    Keep,
+    /// The match contains an escape character to drop, leaving the rest as is
+    Escaped,
+    /// An escaped letter followed by "hh", for "ĥ" written after a letter
+    /// that would otherwise make a digraph with it
+    #[cfg_attr(not(feature = "h-system"), allow(dead_code))]
+    EscapedHh,
+    /// A root from `PLAIN_H_ROOTS` written with "h" for "ĥ", by its index
+    #[cfg_attr(not(feature = "h-system"), allow(dead_code))]
+    PlainH(usize),
+    /// A letter without a diacritic, from a system that writes every letter
+    /// differently, given in lowercase
+    Plain(char),
+}
+
+/// Roots that only exist with "ĥ", as written in the h-system with a plain
+/// "h" for it
+///
+/// The "ĥ" is marked with a capital "H" and a leading '^' anchors the root to
+/// the start of a word. Roots like "ĥor" (choir) that are also a different
+/// word with "h" can't be recovered and are left out.
+#[cfg(feature = "h-system")]
+pub(crate) const PLAIN_H_ROOTS: &[&str] = &[
+    "cheH", "^eHo", "Halif", "Hanuk", "Haos", "Himer", "Hinin", "Hirurg", "Hitin", "Holer", "monaH",
+];
+
+/// LaTeX accent commands for the hatted letters, with and without braces
+const LATEX_CI: &[&str] = &[
+    "\\^{c}", "\\^{g}", "\\^{h}", "\\^{\\j}", "\\^{j}", "\\^{s}", "\\u{u}", "\\^c", "\\^g", "\\^h",
+    "\\^j", "\\^s", "\\u u",
+];
+
+/// Where in a word a pattern is allowed to match
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Anchor {
+    /// The match must begin a word
+    pub(crate) start: bool,
+    /// The match must end a word
+    pub(crate) end: bool,
+}
+
+impl Anchor {
+    /// Whether `haystack[start..end]` satisfies this anchor
+    fn allows(self, haystack: &str, start: usize, end: usize) -> bool {
+        let starts_word = !haystack[..start]
+            .chars()
+            .next_back()
+            .is_some_and(is_word_char);
+        let ends_word = !haystack[end..].chars().next().is_some_and(is_word_char);
+        (!self.start || starts_word) && (!self.end || ends_word)
+    }
+}
+
+/// An automaton along with the rule for each of its patterns
+pub(crate) struct Decoder {
+    matcher: Matcher,
+    pub(crate) rules: Vec<Rule>,
+    anchors: Vec<Anchor>,
+    /// The unanchored patterns alone, with their indexes in `matcher`, for
+    /// when an anchored pattern matches in the wrong place
+    fallback: Option<(Matcher, Vec<usize>)>,
+    /// ASCII bytes (in both cases) of which every ASCII pattern contains one
+    triggers: Vec<(u8, u8)>,
+}
+
+impl Decoder {
+    /// Compile the patterns needed to read `options.from`
+    pub(crate) fn new(options: &Options) -> Decoder {
+        let mut patterns = Patterns::default();
+        match options.from {
+            System::Utf8 | System::Reduced => (),
+            #[cfg(feature = "x-system")]
+            System::X => {
+                patterns.add_x();
+                if options.protect_foreign_words {
+                    patterns.add_x_exceptions();
+                }
+            }
+            #[cfg(feature = "h-system")]
+            System::H => {
+                let exceptions = !options.strict && options.lexicon.is_none();
+                patterns.add_h(options.au_to_breve, exceptions);
+                if options.plain_h {
+                    patterns.add_plain_h();
+                }
+            }
+            System::PostfixCaret | System::PrefixCaret => patterns.add_carets(),
+            System::Apostrophe => patterns.add_apostrophes(),
+            System::Html => patterns.add_references(),
+            System::Latex => patterns.add_latex(),
+            System::UnicodeEscape => patterns.add_unicode_escapes(),
+            System::Cyrillic => patterns.add_cyrillic(),
+        }
+        if options.percent_escapes == PercentEscapes::Convert {
+            patterns.triggers.push((b'%', b'%'));
+        }
+        if options.mime_words {
+            // Encoded words may hide anything behind plain ASCII
+            patterns.triggers.push((b'=', b'='));
+        }
+        if options.to == System::Cyrillic {
+            // Every Latin letter changes, not just the hatted ones
+            patterns
+                .triggers
+                .extend((b'a'..=b'z').map(|b| (b, b.to_ascii_uppercase())));
+        }
+        patterns.remove_keeps(&options.removed_exceptions);
+        if options.w_for_breve && options.from != System::Utf8 {
+            patterns.add_w();
+        }
+        // Letters that are already correct are accepted in every system,
+        // whether precomposed or decomposed
+        patterns.add_utf8();
+        patterns.add_nfd();
+        if options.normalize_confusables {
+            patterns.add_confusables();
+        }
+        if let Some(escape) = options.escape {
+            patterns.add_escapes(options.from, options.to, escape);
+        }
+        for word in options.protected.iter().filter(|w| !w.is_empty()) {
+            patterns.push(word.clone(), Rule::Keep);
+        }
+        if options.from != System::Utf8 {
+            for (fragment, anchor) in &options.exceptions {
+                patterns.push_anchored(fragment.clone(), Rule::Keep, *anchor);
+            }
+        }
+        patterns.build(options.match_speed)
+    }
+
+    /// Patterns that read every system at once, compiled on first use
+    ///
+    /// Where the systems disagree the x-system wins, so "aux" is "aŭ" rather
+    /// than "aŭx".
+    pub(crate) fn mixed() -> &'static Decoder {
+        static MIXED: OnceLock<Decoder> = OnceLock::new();
+        MIXED.get_or_init(|| {
+            let mut patterns = Patterns::default();
+            #[cfg(feature = "x-system")]
+            {
+                patterns.add_x();
+                patterns.add_x_exceptions();
+                patterns.push("aux", Rule::Breve);
+            }
+            #[cfg(feature = "h-system")]
+            patterns.add_h(true, true);
+            patterns.add_utf8();
+            patterns.add_nfd();
+            patterns.build(MatchSpeed::Compact)
+        })
+    }
+
+    /// Iterate over the matches in `haystack` from left to right
+    ///
+    /// Matches are leftmost-longest, except that an anchored pattern is
+    /// passed over when it isn't at the edge of a word.
+    pub(crate) fn find_iter<'a>(&'a self, haystack: &'a str) -> Matches<'a> {
+        Matches {
+            decoder: self,
+            haystack,
+            base: 0,
+            inner: self.matcher.find_iter(haystack),
+        }
+    }
+
+    /// Whether any pattern could possibly match in `s`
+    ///
+    /// This is a cheap pre-check using memchr: all the patterns that aren't
+    /// pure ASCII need a non-ASCII byte, and the rest contain a trigger byte.
+    pub(crate) fn may_match(&self, s: &str) -> bool {
+        let bytes = s.as_bytes();
+        !bytes.is_ascii()
+            || self
+                .triggers
+                .iter()
+                .any(|&(lower, upper)| memchr::memchr2(lower, upper, bytes).is_some())
+    }
+}
+
+/// Iterator over the matches found by a `Decoder`
+pub(crate) struct Matches<'a> {
+    decoder: &'a Decoder,
+    haystack: &'a str,
+    /// Offset in `haystack` where `inner` started searching
+    base: usize,
+    inner: FindIter<'a>,
+}
+
+impl<'a> Matches<'a> {
+    /// Carry on searching from `pos`
+    fn restart(&mut self, pos: usize) {
+        self.base = pos;
+        self.inner = self.decoder.matcher.find_iter(&self.haystack[pos..]);
+    }
+}
+
+impl<'a> Iterator for Matches<'a> {
+    type Item = Match;
+
+    fn next(&mut self) -> Option<Match> {
+        let decoder = self.decoder;
+        loop {
+            let m = self.inner.next()?.offset(self.base);
+            let (fallback, indexes) = match &decoder.fallback {
+                None => return Some(m),
+                Some(fallback) => fallback,
+            };
+            if decoder.anchors[m.pattern()].allows(self.haystack, m.start(), m.end()) {
+                return Some(m);
+            }
+            // The best unanchored match at the same place takes its place,
+            // and failing that the search continues from the next character
+            let rest = &self.haystack[m.start()..];
+            if let Some(f) = fallback.find_iter(rest).next().filter(|f| f.start() == 0) {
+                let f = Match::new(indexes[f.pattern()], m.start(), m.start() + f.end());
+                self.restart(f.end());
+                return Some(f);
+            }
+            let next = rest.chars().next().map_or(1, char::len_utf8);
+            self.restart(m.start() + next);
+        }
+    }
+}
+
+#[derive(Default)]
+struct Patterns {
+    patterns: Vec<Cow<'static, str>>,
+    rules: Vec<Rule>,
+    anchors: Vec<Anchor>,
+    triggers: Vec<(u8, u8)>,
+}
+
+impl Patterns {
+    fn push<P: Into<Cow<'static, str>>>(&mut self, pattern: P, rule: Rule) {
+        self.push_anchored(pattern, rule, Anchor::default());
+    }
+
+    fn push_anchored<P>(&mut self, pattern: P, rule: Rule, anchor: Anchor)
+    where
+        P: Into<Cow<'static, str>>,
+    {
+        self.patterns.push(pattern.into());
+        self.rules.push(rule);
+        self.anchors.push(anchor);
+    }
+
+    #[cfg(feature = "x-system")]
+    fn add_x(&mut self) {
+        self.triggers.push((b'x', b'X'));
+        for &p in crate::FROM_X_CI {
+            self.push(p, Rule::Letter(base_letter(p)));
+        }
+    }
+
+    /// The h-system digraphs and exceptions, and "au" as "aŭ" if `breve`
+    #[cfg(feature = "h-system")]
+    fn add_h(&mut self, breve: bool, exceptions: bool) {
+        // Every digraph ends in "h" and every fragment contains "h" or "au"
+        self.triggers.push((b'h', b'H'));
+        if breve {
+            self.triggers.push((b'u', b'U'));
+        }
+        let wanted = |p: &str| (breve || p != "au") && (exceptions || p.len() == 2);
+        for &p in crate::FROM_H_CI.iter().filter(|p| wanted(p)) {
+            let rule = if p == "au" {
+                Rule::Breve
+            } else if p.len() == 2 {
+                Rule::Letter(base_letter(p))
+            } else {
+                Rule::Keep
+            };
+            self.push_anchored(p, rule, exception_anchor(p));
+        }
+    }
+
+    /// Roots with "ĥ" written as a plain "h"
+    #[cfg(feature = "h-system")]
+    fn add_plain_h(&mut self) {
+        for (i, root) in PLAIN_H_ROOTS.iter().enumerate() {
+            let anchor = Anchor {
+                start: root.starts_with('^'),
+                end: false,
+            };
+            let pattern = root.trim_start_matches('^').to_ascii_lowercase();
+            self.push_anchored(pattern, Rule::PlainH(i), anchor);
+        }
+    }
+
+    /// Carets both before and after the letter
+    fn add_carets(&mut self) {
+        self.triggers.push((b'^', b'^'));
+        self.triggers.push((b'~', b'~'));
+        for &p in crate::FROM_POSTFIX_CARET_CI {
+            self.push(p, Rule::Letter(base_letter(p)));
+        }
+        for &p in crate::FROM_PREFIX_CARET_CI {
+            self.push(p, Rule::Letter(base_letter(&p[1..])));
+        }
+    }
+
+    fn add_apostrophes(&mut self) {
+        self.triggers.push((b'\'', b'\''));
+        for &p in crate::FROM_APOSTROPHE_CI {
+            self.push(p, Rule::Letter(base_letter(p)));
+        }
+        // "ŭ" can end a word after a vowel, where an apostrophe is otherwise
+        // elision
+        for p in ["au'", "eu'"] {
+            self.push(p, Rule::Breve);
+        }
+    }
+
+    fn add_references(&mut self) {
+        self.triggers.push((b'&', b'&'));
+        for (reference, letter) in html::references() {
+            self.push(reference, Rule::Letter(letter));
+        }
+    }
+
+    fn add_latex(&mut self) {
+        self.triggers.push((b'\\', b'\\'));
+        for &p in LATEX_CI {
+            // The letter is the last one in the command
+            let base = p.chars().rfind(char::is_ascii_alphabetic);
+            let letter = base.and_then(Letter::from_base).expect("no letter");
+            self.push(p, Rule::Letter(letter));
+        }
+    }
+
+    fn add_unicode_escapes(&mut self) {
+        self.triggers.push((b'\\', b'\\'));
+        for (escape, letter) in unicode_escape::escapes() {
+            self.push(escape, Rule::Letter(letter));
+        }
+    }
+
+    /// Every Cyrillic letter, in both cases since they aren't ASCII
+    fn add_cyrillic(&mut self) {
+        for &(latin, lower) in cyrillic::LETTERS {
+            let rule = match Letter::from_hatted(latin) {
+                Some((letter, _)) => Rule::Letter(letter),
+                None => Rule::Plain(latin),
+            };
+            for c in lower.to_uppercase().chain(Some(lower)) {
+                self.push(c.to_string(), rule);
+            }
+        }
+    }
+
+    /// "w" for "ŭ", after the vowels that "ŭ" follows
+    fn add_w(&mut self) {
+        self.triggers.push((b'w', b'W'));
+        for p in ["aw", "ew"] {
+            self.push(p, Rule::Breve);
+        }
+    }
+
+    /// Foreign words to leave alone when reading the x-system
+    #[cfg(feature = "x-system")]
+    fn add_x_exceptions(&mut self) {
+        for &p in crate::X_EXCEPTIONS_CI {
+            self.push_anchored(p, Rule::Keep, exception_anchor(p));
+        }
+    }
+
+    /// Escaped digraphs in the input system, plus triggers for the output
+    /// system so that lines needing escapes are not skipped
+    fn add_escapes(&mut self, from: System, to: System, escape: char) {
+        let mut buf = [0; 4];
+        let e: &str = escape.encode_utf8(&mut buf);
+        if let Some((bases, marker)) = digraph_markers(from) {
+            for base in bases.chars() {
+                self.push(format!("{}{}{}", base, e, marker), Rule::Escaped);
+            }
+            #[cfg(feature = "h-system")]
+            if from == System::H {
+                self.push(format!("a{}u", e), Rule::Escaped);
+                for base in bases.chars() {
+                    self.push(format!("{}{}hh", base, e), Rule::EscapedHh);
+                }
+            }
+            self.push(format!("{}{}", e, e), Rule::Escaped);
+        }
+        match to {
+            System::Utf8
+            | System::Reduced
+            | System::Html
+            | System::Latex
+            | System::UnicodeEscape
+            | System::Cyrillic => return,
+            #[cfg(feature = "x-system")]
+            System::X => self.triggers.push((b'x', b'X')),
+            #[cfg(feature = "h-system")]
+            System::H => {
+                self.triggers.push((b'h', b'H'));
+                self.triggers.push((b'u', b'U'));
+            }
+            System::PostfixCaret => {
+                self.triggers.push((b'^', b'^'));
+                self.triggers.push((b'~', b'~'));
+            }
+            System::PrefixCaret => self.triggers.push((b'^', b'^')),
+            System::Apostrophe => self.triggers.push((b'\'', b'\'')),
+        }
+        if escape.is_ascii() {
+            self.triggers.push((escape as u8, escape as u8));
+        }
+    }
+
+    /// Drop the patterns to leave alone that match one of `fragments`
+    fn remove_keeps(&mut self, fragments: &[String]) {
+        if fragments.is_empty() {
+            return;
+        }
+        let mut i = 0;
+        while i < self.patterns.len() {
+            let removed = matches!(self.rules[i], Rule::Keep)
+                && fragments
+                    .iter()
+                    .any(|f| f.eq_ignore_ascii_case(&self.patterns[i]));
+            if removed {
+                self.patterns.remove(i);
+                self.rules.remove(i);
+                self.anchors.remove(i);
+            } else {
+                i += 1;
+            }
+        }
+    }
+
+    fn add_utf8(&mut self) {
+        for &p in crate::FROM_UTF8 {
+            let c = p.chars().next().expect("empty pattern");
+            let (letter, _) = Letter::from_hatted(c).expect("not a hatted letter");
+            self.push(p, Rule::Letter(letter));
+        }
+    }
+
+    fn add_nfd(&mut self) {
+        for &p in crate::FROM_NFD_CI {
+            self.push(p, Rule::Letter(base_letter(p)));
+        }
+    }
+
+    fn add_confusables(&mut self) {
+        for &(p, c) in crate::CONFUSABLES {
+            let (letter, _) = Letter::from_hatted(c).expect("not a hatted letter");
+            self.push(p, Rule::Letter(letter));
+        }
+    }
+
+    fn build(self, speed: MatchSpeed) -> Decoder {
+        let fallback = if self.anchors.iter().any(|a| *a != Anchor::default()) {
+            let indexes: Vec<usize> = (0..self.patterns.len())
+                .filter(|i| self.anchors[*i] == Anchor::default())
+                .collect();
+            let patterns: Vec<&str> = indexes.iter().map(|i| &*self.patterns[*i]).collect();
+            Some((Matcher::new(&patterns, speed), indexes))
+        } else {
+            None
+        };
+        Decoder {
+            matcher: Matcher::new(&self.patterns, speed),
+            rules: self.rules,
+            anchors: self.anchors,
+            fallback,
+            triggers: self.triggers,
+        }
+    }
+}
+
+/// The letters that can start a digraph in `system`, and the letter that ends it
+pub(crate) fn digraph_markers(system: System) -> Option<(&'static str, char)> {
+    match system {
+        System::Utf8
+        | System::Reduced
+        | System::Html
+        | System::Latex
+        | System::UnicodeEscape
+        | System::Cyrillic => None,
+        #[cfg(feature = "x-system")]
+        System::X => Some(("cghjsu", 'x')),
+        #[cfg(feature = "h-system")]
+        System::H => Some(("cghjs", 'h')),
+        System::PostfixCaret => Some(("cghjsu", '^')),
+        // The marker comes first, so escaping is handled separately
+        System::PrefixCaret => None,
+        System::Apostrophe => Some(("cghjsu", '\'')),
+    }
+}
+
+/// The anchor given to an exception fragment in the data files
+fn exception_anchor(fragment: &str) -> Anchor {
+    crate::EXCEPTION_ANCHORS
+        .iter()
+        .find(|(f, _, _)| *f == fragment)
+        .map_or(Anchor::default(), |&(_, start, end)| Anchor { start, end })
+}
+
+pub(crate) fn base_letter(pattern: &str) -> Letter {
+    pattern
+        .chars()
+        .next()
+        .and_then(Letter::from_base)
+        .expect("digraph must start with a base letter")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn found(decoder: &Decoder, haystack: &str) -> Vec<(usize, usize, usize)> {
+        decoder
+            .find_iter(haystack)
+            .map(|m| (m.pattern(), m.start(), m.end()))
+            .collect()
+    }
+
+    #[test]
+    fn test_anchored_patterns() {
+        let mut patterns = Patterns::default();
+        patterns.push("ch", Rule::Letter(Letter::C));
+        let start = Anchor {
+            start: true,
+            end: false,
+        };
+        patterns.push_anchored("chor", Rule::Keep, start);
+        let end = Anchor {
+            start: false,
+            end: true,
+        };
+        patterns.push_anchored("hora", Rule::Keep, end);
+        let decoder = patterns.build(MatchSpeed::Compact);
+
+        assert_eq!(found(&decoder, "choro"), vec![(1, 0, 4)]);
+        assert_eq!(found(&decoder, "ekchoro"), vec![(0, 2, 4)]);
+        assert_eq!(found(&decoder, "xhora"), vec![(2, 1, 5)]);
+        assert_eq!(found(&decoder, "xhoraj chorus"), vec![(1, 7, 11)]);
+    }
+}
@@ -0,0 +1,269 @@
+//! Working out which systems a text is written in
+
+use crate::matcher::Matcher;
+use crate::pipeline::MatchSpeed;
+use crate::System;
+
+/// The result of guessing which system a text uses
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Detection {
+    /// The system that best explains the text
+    pub likely: System,
+    /// How much of the evidence points to `likely`, from 0.0 to 1.0
+    ///
+    /// This is 0.0 when the text contains nothing that distinguishes one
+    /// system from another, such as plain English.
+    pub confidence: f32,
+}
+
+/// Counts of the markers of each system found in a text
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Profile {
+    /// Precomposed UTF-8 hatted letters, e.g. "ĉ"
+    pub utf8_letters: usize,
+    /// x-system digraphs, e.g. "cx"
+    pub x_digraphs: usize,
+    /// h-system digraphs, e.g. "ch"
+    pub h_digraphs: usize,
+    /// Occurrences of "au" that the h-system would read as "aŭ"
+    pub h_breves: usize,
+    /// Words from the h-system vocabulary list, e.g. "senchava"
+    pub vocabulary_words: usize,
+    /// Words that contain markers from more than one system, e.g. "ĉiujxaude"
+    pub mixed_words: usize,
+}
+
+impl Profile {
+    /// Whether the text shows signs of more than one system
+    ///
+    /// This suggests text that was typed inconsistently or has already been
+    /// partially converted.
+    pub fn is_mixed(&self) -> bool {
+        let systems = [
+            self.utf8_letters,
+            self.x_digraphs,
+            self.h_digraphs + self.h_breves,
+        ];
+        self.mixed_words > 0 || systems.iter().filter(|n| **n > 0).count() > 1
+    }
+
+    /// Guess the system based on these counts
+    pub fn detection(&self) -> Detection {
+        let (utf8, x, h) = (
+            self.utf8_letters,
+            self.x_digraphs,
+            self.h_digraphs + self.h_breves,
+        );
+        let total = utf8 + x + h;
+        if total == 0 {
+            return Detection {
+                likely: System::Utf8,
+                confidence: 0.0,
+            };
+        }
+        // Earlier systems win ties
+        let candidates = [
+            (System::Utf8, utf8),
+            #[cfg(feature = "x-system")]
+            (System::X, x),
+            #[cfg(feature = "h-system")]
+            (System::H, h),
+        ];
+        let (likely, count) =
+            candidates.iter().fold(
+                candidates[0],
+                |best, c| if c.1 > best.1 { *c } else { best },
+            );
+        Detection {
+            likely,
+            confidence: count as f32 / total as f32,
+        }
+    }
+}
+
+/// What a pattern found in the text is evidence of
+#[derive(Clone, Copy)]
+enum Marker {
+    Utf8,
+    #[cfg(feature = "x-system")]
+    X,
+    #[cfg(feature = "h-system")]
+    H,
+    #[cfg(feature = "h-system")]
+    Breve,
+    #[cfg(feature = "h-system")]
+    Vocabulary,
+}
+
+impl Marker {
+    /// Bit representing the system this marker belongs to, if any
+    fn system_bit(self) -> u8 {
+        match self {
+            Marker::Utf8 => 1,
+            #[cfg(feature = "x-system")]
+            Marker::X => 2,
+            #[cfg(feature = "h-system")]
+            Marker::H | Marker::Breve => 4,
+            #[cfg(feature = "h-system")]
+            Marker::Vocabulary => 0,
+        }
+    }
+}
+
+/// Count the markers of each system found in a text
+///
+#[cfg_attr(all(feature = "x-system", feature = "h-system"), doc = "```")]
+#[cfg_attr(not(all(feature = "x-system", feature = "h-system")), doc = "```ignore")]
+/// let p = esperanto_text::profile("Ĉiuj cxevaloj kaj senchavaj ideoj");
+/// assert_eq!(p.utf8_letters, 1);
+/// assert_eq!(p.x_digraphs, 1);
+/// assert_eq!(p.vocabulary_words, 1);
+/// assert!(p.is_mixed());
+/// ```
+pub fn profile(s: &str) -> Profile {
+    let mut patterns: Vec<&str> = Vec::new();
+    let mut markers = Vec::new();
+    for p in crate::FROM_UTF8 {
+        patterns.push(p);
+        markers.push(Marker::Utf8);
+    }
+    #[cfg(feature = "x-system")]
+    {
+        for p in crate::FROM_X_CI {
+            patterns.push(p);
+            markers.push(Marker::X);
+        }
+        // "aux" is an x-system ŭ, not an h-system "au" with a stray "x"
+        patterns.push("aux");
+        markers.push(Marker::X);
+    }
+    #[cfg(feature = "h-system")]
+    for p in crate::FROM_H_CI {
+        patterns.push(p);
+        markers.push(match *p {
+            "au" => Marker::Breve,
+            _ if p.len() == 2 => Marker::H,
+            _ => Marker::Vocabulary,
+        });
+    }
+    let matcher = Matcher::new(&patterns, MatchSpeed::Compact);
+
+    let mut profile = Profile::default();
+    let mut word_start = None;
+    let mut word_systems = 0u8;
+    for m in matcher.find_iter(s) {
+        let marker = markers[m.pattern()];
+        match marker {
+            Marker::Utf8 => profile.utf8_letters += 1,
+            #[cfg(feature = "x-system")]
+            Marker::X => profile.x_digraphs += 1,
+            #[cfg(feature = "h-system")]
+            Marker::H => profile.h_digraphs += 1,
+            #[cfg(feature = "h-system")]
+            Marker::Breve => profile.h_breves += 1,
+            #[cfg(feature = "h-system")]
+            Marker::Vocabulary => profile.vocabulary_words += 1,
+        }
+        let start = word_start_before(s, m.start());
+        if word_start != Some(start) {
+            if word_systems.count_ones() > 1 {
+                profile.mixed_words += 1;
+            }
+            word_start = Some(start);
+            word_systems = 0;
+        }
+        word_systems |= marker.system_bit();
+    }
+    if word_systems.count_ones() > 1 {
+        profile.mixed_words += 1;
+    }
+    profile
+}
+
+/// Byte offset of the start of the word containing position `pos`
+fn word_start_before(s: &str, pos: usize) -> usize {
+    s[..pos]
+        .char_indices()
+        .rev()
+        .find(|(_, c)| !c.is_alphabetic())
+        .map_or(0, |(i, c)| i + c.len_utf8())
+}
+
+/// Guess which system a text is written in
+///
+/// Counts the hatted letters, x-system digraphs and h-system digraphs present
+/// and picks the most common. Words from the h-system vocabulary list, such
+/// as "senchava", are not counted as evidence for anything.
+///
+#[cfg_attr(all(feature = "x-system", feature = "h-system"), doc = "```")]
+#[cfg_attr(not(all(feature = "x-system", feature = "h-system")), doc = "```ignore")]
+/// use esperanto_text::{detect_system, System};
+///
+/// assert_eq!(detect_system("Cxu vi sxatas gxin?").likely, System::X);
+/// assert_eq!(detect_system("Ĉu vi ŝatas ĝin?").likely, System::Utf8);
+/// assert_eq!(detect_system("Chu vi shatas ghin?").likely, System::H);
+/// ```
+pub fn detect_system(s: &str) -> Detection {
+    profile(s).detection()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_plain_ascii() {
+        let d = detect_system("The quick brown fox jumps over the lazy dog.");
+        assert_eq!(d.likely, System::Utf8);
+        assert_eq!(d.confidence, 0.0);
+    }
+
+    #[cfg(feature = "x-system")]
+    #[test]
+    fn test_detect_x_system_with_aux() {
+        let d = detect_system("hierauxa antaux");
+        assert_eq!(d.likely, System::X);
+        assert_eq!(d.confidence, 1.0);
+    }
+
+    #[cfg(feature = "h-system")]
+    #[test]
+    fn test_detect_h_system_ignores_vocabulary() {
+        let d = detect_system("senchavaj ideoj kaj taugaj");
+        assert_eq!(d.likely, System::H);
+        assert_eq!(d.confidence, 1.0);
+    }
+
+    #[cfg(feature = "x-system")]
+    #[test]
+    fn test_detect_mixed_confidence() {
+        let d = detect_system("ĉu ĝi cxu");
+        assert_eq!(d.likely, System::Utf8);
+        assert!(d.confidence > 0.6 && d.confidence < 0.7);
+    }
+
+    #[cfg(all(feature = "x-system", feature = "h-system"))]
+    #[test]
+    fn test_profile_counts() {
+        let p = profile("Ĉu ŝi? Cxu sxi? Chu shi? Hierau.");
+        assert_eq!(p.utf8_letters, 2);
+        assert_eq!(p.x_digraphs, 2);
+        assert_eq!(p.h_digraphs, 2);
+        assert_eq!(p.h_breves, 1);
+        assert_eq!(p.mixed_words, 0);
+        assert!(p.is_mixed());
+    }
+
+    #[test]
+    fn test_profile_mixed_words() {
+        let p = profile("ĉiujxaude ŝanĝo");
+        assert_eq!(p.mixed_words, 1);
+    }
+
+    #[test]
+    fn test_profile_single_system_not_mixed() {
+        let p = profile("eĥoŝanĝo ĉiuĵaŭde");
+        assert_eq!(p.utf8_letters, 6);
+        assert!(!p.is_mixed());
+    }
+}
@@ -0,0 +1,377 @@
+//! Looking up h-system words in an embedded list of Esperanto roots
+
+use crate::letter::Letter;
+use crate::lexicon::{BuiltinLexicon, Lexicon};
+
+/// Prefixes that can begin a word before its first root
+const PREFIXES: &[&str] = &[
+    "bo", "dis", "ek", "eks", "fi", "ge", "mal", "mis", "ne", "pra", "re",
+];
+
+/// Suffixes that can follow a root
+const SUFFIXES: &[&str] = &[
+    "aĉ", "ad", "aĵ", "an", "ant", "ar", "at", "ebl", "ec", "eg", "ej", "em", "end", "er", "estr",
+    "et", "id", "ig", "iĝ", "il", "in", "ind", "ing", "int", "ism", "ist", "it", "obl", "on",
+    "ont", "op", "ot", "uj", "ul", "um",
+];
+
+/// Grammatical endings
+const ENDINGS: &[&str] = &[
+    "a", "aj", "ajn", "an", "as", "e", "en", "i", "is", "o", "oj", "ojn", "on", "os", "u", "us",
+];
+
+/// Endings that only particles, pronouns and correlatives take, including
+/// none at all as in "ankaŭ"
+const PARTICLE_ENDINGS: &[&str] = &["", "j", "jn", "n"];
+
+/// Decides h-system words by looking them up in a list of Esperanto roots
+///
+/// Each word is read twice, once as written and once with its digraphs and
+/// "au" turned into hatted letters. If only one reading can be built from
+/// known roots, prefixes, suffixes and endings, that reading wins. Otherwise
+/// the word is left to [`BuiltinLexicon`](struct.BuiltinLexicon.html).
+/// In a word with digraphs, "au" may be read either way in both readings,
+/// so that "autobushaltejo" is read as "aŭtobushaltejo".
+///
+/// Converters without a lexicon of their own also look up h-system
+/// digraphs that the exception list doesn't cover, keeping a word only if
+/// the roots can build it as written and not with hatted letters.
+///
+/// ```
+/// use esperanto_text::{Dictionary, Pipeline, System};
+///
+/// let t = Pipeline::new().from(System::H).lexicon(Dictionary).build();
+/// assert_eq!(t.convert("senchava flughaveno ĉe la shipo"), "senchava flughaveno ĉe la ŝipo");
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Dictionary;
+
+impl Lexicon for Dictionary {
+    fn contains_root(&self, word: &str) -> bool {
+        Dictionary::is_literal(word).unwrap_or_else(|| BuiltinLexicon.contains_root(word))
+    }
+}
+
+impl Dictionary {
+    /// Whether only the reading of `word` as written can be built from known
+    /// roots, or `None` if both readings or neither can
+    pub(crate) fn is_literal(word: &str) -> Option<bool> {
+        let digraphs = reading(word, true, false);
+        let has_digraphs = digraphs != word;
+        let literal = is_word(word) || has_digraphs && is_word(&reading(word, false, true));
+        let hatted = is_word(&reading(word, true, true)) || has_digraphs && is_word(&digraphs);
+        match (literal, hatted) {
+            (true, false) => Some(true),
+            (false, true) => Some(false),
+            _ => None,
+        }
+    }
+}
+
+/// Letters that suggestions may insert or substitute
+const ALPHABET: &str = "abcĉdefgĝhĥijĵklmnoprsŝtuŭvz";
+
+/// The outcome of checking the spelling of a word with
+/// [`check_word`](fn.check_word.html)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SpellResult {
+    /// The word can be built from known roots, affixes and an ending
+    Correct,
+    /// The word isn't recognised
+    Misspelled,
+    /// There is nothing to check, as in a number or punctuation
+    Ignored,
+}
+
+/// `word` read as lowercase UTF-8, whatever system it is written in
+fn normalize(word: &str) -> String {
+    crate::canonical::chars(word)
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Check the spelling of a word in any system against the list of roots
+///
+/// The word must be made up of known roots, prefixes, suffixes and an
+/// ending. Case is ignored, and each part of a hyphenated word is checked.
+///
+/// ```
+/// use esperanto_text::{check_word, SpellResult};
+///
+/// assert_eq!(check_word("Malsanulejo"), SpellResult::Correct);
+/// assert_eq!(check_word("sxipoj"), SpellResult::Correct);
+/// assert_eq!(check_word("cevalo"), SpellResult::Misspelled);
+/// assert_eq!(check_word("2025"), SpellResult::Ignored);
+/// ```
+pub fn check_word(word: &str) -> SpellResult {
+    let word = normalize(word);
+    if !word.chars().any(char::is_alphabetic) || word.chars().any(|c| c.is_ascii_digit()) {
+        return SpellResult::Ignored;
+    }
+    if word.split('-').filter(|p| !p.is_empty()).all(is_word) {
+        SpellResult::Correct
+    } else {
+        SpellResult::Misspelled
+    }
+}
+
+/// Suggest correctly spelled UTF-8 words for a misspelled word in any system
+///
+/// The suggestions are words one letter away from it: with a letter added,
+/// removed, changed or swapped with the next. Adding or removing a
+/// diacritic comes first, then swapped letters, then the rest in
+/// alphabetical order. At most ten are given.
+///
+/// ```
+/// use esperanto_text::suggest;
+///
+/// assert_eq!(suggest("cevalo").first().map(String::as_str), Some("ĉevalo"));
+/// assert_eq!(suggest("kvnakam"), ["kvankam"]);
+/// ```
+pub fn suggest(word: &str) -> Vec<String> {
+    let chars: Vec<char> = normalize(word).chars().collect();
+    let same_base = |c: char| Letter::from_hatted(c).map_or(c, |(l, _)| l.base(false));
+    // Each candidate with its rank: 0 for a diacritic, 1 for a swap, 2 otherwise
+    let mut candidates: Vec<(u8, Vec<char>)> = Vec::new();
+    for i in 0..=chars.len() {
+        if i < chars.len() {
+            let mut removed = chars.clone();
+            removed.remove(i);
+            candidates.push((2, removed));
+        }
+        if i + 1 < chars.len() {
+            let mut swapped = chars.clone();
+            swapped.swap(i, i + 1);
+            candidates.push((1, swapped));
+        }
+        for c in ALPHABET.chars() {
+            if i < chars.len() && chars[i] != c {
+                let mut changed = chars.clone();
+                changed[i] = c;
+                let rank = if same_base(chars[i]) == same_base(c) {
+                    0
+                } else {
+                    2
+                };
+                candidates.push((rank, changed));
+            }
+            let mut added = chars.clone();
+            added.insert(i, c);
+            candidates.push((2, added));
+        }
+    }
+    let mut found: Vec<(u8, String)> = Vec::new();
+    for (rank, candidate) in candidates {
+        let candidate: String = candidate.into_iter().collect();
+        if candidate.chars().ne(chars.iter().copied())
+            && !found.iter().any(|(_, f)| *f == candidate)
+            && is_word(&candidate)
+        {
+            found.push((rank, candidate));
+        }
+    }
+    found.sort_by(|(ra, a), (rb, b)| ra.cmp(rb).then_with(|| crate::eo_cmp(a, b)));
+    found.into_iter().take(10).map(|(_, word)| word).collect()
+}
+
+/// `word` with every h-system digraph and/or "au" read as a hatted letter
+fn reading(word: &str, digraphs: bool, au: bool) -> String {
+    let mut out = String::with_capacity(word.len());
+    let mut chars = word.chars().peekable();
+    while let Some(c) = chars.next() {
+        out.push(c);
+        match (c, chars.peek()) {
+            ('a', Some('u')) if au => {
+                chars.next();
+                out.push('ŭ');
+            }
+            (_, Some('h')) if digraphs => {
+                if let Some(letter) = Letter::from_base(c).filter(|l| *l != Letter::U) {
+                    chars.next();
+                    out.pop();
+                    out.push(letter.hatted(false));
+                }
+            }
+            _ => (),
+        }
+    }
+    out
+}
+
+/// Whether `word` can be built from prefixes, at least one root, suffixes
+/// and an ending
+fn is_word(word: &str) -> bool {
+    // For each byte offset, whether it can be reached and if a root has been
+    // seen on the way there
+    let mut reached: Vec<Option<bool>> = vec![None; word.len() + 1];
+    reached[0] = Some(false);
+    for i in 0..word.len() {
+        let has_root = match reached[i] {
+            Some(has_root) => has_root,
+            None => continue,
+        };
+        let rest = &word[i..];
+        let mut reach = |len: usize, root: bool| {
+            let seen = reached[i + len].unwrap_or(false);
+            reached[i + len] = Some(seen || has_root || root);
+        };
+        for &p in PREFIXES
+            .iter()
+            .filter(|p| !has_root && rest.starts_with(**p))
+        {
+            reach(p.len(), false);
+        }
+        for &s in SUFFIXES
+            .iter()
+            .filter(|s| has_root && rest.starts_with(**s))
+        {
+            reach(s.len(), false);
+        }
+        // A linking "o" may join two roots, as in "vortolibro"
+        if has_root && rest.starts_with('o') {
+            reach(1, false);
+        }
+        for (end, _) in rest.char_indices().skip(1).chain(Some((rest.len(), ' '))) {
+            if is_root(&rest[..end]) {
+                reach(end, true);
+            }
+        }
+    }
+    let with_root = ENDINGS.iter().any(|e| {
+        word.len() >= e.len() && word.ends_with(e) && reached[word.len() - e.len()] == Some(true)
+    });
+    with_root
+        || PARTICLE_ENDINGS.iter().any(|e| {
+            word.strip_suffix(e).is_some_and(|stem| {
+                // Only correlatives like "ĉiu" and "tia" are made plural
+                let plural = e.starts_with('j');
+                is_particles(stem) && (!plural || stem.ends_with(['a', 'u']))
+            })
+        })
+}
+
+/// Whether `s` is one of the roots in the front-coded `ROOT_DATA`
+fn is_root(s: &str) -> bool {
+    let data = crate::ROOT_DATA.as_bytes();
+    // Each root starts with a byte from '0' up, which no letter can be
+    let next_root = |from: usize| {
+        data[from..]
+            .iter()
+            .position(|b| (b'0'..=b'?').contains(b))
+            .map_or(data.len(), |i| from + i)
+    };
+    // Find the last bucket whose first root, written out whole, isn't
+    // after `s`, then read through it
+    let first = |&start: &usize| &data[start + 1..next_root(start + 1)];
+    let bucket = match crate::ROOT_BUCKETS.partition_point(|b| first(b) <= s.as_bytes()) {
+        0 => return false,
+        n => n - 1,
+    };
+    let end = crate::ROOT_BUCKETS
+        .get(bucket + 1)
+        .copied()
+        .unwrap_or(data.len());
+    let mut root = Vec::new();
+    let mut pos = crate::ROOT_BUCKETS[bucket];
+    while pos < end {
+        let next = next_root(pos + 1);
+        root.truncate(usize::from(data[pos] - b'0'));
+        root.extend_from_slice(&data[pos + 1..next]);
+        if root == s.as_bytes() {
+            return true;
+        }
+        pos = next;
+    }
+    false
+}
+
+/// Whether `stem` is made up only of particles, as "dudek" is of "du" and
+/// "dek"
+fn is_particles(stem: &str) -> bool {
+    let mut reached = vec![false; stem.len() + 1];
+    reached[0] = true;
+    for i in 0..stem.len() {
+        if !reached[i] {
+            continue;
+        }
+        for (end, _) in stem[i..].char_indices().skip(1).chain(Some((stem.len() - i, ' '))) {
+            if crate::PARTICLES.binary_search(&&stem[i..i + end]).is_ok() {
+                reached[i + end] = true;
+            }
+        }
+    }
+    !stem.is_empty() && reached[stem.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_word() {
+        for word in &[
+            "ĉiuj",
+            "senchavaj",
+            "malsanulejo",
+            "flughaveno",
+            "ankaŭ",
+            "ŝipoj",
+        ] {
+            assert!(is_word(word), "{}", word);
+        }
+        for word in &["chiuj", "senĉavaj", "xyz", "mal"] {
+            assert!(!is_word(word), "{}", word);
+        }
+        for word in &["dudek", "tiujn", "ĉion", "kien", "kvankam"] {
+            assert!(is_word(word), "{}", word);
+        }
+        for word in &["hund", "hundj", "domjn", "ĉeval", "kajj"] {
+            assert!(!is_word(word), "{}", word);
+        }
+    }
+
+    #[test]
+    fn test_check_word_across_systems() {
+        for word in &["ĉiuj", "cxiuj", "chiuj", "ĉi-tie", "Ŝanĝo"] {
+            assert_eq!(check_word(word), SpellResult::Correct, "{}", word);
+        }
+        assert_eq!(check_word("sxipqo"), SpellResult::Misspelled);
+        assert_eq!(check_word("..."), SpellResult::Ignored);
+    }
+
+    #[test]
+    fn test_suggest() {
+        assert!(suggest("sanghoj")[..2].contains(&"ŝanĝoj".to_owned()));
+        assert!(!suggest("ŝanĝo").contains(&"ŝanĝo".to_owned()));
+        assert!(suggest("lrenejo").contains(&"lernejo".to_owned()));
+        let hundp = suggest("hundp");
+        assert!(hundp.contains(&"hundo".to_owned()));
+        assert!(!hundp.iter().any(|w| ["hund", "hundj", "hundn"].contains(&w.as_str())));
+    }
+
+    #[test]
+    fn test_reading() {
+        assert_eq!(reading("chiuj jhaudo", true, true), "ĉiuj ĵaŭdo");
+        assert_eq!(reading("chiuj jhaudo", true, false), "ĉiuj ĵaudo");
+        assert_eq!(reading("chiuj jhaudo", false, true), "chiuj jhaŭdo");
+        assert_eq!(reading("uhu", true, true), "uhu");
+    }
+
+    #[test]
+    fn test_is_root() {
+        for root in &["abat", "ĉeval", "ŝip", "zum", "kvankam"] {
+            assert!(is_root(root), "{}", root);
+        }
+        for root in &["", "a", "ĉev", "ŝipo", "zz", "hund-"] {
+            assert!(!is_root(root), "{}", root);
+        }
+    }
+
+    #[test]
+    fn test_contains_root() {
+        assert!(Dictionary.contains_root("autobushaltejo"));
+        assert!(Dictionary.contains_root("senchava"));
+        assert!(!Dictionary.contains_root("chevalo"));
+        assert!(!Dictionary.contains_root("jhaudo"));
+    }
+}
@@ -0,0 +1,203 @@
+//! Finding the differences between two texts, ignoring the systems they use
+
+use std::ops::Range;
+
+use crate::canonical::Canonical;
+use crate::token::{tokens, TokenKind};
+
+/// A change between two texts, as found by [`eo_diff`](fn.eo_diff.html)
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Difference {
+    /// The byte range of the old text that was replaced, which is empty for
+    /// an insertion
+    pub old: Range<usize>,
+    /// The byte range of the new text that replaced it, which is empty for a
+    /// deletion
+    pub new: Range<usize>,
+}
+
+/// A word or run of punctuation, read as UTF-8, and where it came from
+struct Piece {
+    text: String,
+    source: Range<usize>,
+}
+
+/// Split `s` into pieces, leaving out whitespace
+fn pieces(s: &str) -> Vec<Piece> {
+    let mut utf8 = String::with_capacity(s.len());
+    // The offset in `s` of every byte of `utf8`, and of its end
+    let mut offsets = Vec::with_capacity(s.len() + 1);
+    for (c, start, _) in Canonical::new(s) {
+        utf8.push(c);
+        offsets.resize(utf8.len(), start);
+    }
+    offsets.push(s.len());
+    tokens(&utf8)
+        .filter(|t| t.kind != TokenKind::Space)
+        .map(|t| {
+            let end = t.start + t.text.len();
+            Piece {
+                text: t.text.to_owned(),
+                source: offsets[t.start]..offsets[end],
+            }
+        })
+        .collect()
+}
+
+/// The most pieces that may differ before the texts are treated as
+/// completely different, which bounds the memory used
+const MAX_EDITS: isize = 4000;
+
+/// The pairs of positions in `a` and `b` that stay the same, in order
+///
+/// This is Myers' algorithm, which is fast when there are few differences.
+fn common(a: &[Piece], b: &[Piece]) -> Vec<(usize, usize)> {
+    // Matching ends are common to every alignment, so take them out first
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x.text == y.text).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x.text == y.text)
+        .count();
+    let mut pairs: Vec<(usize, usize)> = (0..prefix).map(|i| (i, i)).collect();
+    let middle = middle(&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+    pairs.extend(middle.into_iter().map(|(x, y)| (x + prefix, y + prefix)));
+    pairs.extend((1..=suffix).rev().map(|i| (a.len() - i, b.len() - i)));
+    pairs
+}
+
+/// The common pairs of two sequences that differ at both ends
+fn middle(a: &[Piece], b: &[Piece]) -> Vec<(usize, usize)> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let same = |x: isize, y: isize| a[x as usize].text == b[y as usize].text;
+    let offset = n + m + 1;
+    let index = |k: isize| (k + offset) as usize;
+    let mut v = vec![0isize; 2 * offset as usize + 1];
+    // The furthest points reached on the diagonals -d..=d before each step
+    let mut trace = Vec::new();
+    'search: for d in 0..=n + m {
+        if d > MAX_EDITS {
+            return Vec::new();
+        }
+        trace.push(v[index(-d)..=index(d)].to_vec());
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || k != d && v[index(k - 1)] < v[index(k + 1)] {
+                v[index(k + 1)]
+            } else {
+                v[index(k - 1)] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && same(x, y) {
+                x += 1;
+                y += 1;
+            }
+            v[index(k)] = x;
+            if x >= n && y >= m {
+                break 'search;
+            }
+        }
+    }
+    // Walk back through each step, collecting the diagonals
+    let mut pairs = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for (d, v) in trace.iter().enumerate().skip(1).rev() {
+        let d = d as isize;
+        let at = |k: isize| v[(k + d) as usize];
+        let k = x - y;
+        let prev_k = if k == -d || k != d && at(k - 1) < at(k + 1) {
+            k + 1
+        } else {
+            k - 1
+        };
+        let prev_x = at(prev_k);
+        let prev_y = prev_x - prev_k;
+        while x > prev_x && y > prev_y {
+            x -= 1;
+            y -= 1;
+            pairs.push((x as usize, y as usize));
+        }
+        x = prev_x;
+        y = prev_y;
+    }
+    // What is left is a run of matches from the start
+    while x > 0 && y > 0 {
+        x -= 1;
+        y -= 1;
+        pairs.push((x as usize, y as usize));
+    }
+    pairs.reverse();
+    pairs
+}
+
+/// Find the substantive changes between two texts
+///
+/// Each side may use any system, or a mixture of them, and words are
+/// compared as they would be written in UTF-8, so "ĉu" and "cxu" are the
+/// same. Whitespace is ignored too. The texts are compared word by word,
+/// and each run of changed words and punctuation is reported with its byte
+/// range in both texts.
+///
+#[cfg_attr(feature = "x-system", doc = "```")]
+#[cfg_attr(not(feature = "x-system"), doc = "```ignore")]
+/// use esperanto_text::eo_diff;
+///
+/// let old = "Cxu vi sxatas la novan domon?";
+/// let new = "Ĉu vi ŝatas  la malnovan domon?";
+/// let changes = eo_diff(old, new);
+/// assert_eq!(changes.len(), 1);
+/// assert_eq!(&old[changes[0].old.clone()], "novan");
+/// assert_eq!(&new[changes[0].new.clone()], "malnovan");
+/// ```
+pub fn eo_diff(old: &str, new: &str) -> Vec<Difference> {
+    let (a, b) = (pieces(old), pieces(new));
+    let mut differences = Vec::new();
+    let (mut x, mut y) = (0, 0);
+    let end = (a.len(), b.len());
+    for (next_x, next_y) in common(&a, &b).into_iter().chain(Some(end)) {
+        if next_x > x || next_y > y {
+            let span = |pieces: &[Piece], from: usize, to: usize| {
+                if from < to {
+                    pieces[from].source.start..pieces[to - 1].source.end
+                } else {
+                    // Nothing was there, so point just after what came before
+                    let at = from.checked_sub(1).map_or(0, |i| pieces[i].source.end);
+                    at..at
+                }
+            };
+            differences.push(Difference {
+                old: span(&a, x, next_x),
+                new: span(&b, y, next_y),
+            });
+        }
+        x = next_x + 1;
+        y = next_y + 1;
+    }
+    differences
+}
+
+#[cfg(all(test, feature = "h-system"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_eo_diff() {
+        assert!(eo_diff("Ŝi iris hejmen.", "Shi  iris\nhejmen.").is_empty());
+        let old = "la hundo kuris";
+        let new = "la granda hundo";
+        assert_eq!(
+            eo_diff(old, new),
+            [
+                Difference {
+                    old: 2..2,
+                    new: 3..9,
+                },
+                Difference {
+                    old: 9..14,
+                    new: 15..15,
+                },
+            ]
+        );
+        assert_eq!(eo_diff("", "nova")[0].new, 0..4);
+    }
+}
@@ -0,0 +1,222 @@
+//! Decoding legacy 8-bit files before converting them
+//!
+//! Windows-1250 and Windows-1252 are decoded with encoding_rs, as browsers
+//! do. Code page 852 isn't one of the WHATWG encodings, so its table is
+//! written out here.
+
+use crate::error::Error;
+use crate::letter::Letter;
+use crate::{latin3_to_utf8, Pipeline, System, CONFUSABLES};
+
+/// A character encoding that Esperanto text may have been saved in
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Encoding {
+    /// UTF-8, with invalid sequences replaced by U+FFFD
+    Utf8,
+    /// ISO 8859-3, which has all the hatted letters
+    Latin3,
+    /// Windows-1250 (Central European), where "č" and "š" often stood in
+    /// for "ĉ" and "ŝ"
+    Windows1250,
+    /// Windows-1252, which is also what a file labelled ISO 8859-1 usually is
+    Windows1252,
+    /// DOS code page 852 (Central European)
+    Ibm852,
+}
+
+/// The characters for bytes 0x80 to 0xFF in code page 852
+#[rustfmt::skip]
+const IBM_852: [char; 128] = [
+    'Ç', 'ü', 'é', 'â', 'ä', 'ů', 'ć', 'ç', 'ł', 'ë', 'Ő', 'ő', 'î', 'Ź', 'Ä', 'Ć',
+    'É', 'Ĺ', 'ĺ', 'ô', 'ö', 'Ľ', 'ľ', 'Ś', 'ś', 'Ö', 'Ü', 'Ť', 'ť', 'Ł', '×', 'č',
+    'á', 'í', 'ó', 'ú', 'Ą', 'ą', 'Ž', 'ž', 'Ę', 'ę', '¬', 'ź', 'Č', 'ş', '«', '»',
+    '░', '▒', '▓', '│', '┤', 'Á', 'Â', 'Ě', 'Ş', '╣', '║', '╗', '╝', 'Ż', 'ż', '┐',
+    '└', '┴', '┬', '├', '─', '┼', 'Ă', 'ă', '╚', '╔', '╩', '╦', '╠', '═', '╬', '¤',
+    'đ', 'Đ', 'Ď', 'Ë', 'ď', 'Ň', 'Í', 'Î', 'ě', '┘', '┌', '█', '▄', 'Ţ', 'Ů', '▀',
+    'Ó', 'ß', 'Ô', 'Ń', 'ń', 'ň', 'Š', 'š', 'Ŕ', 'Ú', 'ŕ', 'Ű', 'ý', 'Ý', 'ţ', '´',
+    '\u{AD}', '˝', '˛', 'ˇ', '˘', '§', '÷', '¸', '°', '¨', '˙', 'ű', 'Ř', 'ř', '■', '\u{A0}',
+];
+
+/// The encodings tried when sniffing, in order of preference
+const CANDIDATES: &[Encoding] = &[
+    Encoding::Latin3,
+    Encoding::Windows1250,
+    Encoding::Ibm852,
+    Encoding::Windows1252,
+];
+
+impl Encoding {
+    /// Look up an encoding by one of its usual labels, such as "latin3",
+    /// "cp1250" or "ibm852", ignoring case
+    ///
+    /// Apart from code page 852, the labels are the WHATWG ones, so
+    /// "iso-8859-1" and "ascii" also mean Windows-1252.
+    pub fn for_label(label: &str) -> Option<Encoding> {
+        let label = label.trim().to_ascii_lowercase();
+        if let "ibm852" | "cp852" | "852" | "cspcp852" = label.as_str() {
+            return Some(Encoding::Ibm852);
+        }
+        let encoding = encoding_rs::Encoding::for_label(label.as_bytes())?;
+        if encoding == encoding_rs::UTF_8 {
+            Some(Encoding::Utf8)
+        } else if encoding == encoding_rs::ISO_8859_3 {
+            Some(Encoding::Latin3)
+        } else if encoding == encoding_rs::WINDOWS_1250 {
+            Some(Encoding::Windows1250)
+        } else if encoding == encoding_rs::WINDOWS_1252 {
+            Some(Encoding::Windows1252)
+        } else {
+            None
+        }
+    }
+
+    /// Guess the encoding of `bytes`
+    ///
+    /// Valid UTF-8 is taken to be UTF-8. Otherwise each legacy encoding is
+    /// tried, and the one that gives the most Esperanto letters and the
+    /// fewest control and box-drawing characters wins.
+    ///
+    /// ```
+    /// use esperanto_text::Encoding;
+    ///
+    /// assert_eq!(Encoding::sniff("ĉu".as_bytes()), Encoding::Utf8);
+    /// assert_eq!(Encoding::sniff(b"\xe6u \xfeipo"), Encoding::Latin3);
+    /// assert_eq!(Encoding::sniff(b"\xe8iuj \x9aipoj"), Encoding::Windows1250);
+    /// ```
+    pub fn sniff(bytes: &[u8]) -> Encoding {
+        if std::str::from_utf8(bytes).is_ok() {
+            return Encoding::Utf8;
+        }
+        let mut best = (i64::MIN, Encoding::Utf8);
+        for &encoding in CANDIDATES {
+            let score = encoding.decode(bytes).chars().map(score).sum();
+            if score > best.0 {
+                best = (score, encoding);
+            }
+        }
+        best.1
+    }
+
+    /// Decode `bytes` to UTF-8
+    ///
+    /// Invalid UTF-8 and bytes that Latin-3 leaves undefined become U+FFFD.
+    /// The Windows code pages follow the WHATWG decoders, which read their
+    /// few undefined bytes as C1 control characters.
+    pub fn decode(self, bytes: &[u8]) -> String {
+        let encoding = match self {
+            Encoding::Utf8 => return String::from_utf8_lossy(bytes).into_owned(),
+            Encoding::Latin3 => return latin3_to_utf8(bytes),
+            Encoding::Windows1250 => encoding_rs::WINDOWS_1250,
+            Encoding::Windows1252 => encoding_rs::WINDOWS_1252,
+            Encoding::Ibm852 => {
+                return bytes
+                    .iter()
+                    .map(|&b| match b {
+                        0..=0x7F => char::from(b),
+                        _ => IBM_852[usize::from(b) - 0x80],
+                    })
+                    .collect()
+            }
+        };
+        encoding.decode_without_bom_handling(bytes).0.into_owned()
+    }
+}
+
+/// How much a decoded non-ASCII character suggests the encoding was right
+fn score(c: char) -> i64 {
+    let mut buf = [0; 4];
+    if c.is_ascii() {
+        0
+    } else if Letter::from_hatted(c).is_some() {
+        3
+    } else if CONFUSABLES
+        .iter()
+        .any(|(p, _)| *p == c.encode_utf8(&mut buf))
+    {
+        2
+    } else if c.is_control() || c == char::REPLACEMENT_CHARACTER || ('─'..='▟').contains(&c) {
+        -3
+    } else {
+        0
+    }
+}
+
+/// Decode a legacy file and convert it from one system to another in one
+/// call
+///
+/// `label` names the encoding, as in
+/// [`Encoding::for_label`](enum.Encoding.html#method.for_label). Without a
+/// label the encoding is guessed with
+/// [`Encoding::sniff`](enum.Encoding.html#method.sniff). Lookalike letters
+/// such as "č" for "ĉ", which were often used where an encoding had no hatted
+/// letters, are read as hatted letters.
+///
+/// ```
+/// use esperanto_text::{decode_and_convert, System};
+///
+/// let bytes = b"\xe8iuj \x9aipoj";
+/// assert_eq!(decode_and_convert(bytes, Some("cp1250"), System::Utf8, System::X).unwrap(), "cxiuj sxipoj");
+/// assert_eq!(decode_and_convert(bytes, None, System::Utf8, System::Utf8).unwrap(), "ĉiuj ŝipoj");
+/// assert!(decode_and_convert(bytes, Some("koi8-r"), System::Utf8, System::Utf8).is_err());
+/// ```
+pub fn decode_and_convert(
+    bytes: &[u8],
+    label: Option<&str>,
+    from: System,
+    to: System,
+) -> Result<String, Error> {
+    let encoding = match label {
+        Some(label) => {
+            Encoding::for_label(label).ok_or_else(|| Error::UnknownEncoding(label.to_owned()))?
+        }
+        None => Encoding::sniff(bytes),
+    };
+    let t = Pipeline::new()
+        .from(from)
+        .to(to)
+        .normalize_confusables(true)
+        .build();
+    Ok(t.convert(&encoding.decode(bytes)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_labels() {
+        assert_eq!(Encoding::for_label(" CP1250 "), Some(Encoding::Windows1250));
+        assert_eq!(Encoding::for_label("latin1"), Some(Encoding::Windows1252));
+        assert_eq!(Encoding::for_label("l3"), Some(Encoding::Latin3));
+        assert_eq!(Encoding::for_label("852"), Some(Encoding::Ibm852));
+        assert_eq!(Encoding::for_label("koi8-r"), None);
+    }
+
+    #[test]
+    fn test_sniff_code_page_852() {
+        // "ĉiuj ŝipoj" approximated as "čiuj šipoj" in code page 852
+        let bytes = b"\x9fiuj \xe7ipoj";
+        assert_eq!(Encoding::sniff(bytes), Encoding::Ibm852);
+        assert_eq!(Encoding::Ibm852.decode(bytes), "čiuj šipoj");
+        assert_eq!(Encoding::Windows1252.decode(b"\x80\xe9"), "€é");
+    }
+
+    #[test]
+    fn test_tables_match_code_pages() {
+        // Spot checks against the published mappings for the letters that
+        // most often stood in for the hatted ones
+        assert_eq!(
+            Encoding::Windows1250.decode(b"\x8a\x9a\xc8\xe8\xb9\x81"),
+            "ŠšČčą\u{81}"
+        );
+        assert_eq!(
+            Encoding::Windows1252.decode(b"\x8a\x9a\x9f\xa0\xff\x81"),
+            "ŠšŸ\u{A0}ÿ\u{81}"
+        );
+        assert_eq!(
+            Encoding::Ibm852.decode(b"\xe6\xe7\xac\x9f\xa6\xff"),
+            "ŠšČčŽ\u{A0}"
+        );
+    }
+}
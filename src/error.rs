@@ -0,0 +1,71 @@
+//! The error returned by everything in the crate that can fail
+
+use std::fmt;
+
+/// Why a conversion, an encoding or a [`Pipeline`](struct.Pipeline.html)
+/// setting failed
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Error {
+    /// The input isn't valid UTF-8
+    InvalidBytes {
+        /// The byte offset of the first invalid byte
+        position: usize,
+    },
+    /// The output didn't fit in the buffer it was to be written to
+    BufferOverflow {
+        /// The length in bytes the buffer would need
+        needed: usize,
+    },
+    /// The conversion was stopped by a `CancelToken`
+    Cancelled,
+    /// Options were chosen that don't work together, or have no effect
+    /// with the systems chosen
+    UnsupportedOptions(&'static str),
+    /// A character can't be written in Latin-3
+    Unencodable {
+        /// The character
+        character: char,
+        /// Its byte offset in the UTF-8 input
+        position: usize,
+    },
+    /// An encoding label that isn't recognised
+    UnknownEncoding(String),
+    /// A character given a mapping isn't a hatted letter
+    NotHatted(char),
+    /// A hatted letter was mapped to nothing, so it would be left out of the
+    /// output altogether
+    EmptyMapping(char),
+    /// The first letter would be written the same as the second, which may
+    /// be a letter without a hat, so the output couldn't be read back
+    AmbiguousMapping(char, char),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidBytes { position } => write!(f, "invalid UTF-8 at byte {}", position),
+            Error::BufferOverflow { needed } => {
+                write!(f, "output needs a buffer of {} bytes", needed)
+            }
+            Error::Cancelled => write!(f, "conversion was cancelled"),
+            Error::UnsupportedOptions(reason) => write!(f, "unsupported options: {}", reason),
+            Error::Unencodable {
+                character,
+                position,
+            } => write!(
+                f,
+                "{:?} at byte {} has no Latin-3 encoding",
+                character, position
+            ),
+            Error::UnknownEncoding(label) => write!(f, "unknown encoding: {}", label),
+            Error::NotHatted(c) => write!(f, "{:?} is not a hatted letter", c),
+            Error::EmptyMapping(c) => write!(f, "{:?} is mapped to nothing", c),
+            Error::AmbiguousMapping(c, other) => {
+                write!(f, "{:?} would be written the same as {:?}", c, other)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
@@ -0,0 +1,107 @@
+//! Measuring how accurately the h-system is read back
+
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+
+use crate::token::runs;
+use crate::{System, Transliterator};
+
+/// How well h-system text was read back, word by word
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EvalReport {
+    /// Words in the gold texts
+    pub words: usize,
+    /// Words that came back different
+    pub errors: usize,
+    /// Every distinct misreading, most frequent first
+    pub misread: Vec<Misreading>,
+}
+
+impl EvalReport {
+    /// The fraction of words that came back exactly as they were
+    pub fn accuracy(&self) -> f64 {
+        if self.words == 0 {
+            return 1.0;
+        }
+        (self.words - self.errors) as f64 / self.words as f64
+    }
+}
+
+/// A word that was read back wrong, and how often
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Misreading {
+    /// The word as it was written in the gold text
+    pub expected: String,
+    /// The word after converting to the h-system and back
+    pub actual: String,
+    /// How many times this happened
+    pub count: usize,
+}
+
+/// Convert correctly written UTF-8 texts to the h-system and back, and
+/// report how many words were read back wrong
+///
+/// This measures the same conversion as `h_system_to_utf8`, so it can be
+/// used to check that a change to the exception lists is an improvement.
+///
+/// ```
+/// use esperanto_text::evaluate_h_system;
+///
+/// let report = evaluate_h_system(&["Ĉu Ashley estas senchava?"]);
+/// assert_eq!(report.words, 4);
+/// assert_eq!(report.errors, 1);
+/// assert_eq!(report.misread[0].actual, "Aŝley");
+/// ```
+pub fn evaluate_h_system<I, S>(gold: I) -> EvalReport
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let forward = Transliterator::new(System::Utf8, System::H);
+    let back = Transliterator::new(System::H, System::Utf8);
+    let mut report = EvalReport::default();
+    let mut misread: BTreeMap<(String, String), usize> = BTreeMap::new();
+    for text in gold {
+        let text = text.as_ref();
+        let returned = back.convert(&forward.convert(text));
+        // Conversion never joins or splits words, so the runs line up
+        for ((expected, is_word), (actual, _)) in runs(text).zip(runs(&returned)) {
+            if !is_word {
+                continue;
+            }
+            report.words += 1;
+            if expected != actual {
+                report.errors += 1;
+                *misread
+                    .entry((expected.to_owned(), actual.to_owned()))
+                    .or_default() += 1;
+            }
+        }
+    }
+    report.misread = misread
+        .into_iter()
+        .map(|((expected, actual), count)| Misreading {
+            expected,
+            actual,
+            count,
+        })
+        .collect();
+    report.misread.sort_by_key(|m| Reverse(m.count));
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_most_frequent_first() {
+        let report = evaluate_h_system(["kakau kakau desha", "", "kakau"]);
+        assert_eq!(report.words, 4);
+        assert_eq!(report.errors, 4);
+        assert_eq!(report.misread[0].expected, "kakau");
+        assert_eq!(report.misread[0].count, 3);
+        assert_eq!(report.accuracy(), 0.0);
+        assert_eq!(evaluate_h_system(["ĉiuj"]).accuracy(), 1.0);
+    }
+}
@@ -0,0 +1,67 @@
+//! Reading extra exception fragments at runtime
+
+use std::io::{self, BufRead, BufReader, Read};
+
+use crate::decoder::Anchor;
+
+/// Read a list of exception fragments in the same format as `data/`
+///
+/// One fragment per line. Everything after a '#' is a comment, and a leading
+/// '^' or trailing '$' anchors the fragment to the start or end of a word.
+pub(crate) fn read_exceptions<R: Read>(reader: R) -> io::Result<Vec<(String, Anchor)>> {
+    let mut exceptions = Vec::new();
+    for (n, line) in BufReader::new(reader).lines().enumerate() {
+        let line = line?;
+        let entry = line.split('#').next().unwrap_or("").trim();
+        if entry.is_empty() {
+            continue;
+        }
+        match parse_exception(entry) {
+            Some(exception) => exceptions.push(exception),
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("line {}: not a single word fragment: {:?}", n + 1, entry),
+                ))
+            }
+        }
+    }
+    Ok(exceptions)
+}
+
+/// Split the anchors off a single entry, if it is a valid fragment
+pub(crate) fn parse_exception(entry: &str) -> Option<(String, Anchor)> {
+    let fragment = entry.trim_start_matches('^').trim_end_matches('$');
+    if fragment.is_empty() || fragment.contains(char::is_whitespace) {
+        return None;
+    }
+    let anchor = Anchor {
+        start: entry.starts_with('^'),
+        end: entry.ends_with('$'),
+    };
+    Some((fragment.to_owned(), anchor))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_exceptions() {
+        let text = "# Our list\nkuracherb\n^tux$   # the penguin\n\n";
+        let found = read_exceptions(text.as_bytes()).unwrap();
+        let fragments: Vec<&str> = found.iter().map(|(f, _)| f.as_str()).collect();
+        assert_eq!(fragments, vec!["kuracherb", "tux"]);
+        assert_eq!(
+            found[1].1,
+            Anchor {
+                start: true,
+                end: true
+            }
+        );
+
+        let err = read_exceptions("ok\ntwo words\n".as_bytes()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().starts_with("line 2:"));
+    }
+}
@@ -0,0 +1,92 @@
+//! Counting letters and words in a text
+
+use std::collections::BTreeMap;
+
+use crate::canonical;
+use crate::letter::Letter;
+use crate::token::runs;
+
+/// Letter and word counts for a text, as returned by
+/// [`text_stats`](fn.text_stats.html)
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TextStats {
+    /// How many times each letter appears, keyed by the lowercase UTF-8 letter
+    pub letters: BTreeMap<char, usize>,
+    /// How many times each word appears, keyed by the lowercase UTF-8 word
+    pub words: BTreeMap<String, usize>,
+    /// The number of letters, counting a digraph as one
+    pub total_letters: usize,
+    /// The number of words
+    pub total_words: usize,
+    /// The number of hatted letters, in any system
+    pub hatted_letters: usize,
+}
+
+impl TextStats {
+    /// The share of letters that are hatted, from 0.0 to 1.0
+    pub fn hatted_density(&self) -> f64 {
+        if self.total_letters == 0 {
+            return 0.0;
+        }
+        self.hatted_letters as f64 / self.total_letters as f64
+    }
+
+    /// The share of letters that are `letter`, ignoring case, from 0.0 to 1.0
+    pub fn letter_frequency(&self, letter: char) -> f64 {
+        let lower = letter.to_lowercase().next().unwrap_or(letter);
+        match self.letters.get(&lower) {
+            Some(n) => *n as f64 / self.total_letters as f64,
+            None => 0.0,
+        }
+    }
+}
+
+/// Count the letters and words of a text in any system
+///
+/// The text is read as UTF-8 first, so "cx", "ch" and "ĉ" all count as the
+/// letter "ĉ".
+///
+#[cfg_attr(all(feature = "x-system", feature = "h-system"), doc = "```")]
+#[cfg_attr(not(all(feature = "x-system", feature = "h-system")), doc = "```ignore")]
+/// let stats = esperanto_text::text_stats("Ĉu vi? Cxu li? Chu ŝi?");
+/// assert_eq!(stats.words["ĉu"], 3);
+/// assert_eq!(stats.letters[&'ĉ'], 3);
+/// assert_eq!(stats.total_letters, 12);
+/// assert_eq!(stats.hatted_letters, 4);
+/// ```
+pub fn text_stats(s: &str) -> TextStats {
+    let text: String = canonical::chars(s).flat_map(char::to_lowercase).collect();
+    let mut stats = TextStats::default();
+    for (run, word) in runs(&text) {
+        if !word {
+            continue;
+        }
+        let letters = run.chars().filter(|c| c.is_alphabetic());
+        for c in letters {
+            *stats.letters.entry(c).or_insert(0) += 1;
+            stats.total_letters += 1;
+            if Letter::from_hatted(c).is_some() {
+                stats.hatted_letters += 1;
+            }
+        }
+        *stats.words.entry(run.to_owned()).or_insert(0) += 1;
+        stats.total_words += 1;
+    }
+    stats
+}
+
+#[cfg(all(test, feature = "x-system"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_stats() {
+        let stats = text_stats("La hundo, la kato kaj la ĉevalo. Ankaŭ 3 sxafoj!");
+        assert_eq!(stats.total_words, 9);
+        assert_eq!(stats.words["la"], 3);
+        assert_eq!(stats.hatted_letters, 3);
+        assert!((stats.letter_frequency('A') - 9.0 / 34.0).abs() < 1e-9);
+        assert_eq!(stats.letter_frequency('q'), 0.0);
+        assert_eq!(text_stats("").hatted_density(), 0.0);
+    }
+}
@@ -0,0 +1,60 @@
+//! HTML character references for the hatted letters
+
+use crate::letter::Letter;
+
+/// The named reference for `letter` in lowercase, such as "&ccirc;"
+fn named(letter: Letter) -> String {
+    let accent = match letter {
+        Letter::U => "breve",
+        _ => "circ",
+    };
+    format!("&{}{};", letter.base(false), accent)
+}
+
+/// Every reference read as a hatted letter: decimal, hexadecimal and named
+///
+/// Names are only given in lowercase, since patterns match regardless of
+/// ASCII case.
+pub(crate) fn references() -> impl Iterator<Item = (String, Letter)> {
+    Letter::ALL.iter().flat_map(|&letter| {
+        let numeric = [false, true].iter().flat_map(move |&upper| {
+            let code = u32::from(letter.hatted(upper));
+            vec![format!("&#{};", code), format!("&#x{:x};", code)]
+        });
+        numeric.chain(Some(named(letter))).map(move |r| (r, letter))
+    })
+}
+
+/// Whether the numeric reference `found` is for a capital, or `None` if it
+/// isn't one
+///
+/// Hex digits may be in either case, so unlike a named reference such as
+/// "&Ccirc;" the case can't be read from the letters.
+pub(crate) fn numeric_is_upper(found: &str) -> Option<bool> {
+    let number = found.strip_prefix("&#")?.strip_suffix(';')?;
+    let code = match number.strip_prefix(|c| c == 'x' || c == 'X') {
+        Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+        None => number.parse().ok()?,
+    };
+    let (_, upper) = Letter::from_hatted(char::from_u32(code)?)?;
+    Some(upper)
+}
+
+/// Append the decimal reference for a hatted letter, such as "&#265;"
+pub(crate) fn push_reference(letter: Letter, upper: bool, dst: &mut String) {
+    dst.push_str(&format!("&#{};", u32::from(letter.hatted(upper))));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_numeric_is_upper() {
+        assert_eq!(numeric_is_upper("&#265;"), Some(false));
+        assert_eq!(numeric_is_upper("&#X11D;"), Some(false));
+        assert_eq!(numeric_is_upper("&#x11c;"), Some(true));
+        assert_eq!(numeric_is_upper("&Ccirc;"), None);
+        assert_eq!(references().count(), 30);
+    }
+}
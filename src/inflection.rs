@@ -0,0 +1,207 @@
+//! Adding and removing the plural and accusative endings
+
+use crate::canonical::Canonical;
+use crate::morpheme::{ending_len, is_root_sized};
+
+/// Correlatives that take the plural or accusative ending
+const CORRELATIVES: &[&str] = &[
+    "ia", "iu", "io", "ie", "kia", "kiu", "kio", "kie", "tia", "tiu", "tio", "tie", "ĉia", "ĉiu",
+    "ĉio", "ĉie", "nenia", "neniu", "nenio", "nenie",
+];
+
+/// Personal pronouns, which take the accusative ending but not the plural
+const PRONOUNS: &[&str] = &["mi", "ci", "vi", "li", "ŝi", "ĝi", "si", "ni", "ili", "oni"];
+
+/// A word read as UTF-8, remembering where each character came from
+struct Word<'a> {
+    source: &'a str,
+    /// The word in lowercase UTF-8
+    lower: String,
+    /// The byte offset in `source` of each character of `lower`
+    starts: Vec<usize>,
+    /// Whether every letter is a capital
+    upper: bool,
+}
+
+impl<'a> Word<'a> {
+    fn new(source: &'a str) -> Word<'a> {
+        let mut lower = String::with_capacity(source.len());
+        let mut starts = Vec::with_capacity(source.len());
+        let (mut letters, mut capitals) = (0, 0);
+        for (c, start, _) in Canonical::new(source) {
+            if c.is_alphabetic() {
+                letters += 1;
+                capitals += usize::from(c.is_uppercase());
+            }
+            lower.push(c.to_lowercase().next().unwrap_or(c));
+            starts.push(start);
+        }
+        Word {
+            source,
+            lower,
+            starts,
+            upper: letters > 1 && capitals == letters,
+        }
+    }
+
+    /// The byte offset in `source` of the character at byte `i` of `lower`
+    fn source_offset(&self, i: usize) -> usize {
+        let index = self.lower[..i].chars().count();
+        self.starts.get(index).copied().unwrap_or(self.source.len())
+    }
+
+    /// The length in bytes of `lower` without a final apostrophe, if the
+    /// word is a noun with its ending cut short, like "dom'"
+    fn elided(&self) -> Option<usize> {
+        let stem = self.lower.strip_suffix(['\'', '’'])?;
+        Some(stem.len()).filter(|_| is_root_sized(stem))
+    }
+
+    /// The length of the word in `lower` without any plural or accusative
+    /// ending, and whether each is there
+    fn declension(&self) -> Option<(usize, bool, bool)> {
+        let lower = self.lower.as_str();
+        [
+            ("jn", true, true),
+            ("n", false, true),
+            ("j", true, false),
+            ("", false, false),
+        ]
+        .iter()
+        .find_map(|&(suffix, plural, accusative)| {
+            let base = lower.strip_suffix(suffix)?;
+            Some((base.len(), plural, accusative)).filter(|_| declinable(base, plural))
+        })
+    }
+
+    /// `source` with `text` in place of the part read as `lower[start..end]`
+    fn splice(&self, start: usize, end: usize, text: &str) -> String {
+        let (start, end) = (self.source_offset(start), self.source_offset(end));
+        let mut out = String::with_capacity(self.source.len() + text.len());
+        out.push_str(&self.source[..start]);
+        if self.upper {
+            out.push_str(&text.to_uppercase());
+        } else {
+            out.push_str(text);
+        }
+        out.push_str(&self.source[end..]);
+        out
+    }
+}
+
+/// Whether a word without its plural and accusative endings can take them
+fn declinable(base: &str, plural: bool) -> bool {
+    if CORRELATIVES.contains(&base) {
+        return !plural || base.ends_with(['a', 'u']);
+    }
+    if PRONOUNS.contains(&base) {
+        return !plural;
+    }
+    let stem_sized = |b: &str| is_root_sized(&b[..b.len() - 1]);
+    match base.chars().last() {
+        Some('o') | Some('a') => stem_sized(base),
+        Some('e') => !plural && stem_sized(base),
+        _ => false,
+    }
+}
+
+/// Give a noun, adjective or correlative the plural ending "j"
+///
+/// The word may be written in any system and keeps it. An accusative "n"
+/// stays at the end, and a noun with its ending cut short, like "dom'", gets
+/// its full ending back. Words that can't be made plural, or already are,
+/// are returned as they are.
+///
+#[cfg_attr(feature = "h-system", doc = "```")]
+#[cfg_attr(not(feature = "h-system"), doc = "```ignore")]
+/// use esperanto_text::pluralize;
+///
+/// assert_eq!(pluralize("ŝipo"), "ŝipoj");
+/// assert_eq!(pluralize("sxipon"), "sxipojn");
+/// assert_eq!(pluralize("Chiu"), "Chiuj");
+/// assert_eq!(pluralize("dom'"), "domoj");
+/// assert_eq!(pluralize("kaj"), "kaj");
+/// ```
+pub fn pluralize(word: &str) -> String {
+    let w = Word::new(word);
+    if let Some(stem) = w.elided() {
+        return w.splice(stem, w.lower.len(), "oj");
+    }
+    match w.declension() {
+        Some((base, false, _)) if declinable(&w.lower[..base], true) => w.splice(base, base, "j"),
+        _ => word.to_owned(),
+    }
+}
+
+/// Give a noun, adjective, pronoun, correlative or adverb the accusative
+/// ending "n"
+///
+/// The word may be written in any system and keeps it. A noun with its
+/// ending cut short, like "dom'", gets its full ending back. Words that
+/// can't take the accusative, or already have it, are returned as they are.
+///
+/// ```
+/// use esperanto_text::add_accusative;
+///
+/// assert_eq!(add_accusative("ŝipoj"), "ŝipojn");
+/// assert_eq!(add_accusative("MI"), "MIN");
+/// assert_eq!(add_accusative("hejme"), "hejmen");
+/// assert_eq!(add_accusative("dom'"), "domon");
+/// assert_eq!(add_accusative("domon"), "domon");
+/// ```
+pub fn add_accusative(word: &str) -> String {
+    let w = Word::new(word);
+    if let Some(stem) = w.elided() {
+        return w.splice(stem, w.lower.len(), "on");
+    }
+    match w.declension() {
+        Some((_, _, false)) => w.splice(w.lower.len(), w.lower.len(), "n"),
+        _ => word.to_owned(),
+    }
+}
+
+/// Remove the grammatical ending from a word in any system
+///
+/// Unlike [`stem`](fn.stem.html), this reads the word in any system, so a
+/// root ending in a hatted letter loses only the ending. A final apostrophe
+/// standing for an ending is removed too.
+///
+/// ```
+/// use esperanto_text::strip_ending;
+///
+/// assert_eq!(strip_ending("domojn"), "dom");
+/// assert_eq!(strip_ending("cxehxoj"), "cxehx");
+/// assert_eq!(strip_ending("ghuas"), "ghu");
+/// assert_eq!(strip_ending("dom'"), "dom");
+/// ```
+pub fn strip_ending(word: &str) -> &str {
+    let w = Word::new(word);
+    let end = match w.elided() {
+        Some(stem) => stem,
+        None => w.lower.len() - ending_len(&w.lower),
+    };
+    &word[..w.source_offset(end)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inflection() {
+        for (word, plural, accusative) in &[
+            ("bona", "bonaj", "bonan"),
+            ("BONAJ", "BONAJ", "BONAJN"),
+            ("ĉambron", "ĉambrojn", "ĉambron"),
+            ("c^ambro", "c^ambroj", "c^ambron"),
+            ("tio", "tio", "tion"),
+            ("kie", "kie", "kien"),
+            ("la", "la", "la"),
+            ("l'", "l'", "l'"),
+            ("venu", "venu", "venu"),
+        ] {
+            assert_eq!(pluralize(word), *plural);
+            assert_eq!(add_accusative(word), *accusative);
+        }
+    }
+}
@@ -0,0 +1,79 @@
+//! Transcribing Esperanto into the International Phonetic Alphabet
+
+use crate::syllable::syllable_starts;
+use crate::token::runs;
+
+/// The sound of each lowercase letter
+const SOUNDS: &[(char, &str)] = &[
+    ('a', "a"), ('b', "b"), ('c', "t͡s"), ('ĉ', "t͡ʃ"), ('d', "d"), ('e', "e"), ('f', "f"),
+    ('g', "ɡ"), ('ĝ', "d͡ʒ"), ('h', "h"), ('ĥ', "x"), ('i', "i"), ('j', "j"), ('ĵ', "ʒ"),
+    ('k', "k"), ('l', "l"), ('m', "m"), ('n', "n"), ('o', "o"), ('p', "p"), ('r', "r"),
+    ('s', "s"), ('ŝ', "ʃ"), ('t', "t"), ('u', "u"), ('ŭ', "u̯"), ('v', "v"), ('z', "z"),
+];
+
+/// Transcribe UTF-8 Esperanto into IPA
+///
+/// Syllables are separated by "." and the stressed one, normally the second
+/// to last, is marked with "ˈ". A word cut short with an apostrophe, as in
+/// "dom'", keeps its stress on what is now the last syllable. Words of one
+/// syllable aren't marked. Anything that isn't an Esperanto letter is kept
+/// as it is.
+///
+/// ```
+/// use esperanto_text::utf8_to_ipa;
+///
+/// assert_eq!(utf8_to_ipa("Ĉu vi parolas Esperanton?"), "t͡ʃu vi paˈro.las es.peˈran.ton?");
+/// assert_eq!(utf8_to_ipa("la ĉiel'"), "la t͡ʃiˈel'");
+/// ```
+pub fn utf8_to_ipa(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() * 2);
+    let mut runs = runs(s).peekable();
+    while let Some((run, word)) = runs.next() {
+        if word {
+            let elided = runs
+                .peek()
+                .is_some_and(|(next, _)| next.starts_with(['\'', '’']));
+            transcribe_word(run, elided, &mut out);
+        } else {
+            out.push_str(run);
+        }
+    }
+    out
+}
+
+/// Append the transcription of one word
+fn transcribe_word(word: &str, elided: bool, dst: &mut String) {
+    let letters: Vec<char> = word.chars().flat_map(char::to_lowercase).collect();
+    let starts = syllable_starts(&letters);
+    let stressed = match starts.len() {
+        1 => None,
+        n if elided => Some(n - 1),
+        n => Some(n - 2),
+    };
+    for (i, c) in letters.iter().enumerate() {
+        if let Some(syllable) = starts.iter().position(|s| *s == i) {
+            if Some(syllable) == stressed {
+                dst.push('ˈ');
+            } else if syllable > 0 {
+                dst.push('.');
+            }
+        }
+        match SOUNDS.iter().find(|(l, _)| l == c) {
+            Some((_, sound)) => dst.push_str(sound),
+            None => dst.push(*c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_utf8_to_ipa() {
+        assert_eq!(utf8_to_ipa("ĝis"), "d͡ʒis");
+        assert_eq!(utf8_to_ipa("Aŭtobuso"), "au̯.toˈbu.so");
+        assert_eq!(utf8_to_ipa("ĥoro, ĵaŭdo"), "ˈxo.ro, ˈʒau̯.do");
+        assert_eq!(utf8_to_ipa("ekstra 12"), "ˈeks.tra 12");
+    }
+}
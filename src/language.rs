@@ -0,0 +1,97 @@
+//! Guessing whether text is written in Esperanto at all
+
+use crate::canonical;
+use crate::token::{tokens, TokenKind};
+
+/// Common words that are distinctive to Esperanto, in UTF-8
+const FUNCTION_WORDS: &[&str] = &[
+    "ankaŭ", "antaŭ", "aŭ", "ĉar", "ĉe", "ĉi", "ĉiuj", "ĉu", "dum", "estas", "estis", "ĝi", "ĝis",
+    "havas", "ili", "jam", "kaj", "ke", "kiel", "kio", "kiu", "kiuj", "kun", "lia", "nia", "nur",
+    "oni", "per", "plej", "pli", "povas", "pri", "sed", "ŝi", "tiel", "tio", "tiu", "tre", "via",
+];
+
+/// Grammatical endings, which nearly every longer Esperanto word has
+const ENDINGS: &[&str] = &["o", "a", "e", "i", "u", "as", "is", "os", "us", "j", "n"];
+
+/// Letter pairs that are rare in Esperanto but common in other languages
+const FOREIGN_PAIRS: &[&str] = &["ck", "ee", "oo", "ou", "ph", "qu", "th", "wh"];
+
+/// Estimate how likely it is that `s` is Esperanto, from 0.0 to 1.0
+///
+/// This is a cheap heuristic for filtering text before converting it, and
+/// works for any system. It looks at how many words are distinctive
+/// Esperanto function words, how many longer words have a grammatical
+/// ending, and how often letters and pairs of letters that Esperanto doesn't
+/// use appear. Short texts give less reliable scores.
+///
+#[cfg_attr(feature = "x-system", doc = "```")]
+#[cfg_attr(not(feature = "x-system"), doc = "```ignore")]
+/// use esperanto_text::esperanto_likelihood;
+///
+/// assert!(esperanto_likelihood("Cxu vi scias, kie estas la stacidomo?") > 0.7);
+/// assert!(esperanto_likelihood("Do you know where the station is?") < 0.3);
+/// ```
+pub fn esperanto_likelihood(s: &str) -> f32 {
+    let mut words = 0;
+    let mut function_words = 0;
+    let mut long_words = 0;
+    let mut endings = 0;
+    let mut letters = 0;
+    let mut foreign = 0;
+    for token in tokens(s).filter(|t| t.kind == TokenKind::Word) {
+        let raw = token.text.to_lowercase();
+        let word: String = canonical::chars(&raw).collect();
+        words += 1;
+        if FUNCTION_WORDS.contains(&word.as_str()) {
+            function_words += 1;
+        }
+        if word.chars().count() >= 3 {
+            long_words += 1;
+            if ENDINGS.iter().any(|e| word.ends_with(e)) {
+                endings += 1;
+            }
+        }
+        letters += word.chars().count();
+        foreign += word
+            .chars()
+            .filter(|c| {
+                matches!(c, 'q' | 'w' | 'x' | 'y')
+                    || !c.is_ascii() && !"ĉĝĥĵŝŭ\u{302}\u{306}".contains(*c)
+            })
+            .count();
+        foreign += FOREIGN_PAIRS
+            .iter()
+            .map(|p| raw.matches(p).count())
+            .sum::<usize>();
+    }
+    if words == 0 {
+        return 0.0;
+    }
+    let function_score = (function_words as f32 / words as f32 / 0.2).min(1.0);
+    let ending_score = if long_words == 0 {
+        function_score
+    } else {
+        ((endings as f32 / long_words as f32 - 0.5) / 0.4).clamp(0.0, 1.0)
+    };
+    let penalty = (foreign as f32 / letters as f32 * 20.0).min(1.0);
+    (0.5 * function_score + 0.5 * ending_score - penalty).clamp(0.0, 1.0)
+}
+
+#[cfg(all(test, feature = "x-system", feature = "h-system", feature = "from-utf8"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_esperanto_likelihood() {
+        let esperanto = "La lingvo Esperanto estas planlingvo, kiun kreis Zamenhof. \
+                         Ĝi estas facile lernebla kaj ankaŭ tre regula.";
+        assert!(esperanto_likelihood(esperanto) > 0.8);
+        assert!(esperanto_likelihood(&crate::utf8_to_h_system(esperanto)) > 0.8);
+        assert!(esperanto_likelihood(&crate::utf8_to_x_system(esperanto)) > 0.8);
+        let english = "The quick brown fox jumps over the lazy dog, which was sleeping.";
+        assert!(esperanto_likelihood(english) < 0.2);
+        let spanish = "El perro de mi hermano come la comida en la cocina pequeña.";
+        assert!(esperanto_likelihood(spanish) < 0.5);
+        assert_eq!(esperanto_likelihood("123 !"), 0.0);
+    }
+}
@@ -0,0 +1,144 @@
+//! Reading and writing ISO 8859-3 (Latin-3), the legacy Esperanto encoding
+
+use std::convert::TryFrom;
+
+use crate::error::Error;
+use crate::letter::Letter;
+
+/// Bytes from 0xA0 up whose character differs from Latin-1
+const DIFFERENCES: &[(u8, char)] = &[
+    (0xA1, 'Ħ'),
+    (0xA2, '˘'),
+    (0xA6, 'Ĥ'),
+    (0xA9, 'İ'),
+    (0xAA, 'Ş'),
+    (0xAB, 'Ğ'),
+    (0xAC, 'Ĵ'),
+    (0xAF, 'Ż'),
+    (0xB1, 'ħ'),
+    (0xB6, 'ĥ'),
+    (0xB9, 'ı'),
+    (0xBA, 'ş'),
+    (0xBB, 'ğ'),
+    (0xBC, 'ĵ'),
+    (0xBF, 'ż'),
+    (0xC5, 'Ċ'),
+    (0xC6, 'Ĉ'),
+    (0xD5, 'Ġ'),
+    (0xD8, 'Ĝ'),
+    (0xDD, 'Ŭ'),
+    (0xDE, 'Ŝ'),
+    (0xE5, 'ċ'),
+    (0xE6, 'ĉ'),
+    (0xF5, 'ġ'),
+    (0xF8, 'ĝ'),
+    (0xFD, 'ŭ'),
+    (0xFE, 'ŝ'),
+    (0xFF, '˙'),
+];
+
+/// Bytes that Latin-3 leaves unassigned
+const UNASSIGNED: &[u8] = &[0xA5, 0xAE, 0xBE, 0xC3, 0xD0, 0xE3, 0xF0];
+
+/// Decode Latin-3 bytes, such as a pre-Unicode Esperanto text file, to UTF-8
+///
+/// The few byte values that Latin-3 leaves unassigned become U+FFFD.
+///
+/// ```
+/// use esperanto_text::latin3_to_utf8;
+///
+/// assert_eq!(latin3_to_utf8(b"\xe6iu\xbca\xfdde"), "ĉiuĵaŭde");
+/// ```
+pub fn latin3_to_utf8(bytes: &[u8]) -> String {
+    bytes.iter().map(|&b| decode(b)).collect()
+}
+
+/// Encode UTF-8 text as Latin-3
+///
+/// Hatted letters written with a combining accent are composed first. Any
+/// other character that Latin-3 has no byte for is an error.
+///
+/// ```
+/// use esperanto_text::{latin3_to_utf8, utf8_to_latin3};
+///
+/// let bytes = utf8_to_latin3("Ĉiuĵaŭde").unwrap();
+/// assert_eq!(bytes, b"\xc6iu\xbca\xfdde");
+/// assert_eq!(latin3_to_utf8(&bytes), "Ĉiuĵaŭde");
+/// assert!(utf8_to_latin3("ĉu €?").is_err());
+/// ```
+pub fn utf8_to_latin3(s: &str) -> Result<Vec<u8>, Error> {
+    let mut out = Vec::with_capacity(s.len());
+    let mut chars = s.char_indices().peekable();
+    while let Some((position, c)) = chars.next() {
+        let composed = Letter::from_base(c)
+            .filter(|l| chars.peek().is_some_and(|(_, next)| *next == l.combining()))
+            .map(|l| l.hatted(c.is_uppercase()));
+        if composed.is_some() {
+            chars.next();
+        }
+        let c = composed.unwrap_or(c);
+        match encode(c) {
+            Some(b) => out.push(b),
+            None => {
+                return Err(Error::Unencodable {
+                    character: c,
+                    position,
+                })
+            }
+        }
+    }
+    Ok(out)
+}
+
+fn decode(b: u8) -> char {
+    if let Some(&(_, c)) = DIFFERENCES.iter().find(|(d, _)| *d == b) {
+        c
+    } else if UNASSIGNED.contains(&b) {
+        char::REPLACEMENT_CHARACTER
+    } else {
+        char::from(b)
+    }
+}
+
+fn encode(c: char) -> Option<u8> {
+    if let Some(&(b, _)) = DIFFERENCES.iter().find(|(_, d)| *d == c) {
+        return Some(b);
+    }
+    let b = u8::try_from(c).ok()?;
+    let replaced = b >= 0xA0 && DIFFERENCES.iter().any(|(d, _)| *d == b);
+    if replaced || UNASSIGNED.contains(&b) {
+        None
+    } else {
+        Some(b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_byte_round_trips() {
+        for b in 0..=255u8 {
+            let c = decode(b);
+            if c == char::REPLACEMENT_CHARACTER {
+                assert!(encode(c).is_none());
+            } else {
+                assert_eq!(encode(c), Some(b), "{:#x}", b);
+            }
+        }
+    }
+
+    #[test]
+    fn test_decomposed_and_unencodable() {
+        assert_eq!(utf8_to_latin3("S\u{302}i").unwrap(), b"\xdei");
+        assert_eq!(
+            utf8_to_latin3("aŭ ñ ĉ ł"),
+            Err(Error::Unencodable {
+                character: 'ł',
+                position: 10
+            })
+        );
+        assert!(utf8_to_latin3("\u{a5}").is_err());
+    }
+}
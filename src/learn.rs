@@ -0,0 +1,102 @@
+//! Proposing new h-system exceptions from correctly written text
+
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+
+use crate::roundtrip::verify_roundtrip_with;
+use crate::{System, Transliterator};
+
+/// Grammatical endings to leave off a proposed fragment, longest first
+const ENDINGS: &[&str] = &[
+    "ojn", "ajn", "oj", "aj", "on", "an", "en", "as", "is", "os", "us", "o", "a", "e", "i", "u",
+];
+
+/// The h-system spellings that can be misread as hatted letters
+const DIGRAPHS: &[&str] = &["ch", "gh", "hh", "jh", "sh", "au"];
+
+/// An exception fragment that would have prevented some round-trip errors
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Suggestion {
+    /// The fragment, lowercase and ready to add to `data/h-exceptions.txt`
+    /// or `data/au-exceptions.txt`
+    pub fragment: String,
+    /// How many misread words it would have fixed
+    pub count: usize,
+    /// One of those words, as written in the original text
+    pub example: String,
+}
+
+/// Find words that are misread after converting `gold` texts to the h-system
+/// and back, and propose exception fragments that would fix them
+///
+/// The texts must be correctly written UTF-8. Only words that are misread
+/// because they contain "ch", "au" and so on that aren't hatted letters can
+/// be fixed this way; words that also contain hatted letters are passed
+/// over, since an exception would leave those letters unconverted too. The
+/// most useful suggestions come first.
+///
+/// ```
+/// use esperanto_text::suggest_exceptions;
+///
+/// let found = suggest_exceptions(&["La flughaveno kaj Ashley.", "Ĉu Ashley venos?"]);
+/// assert_eq!(found[0].fragment, "ashley");
+/// assert_eq!(found[0].count, 2);
+/// ```
+pub fn suggest_exceptions<I, S>(gold: I) -> Vec<Suggestion>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let forward = Transliterator::new(System::Utf8, System::H);
+    let back = Transliterator::new(System::H, System::Utf8);
+    let mut found: BTreeMap<String, (usize, String)> = BTreeMap::new();
+    for text in gold {
+        let text = text.as_ref();
+        for range in verify_roundtrip_with(text, &forward, &back).divergent {
+            let word = &text[range];
+            if let Some(fragment) = fragment_for(word) {
+                let entry = found
+                    .entry(fragment)
+                    .or_insert_with(|| (0, word.to_owned()));
+                entry.0 += 1;
+            }
+        }
+    }
+    let mut suggestions: Vec<Suggestion> = found
+        .into_iter()
+        .map(|(fragment, (count, example))| Suggestion {
+            fragment,
+            count,
+            example,
+        })
+        .collect();
+    suggestions.sort_by_key(|s| Reverse(s.count));
+    suggestions
+}
+
+/// The fragment that would keep `word` as it is, if one can
+fn fragment_for(word: &str) -> Option<String> {
+    let word = word.to_lowercase();
+    if !word.is_ascii() || !DIGRAPHS.iter().any(|d| word.contains(d)) {
+        return None;
+    }
+    let stem = ENDINGS
+        .iter()
+        .find_map(|e| word.strip_suffix(e))
+        .filter(|stem| DIGRAPHS.iter().any(|d| stem.contains(d)))
+        .unwrap_or(&word);
+    Some(stem.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fragment_for() {
+        assert_eq!(fragment_for("Dishavigojn"), Some("dishavig".to_owned()));
+        assert_eq!(fragment_for("kakau"), Some("kakau".to_owned()));
+        assert_eq!(fragment_for("ŝtupo"), None);
+        assert_eq!(fragment_for("ŝanĝhavo"), None);
+    }
+}
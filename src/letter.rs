@@ -0,0 +1,248 @@
+//! The six letters of the Esperanto alphabet that carry a diacritic
+
+use std::ops::{BitOr, BitOrAssign, Not};
+
+/// A letter that is written with a circumflex (or breve, for ŭ)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum Letter {
+    C,
+    G,
+    H,
+    J,
+    S,
+    U,
+}
+
+impl Letter {
+    /// Every letter, in alphabetical order
+    pub(crate) const ALL: [Letter; 6] = [
+        Letter::C,
+        Letter::G,
+        Letter::H,
+        Letter::J,
+        Letter::S,
+        Letter::U,
+    ];
+
+    /// Identify the letter from its ASCII base, e.g. 'c' or 'C' for ĉ
+    pub(crate) fn from_base(c: char) -> Option<Letter> {
+        match c.to_ascii_lowercase() {
+            'c' => Some(Letter::C),
+            'g' => Some(Letter::G),
+            'h' => Some(Letter::H),
+            'j' => Some(Letter::J),
+            's' => Some(Letter::S),
+            'u' => Some(Letter::U),
+            _ => None,
+        }
+    }
+
+    /// Identify the letter and its case from the precomposed UTF-8 character
+    pub(crate) fn from_hatted(c: char) -> Option<(Letter, bool)> {
+        match c {
+            'ĉ' => Some((Letter::C, false)),
+            'ĝ' => Some((Letter::G, false)),
+            'ĥ' => Some((Letter::H, false)),
+            'ĵ' => Some((Letter::J, false)),
+            'ŝ' => Some((Letter::S, false)),
+            'ŭ' => Some((Letter::U, false)),
+            'Ĉ' => Some((Letter::C, true)),
+            'Ĝ' => Some((Letter::G, true)),
+            'Ĥ' => Some((Letter::H, true)),
+            'Ĵ' => Some((Letter::J, true)),
+            'Ŝ' => Some((Letter::S, true)),
+            'Ŭ' => Some((Letter::U, true)),
+            _ => None,
+        }
+    }
+
+    /// The letter without its diacritic
+    pub(crate) fn base(self, upper: bool) -> char {
+        let c = match self {
+            Letter::C => 'c',
+            Letter::G => 'g',
+            Letter::H => 'h',
+            Letter::J => 'j',
+            Letter::S => 's',
+            Letter::U => 'u',
+        };
+        if upper {
+            c.to_ascii_uppercase()
+        } else {
+            c
+        }
+    }
+
+    /// The combining diacritic that follows the base letter in decomposed form
+    pub(crate) fn combining(self) -> char {
+        self.diacritic().combining()
+    }
+
+    /// The diacritic the letter carries
+    pub(crate) fn diacritic(self) -> Diacritic {
+        match self {
+            Letter::U => Diacritic::Breve,
+            _ => Diacritic::Circumflex,
+        }
+    }
+
+    /// The precomposed UTF-8 character for the letter
+    pub(crate) fn hatted(self, upper: bool) -> char {
+        match (self, upper) {
+            (Letter::C, false) => 'ĉ',
+            (Letter::G, false) => 'ĝ',
+            (Letter::H, false) => 'ĥ',
+            (Letter::J, false) => 'ĵ',
+            (Letter::S, false) => 'ŝ',
+            (Letter::U, false) => 'ŭ',
+            (Letter::C, true) => 'Ĉ',
+            (Letter::G, true) => 'Ĝ',
+            (Letter::H, true) => 'Ĥ',
+            (Letter::J, true) => 'Ĵ',
+            (Letter::S, true) => 'Ŝ',
+            (Letter::U, true) => 'Ŭ',
+        }
+    }
+}
+
+/// A set of the six hatted letters, for choosing which ones to convert
+///
+/// Sets are combined with `|`, and `!` gives the letters not in a set.
+///
+/// ```
+/// use esperanto_text::LetterSet;
+///
+/// let set = LetterSet::C | LetterSet::S;
+/// assert!(set.contains(LetterSet::C));
+/// assert!(!set.contains(LetterSet::U));
+/// assert_eq!(!LetterSet::U, LetterSet::CIRCUMFLEX);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct LetterSet(u8);
+
+impl LetterSet {
+    /// No letters
+    pub const NONE: LetterSet = LetterSet(0);
+    /// "ĉ"
+    pub const C: LetterSet = LetterSet(1);
+    /// "ĝ"
+    pub const G: LetterSet = LetterSet(1 << 1);
+    /// "ĥ"
+    pub const H: LetterSet = LetterSet(1 << 2);
+    /// "ĵ"
+    pub const J: LetterSet = LetterSet(1 << 3);
+    /// "ŝ"
+    pub const S: LetterSet = LetterSet(1 << 4);
+    /// "ŭ"
+    pub const U: LetterSet = LetterSet(1 << 5);
+    /// The letters with a circumflex, which is all but "ŭ"
+    pub const CIRCUMFLEX: LetterSet = LetterSet(0x1F);
+    /// All six letters
+    pub const ALL: LetterSet = LetterSet(0x3F);
+
+    /// Whether every letter in `other` is in this set
+    pub fn contains(self, other: LetterSet) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Whether this set has no letters
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Whether `letter` is in this set
+    pub(crate) fn has(self, letter: Letter) -> bool {
+        self.contains(LetterSet::from(letter))
+    }
+}
+
+impl Default for LetterSet {
+    fn default() -> LetterSet {
+        LetterSet::ALL
+    }
+}
+
+impl From<Letter> for LetterSet {
+    fn from(letter: Letter) -> LetterSet {
+        match letter {
+            Letter::C => LetterSet::C,
+            Letter::G => LetterSet::G,
+            Letter::H => LetterSet::H,
+            Letter::J => LetterSet::J,
+            Letter::S => LetterSet::S,
+            Letter::U => LetterSet::U,
+        }
+    }
+}
+
+impl BitOr for LetterSet {
+    type Output = LetterSet;
+
+    fn bitor(self, other: LetterSet) -> LetterSet {
+        LetterSet(self.0 | other.0)
+    }
+}
+
+impl BitOrAssign for LetterSet {
+    fn bitor_assign(&mut self, other: LetterSet) {
+        self.0 |= other.0;
+    }
+}
+
+impl Not for LetterSet {
+    type Output = LetterSet;
+
+    fn not(self) -> LetterSet {
+        LetterSet(!self.0 & LetterSet::ALL.0)
+    }
+}
+
+/// The diacritic an Esperanto letter can carry
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Diacritic {
+    /// The circumflex of "ĉ", "ĝ", "ĥ", "ĵ" and "ŝ"
+    Circumflex,
+    /// The breve of "ŭ"
+    Breve,
+}
+
+impl Diacritic {
+    /// The combining character for the diacritic, U+0302 or U+0306
+    pub fn combining(self) -> char {
+        match self {
+            Diacritic::Circumflex => '\u{302}',
+            Diacritic::Breve => '\u{306}',
+        }
+    }
+}
+
+/// The hatted form of an ASCII letter, keeping its case
+///
+/// Returns `None` for anything but "c", "g", "h", "j", "s" and "u".
+///
+/// ```
+/// use esperanto_text::hatted;
+///
+/// assert_eq!(hatted('c'), Some('ĉ'));
+/// assert_eq!(hatted('U'), Some('Ŭ'));
+/// assert_eq!(hatted('x'), None);
+/// ```
+pub fn hatted(c: char) -> Option<char> {
+    Letter::from_base(c).map(|letter| letter.hatted(c.is_ascii_uppercase()))
+}
+
+/// The ASCII base of a hatted letter, keeping its case, and the diacritic
+/// it had
+///
+/// Returns `None` for anything but the twelve precomposed hatted letters.
+///
+/// ```
+/// use esperanto_text::{unhatted, Diacritic};
+///
+/// assert_eq!(unhatted('Ĝ'), Some(('G', Diacritic::Circumflex)));
+/// assert_eq!(unhatted('ŭ'), Some(('u', Diacritic::Breve)));
+/// assert_eq!(unhatted('g'), None);
+/// ```
+pub fn unhatted(c: char) -> Option<(char, Diacritic)> {
+    Letter::from_hatted(c).map(|(letter, upper)| (letter.base(upper), letter.diacritic()))
+}
@@ -0,0 +1,80 @@
+//! Deciding whether an h-system digraph or "au" is really part of a word
+
+use std::fmt;
+use std::sync::Arc;
+
+/// A source of knowledge about which words are real, used to decide when an
+/// h-system digraph or "au" should be left alone
+///
+/// When reading the h-system, each word containing "ch", "gh", "hh", "jh",
+/// "sh" or "au" is passed to the lexicon exactly as written, in lowercase. If
+/// the lexicon recognises it, the word is left unchanged, so "senchava" stays
+/// as it is rather than becoming "senĉava".
+///
+/// Set one with [`Pipeline::lexicon`](struct.Pipeline.html#method.lexicon).
+/// It replaces the built-in list, which is available as
+/// [`BuiltinLexicon`](struct.BuiltinLexicon.html) to fall back on. Compounds
+/// joined at "au", such as "praulo", are recognised whatever the lexicon.
+///
+/// ```
+/// use esperanto_text::{BuiltinLexicon, Lexicon, Pipeline, System};
+///
+/// struct Names;
+///
+/// impl Lexicon for Names {
+///     fn contains_root(&self, word: &str) -> bool {
+///         word == "sasha" || BuiltinLexicon.contains_root(word)
+///     }
+/// }
+///
+/// let t = Pipeline::new().from(System::H).lexicon(Names).build();
+/// assert_eq!(t.convert("Sasha kaj senchava shipo"), "Sasha kaj senchava ŝipo");
+/// ```
+pub trait Lexicon: Send + Sync {
+    /// Whether `word`, spelled exactly as given, is a known word or is made
+    /// up of known roots
+    fn contains_root(&self, word: &str) -> bool;
+}
+
+/// The list of word fragments that the h-system converters use by default
+///
+/// A word is recognised if it contains one of the fragments listed in
+/// `data/h-exceptions.txt` or `data/au-exceptions.txt`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct BuiltinLexicon;
+
+impl Lexicon for BuiltinLexicon {
+    fn contains_root(&self, word: &str) -> bool {
+        crate::FROM_H_CI.iter().filter(|f| f.len() > 2).any(|f| {
+            match crate::EXCEPTION_ANCHORS.iter().find(|(a, _, _)| a == f) {
+                Some((_, true, true)) => word == *f,
+                Some((_, true, false)) => word.starts_with(f),
+                Some((_, false, true)) => word.ends_with(f),
+                _ => word.contains(f),
+            }
+        })
+    }
+}
+
+/// A lexicon shared between a `Pipeline` and the converters built from it
+#[derive(Clone)]
+pub(crate) struct SharedLexicon(pub(crate) Arc<dyn Lexicon>);
+
+impl fmt::Debug for SharedLexicon {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SharedLexicon")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_lexicon() {
+        assert!(BuiltinLexicon.contains_root("senchava"));
+        assert!(BuiltinLexicon.contains_root("saudaarabujo"));
+        assert!(!BuiltinLexicon.contains_root("chiuj"));
+        assert!(!BuiltinLexicon.contains_root("ch"));
+    }
+}
@@ -13,9 +13,38 @@ is used to avoid changing the meaning of real words.
 
 A binary called `eotext` is included to use these functions from a CLI.
 
+For method-call style conversions, import the [`prelude`](prelude/index.html)
+to get the [`EsperantoExt`](prelude/trait.EsperantoExt.html) trait on `str`.
+The tables of patterns the converters match are in
+[`patterns`](patterns/index.html). Where doing the best with bad input or
+mismatched options isn't wanted, the `try_` functions and
+[`Pipeline::try_build`](struct.Pipeline.html#method.try_build) return an
+[`Error`](enum.Error.html) instead.
+
+# Cargo features
+
+All of these are enabled by default. Builds that only need some directions,
+such as a WASM widget that only reads the x-system, can turn the rest off with
+`default-features = false` to leave out their code and tables.
+
+- `x-system`: the x-system and its patterns
+- `h-system`: the h-system and its vocabulary list
+- `to-utf8`: the `*_to_utf8` functions
+- `from-utf8`: the `utf8_to_*` functions
+- `aho-corasick`: match patterns with the aho-corasick crate, which the
+  h-system requires. Without it a small built-in scanner is used, which is
+  enough for the x-system and leaves memchr as the only dependency.
+
+The optional `rayon` feature adds parallel conversion functions, and
+`allocator-api2` adds `Transliterator::convert_in` for allocating output from
+a custom allocator.
+
 # Example: UTF-8 to x-system
 
-```
+*/
+#![cfg_attr(all(feature = "x-system", feature = "from-utf8"), doc = "```")]
+#![cfg_attr(not(all(feature = "x-system", feature = "from-utf8")), doc = "```ignore")]
+/*!
 let input = "eĥoŝanĝo ĉiuĵaŭde";
 assert_eq!(
     esperanto_text::utf8_to_x_system(input),
@@ -25,7 +54,10 @@ assert_eq!(
 
 # Example: h-system to UTF-8
 
-```
+*/
+#![cfg_attr(all(feature = "h-system", feature = "to-utf8"), doc = "```")]
+#![cfg_attr(not(all(feature = "h-system", feature = "to-utf8")), doc = "```ignore")]
+/*!
 let input = "Chiuj estas senchavaj kaj taugaj ideoj.";
 assert_eq!(
     esperanto_text::h_system_to_utf8(input),
@@ -33,210 +65,341 @@ assert_eq!(
 );
 ```
 
+# Example: converting many strings
+
+Each call to the functions above compiles its patterns from scratch. When
+converting a large number of strings, build a
+[`Transliterator`](struct.Transliterator.html) once and reuse it, or use
+[`convert_all`](fn.convert_all.html).
+
+*/
+#![cfg_attr(feature = "x-system", doc = "```")]
+#![cfg_attr(not(feature = "x-system"), doc = "```ignore")]
+/*!
+use esperanto_text::System;
+
+let words = vec!["cxu", "sxi", "jxauxdo"];
+assert_eq!(
+    esperanto_text::convert_all(&words, System::X, System::Utf8),
+    vec!["ĉu", "ŝi", "ĵaŭdo"],
+);
+```
+
 */
 
-use aho_corasick::{AhoCorasick, AhoCorasickBuilder, MatchKind};
+#[cfg(not(any(feature = "x-system", feature = "h-system")))]
+compile_error!("at least one of the `x-system` and `h-system` features must be enabled");
+
+mod braille;
+mod cache;
+mod cancel;
+mod case;
+mod canonical;
+mod compare;
+mod compose;
+mod cyrillic;
+mod date;
+mod decoder;
+mod detect;
+mod diff;
+#[cfg(feature = "big-dict")]
+mod dictionary;
+#[cfg(feature = "encodings")]
+mod encoding;
+mod error;
+#[cfg(feature = "h-system")]
+mod eval;
+mod exceptions;
+mod frequency;
+mod html;
+mod inflection;
+mod ipa;
+mod language;
+mod latin3;
+#[cfg(feature = "learn")]
+mod learn;
+mod letter;
+#[cfg(feature = "h-system")]
+mod lexicon;
+mod matcher;
+mod mime;
+mod modern;
+mod mojibake;
+mod morpheme;
+mod morse;
+#[cfg(feature = "h-system")]
+mod morphology;
+mod number;
+mod ocr;
+mod orthography;
+pub mod patterns;
+mod percent;
+mod pipeline;
+mod pool;
+pub mod prelude;
+mod roundtrip;
+mod sentence;
+mod skip;
+mod speech;
+mod spelling;
+mod stats;
+mod syllable;
+mod system;
+mod token;
+mod transliterator;
+mod unicode_escape;
+
+pub use braille::{braille_to_utf8, utf8_to_braille};
+pub use cache::CachedTransliterator;
+pub use cancel::CancelToken;
+pub use case::{to_lowercase_eo, to_titlecase_eo, to_uppercase_eo};
+pub use compare::{
+    eo_cmp, eo_collation_key, eo_eq, eo_find, eo_find_iter, eo_levenshtein, sort_eo, EoMatches,
+};
+pub use compose::Composer;
+pub use date::{
+    format_date, format_date_with_weekday, month_name, parse_date, weekday_name, Date,
+};
+#[cfg(feature = "big-dict")]
+pub use dictionary::{check_word, suggest, Dictionary, SpellResult};
+#[cfg(feature = "h-system")]
+pub use eval::{evaluate_h_system, EvalReport, Misreading};
+pub use detect::{detect_system, profile, Detection, Profile};
+pub use diff::{eo_diff, Difference};
+#[cfg(feature = "encodings")]
+pub use encoding::{decode_and_convert, Encoding};
+pub use error::Error;
+pub use frequency::{text_stats, TextStats};
+pub use inflection::{add_accusative, pluralize, strip_ending};
+pub use ipa::utf8_to_ipa;
+pub use language::esperanto_likelihood;
+pub use latin3::{latin3_to_utf8, utf8_to_latin3};
+#[cfg(feature = "learn")]
+pub use learn::{suggest_exceptions, Suggestion};
+pub use letter::{hatted, unhatted, Diacritic, LetterSet};
+#[cfg(feature = "h-system")]
+pub use lexicon::{BuiltinLexicon, Lexicon};
+pub use mojibake::fix_mojibake;
+pub use morpheme::{segment, stem, Morpheme};
+pub use morse::{morse_to_utf8, utf8_to_morse, Morse};
+pub use number::{
+    expand_ordinals, number_to_words, number_to_words_in, ordinal_to_words, ordinal_to_words_in,
+    words_to_number,
+};
+pub use ocr::{fix_ocr, OcrRepair, OCR_CONFUSIONS};
+pub use orthography::{validate_orthography, Issue, IssueKind};
+pub use pipeline::{
+    CasingPolicy, Invisible, MatchSpeed, PercentEscapes, Pipeline, UnicodeForm,
+};
+pub use pool::TransliteratorPool;
+pub use roundtrip::{verify_roundtrip, verify_roundtrip_with, RoundTrip};
+pub use sentence::split_sentences;
+pub use speech::{prepare_for_tts, prepare_for_tts_ipa};
+pub use spelling::{parse_spelling, spell_out};
+pub use stats::RuleStats;
+pub use syllable::{hyphenation_points, syllabify};
+pub use system::System;
+pub use token::{tokens, Token, TokenKind, Tokens};
+pub use transliterator::Transliterator;
 
 /// Patterns to match for x-system input (case-insensitive)
+#[cfg(feature = "x-system")]
 const FROM_X_CI: &[&str] = &[
     "cx", "gx", "hx", "jx", "sx", "ux",
 ];
 
+/// Patterns to match for postfix caret input (case-insensitive)
+const FROM_POSTFIX_CARET_CI: &[&str] = &[
+    "c^", "g^", "h^", "j^", "s^", "u^",
+    "u~",
+];
+
+/// Patterns to match for apostrophe input (case-insensitive)
+const FROM_APOSTROPHE_CI: &[&str] = &[
+    "c'", "g'", "h'", "j'", "s'", "u'",
+];
+
+/// Patterns to match for prefix caret input (case-insensitive)
+const FROM_PREFIX_CARET_CI: &[&str] = &[
+    "^c", "^g", "^h", "^j", "^s", "^u",
+];
+
 /// Patterns to match for UTF-8 input
 ///
-/// Need to specify both cases as AhoCorasick's insensitive mode is ASCII-only.
+/// Need to specify both cases as the matcher's insensitive mode is ASCII-only.
 const FROM_UTF8: &[&str] = &[
     "ĉ", "ĝ", "ĥ", "ĵ", "ŝ", "ŭ",
     "Ĉ", "Ĝ", "Ĥ", "Ĵ", "Ŝ", "Ŭ",
 ];
 
-/// Patterns to match for h-system input (case-insensitive)
+/// Patterns to match for decomposed (NFD) UTF-8 input (case-insensitive)
 ///
-/// This includes all the transliterations but also a reasonably exhaustive
-/// list of word fragments that need to be left alone, rather than blindly
-/// substituting "something+h" with a diacritic. These longer segments will
-/// be allowed to pass through unchanged.
-const FROM_H_CI: &[&str] = &[
-    // Uses of "h" to leave alone
-    "komenchor", "kuracherb", "potenchav", "prononchelp", "senchav",
-    /* (ŝ) */ "pruchelp", "drogherb", "flughaven", "longhar",
-    /* (ŝ) */ "lesvigholstini", "vanghar", "gajhumor", "amashisteri",
-    /* (aŭ) */ "tobushaltej", "bushaltej", /* (ĉ) */ "ashund", "dishak",
-    "disharmoni", "dishelig", "dishirtig", "fikshejm", "grashav",
-    "grashepata", "invershav", "kashal", "misharmoni", "mishelp",
-    "mishumor", "neinvershav", "plushor", "sekshontem", "seshektar",
-    "seshor", "sukceshav",
-
-    // Uses of "au" (without circumflex) to leave alone
-    "blankaurs", "doganauni", /* (eŭ) */ "ropauni", "grandaursin",
-    "imaginaraunu", "kakauj", "malgrandaursin", "matricaunu",
-    "naur", "praul", "saudaarabuj", "tiaul", "traurb", "unuaul",
-
-    // Regular letters to transliterate
-    "ch", "gh", "hh", "jh", "sh",
-
-    // In most situations this is meant to become "aŭ"
-    "au",
+/// Each is a base letter followed by a combining circumflex or breve, as found
+/// in text copied from macOS or produced by some OCR tools.
+const FROM_NFD_CI: &[&str] = &[
+    "c\u{302}", "g\u{302}", "h\u{302}", "j\u{302}", "s\u{302}", "u\u{306}",
 ];
 
+/// Lookalike characters that are read as hatted letters when
+/// [`Pipeline::normalize_confusables`](struct.Pipeline.html#method.normalize_confusables)
+/// is enabled, with the letter each one stands for
+///
+/// These mostly come from keyboard layouts for other languages: a caron
+/// in place of a circumflex, or a different breve over the "u". The ASCII
+/// base letter of the decomposed sequences is matched in either case.
+pub const CONFUSABLES: &[(&str, char)] = &[
+    // Caron instead of circumflex
+    ("č", 'ĉ'), ("ǧ", 'ĝ'), ("ȟ", 'ĥ'), ("ǰ", 'ĵ'), ("š", 'ŝ'),
+    ("Č", 'Ĉ'), ("Ǧ", 'Ĝ'), ("Ȟ", 'Ĥ'),             ("Š", 'Ŝ'),
+    ("c\u{30C}", 'ĉ'), ("g\u{30C}", 'ĝ'), ("h\u{30C}", 'ĥ'), ("j\u{30C}", 'ĵ'), ("s\u{30C}", 'ŝ'),
+    // Caron or inverted breve instead of breve
+    ("ǔ", 'ŭ'), ("Ǔ", 'Ŭ'), ("ȗ", 'ŭ'), ("Ȗ", 'Ŭ'),
+    ("u\u{30C}", 'ŭ'), ("u\u{311}", 'ŭ'),
+    // Spacing modifier letters typed after the base letter
+    ("c\u{2C6}", 'ĉ'), ("g\u{2C6}", 'ĝ'), ("h\u{2C6}", 'ĥ'), ("j\u{2C6}", 'ĵ'), ("s\u{2C6}", 'ŝ'),
+    ("u\u{2D8}", 'ŭ'),
+];
+
+// `FROM_H_CI`, `X_EXCEPTIONS_CI` and `EXCEPTION_ANCHORS` are generated by
+// build.rs from the word lists in data/
+include!(concat!(env!("OUT_DIR"), "/patterns.rs"));
+
 /// Convert UTF-8 "ĵaŭdo" to x-system "jxauxdo"
+#[cfg(all(feature = "x-system", feature = "from-utf8"))]
 pub fn utf8_to_x_system(s: &str) -> String {
-    let ac = AhoCorasick::new(FROM_UTF8);
-    let mut result = String::new();
-    ac.replace_all_with(s, &mut result, |m, found, dst| {
-        let leading_capital = match dst.chars().rev().next() {
-            Some(c) if c.is_uppercase() => false,
-            Some(_) => true,
-            None => true,
-        };
-        let (_, tail) = s.split_at(m.end());
-        let capital_follows = match tail.chars().next() {
-            Some(c) if c.is_uppercase() => true,
-            Some(_) => false,
-            None => false,
-        };
-        dst.push_str(match found {
-            "ĉ" => "cx",
-            "ĝ" => "gx",
-            "ĥ" => "hx",
-            "ĵ" => "jx",
-            "ŝ" => "sx",
-            "ŭ" => "ux",
-            other => match (other, leading_capital && !capital_follows) {
-                ("Ĉ", false) => "CX",
-                ("Ĝ", false) => "GX",
-                ("Ĥ", false) => "HX",
-                ("Ĵ", false) => "JX",
-                ("Ŝ", false) => "SX",
-                ("Ŭ", false) => "UX",
-                ("Ĉ", true) => "Cx",
-                ("Ĝ", true) => "Gx",
-                ("Ĥ", true) => "Hx",
-                ("Ĵ", true) => "Jx",
-                ("Ŝ", true) => "Sx",
-                ("Ŭ", true) => "Ux",
-                _ => other,
-            }
-        });
-        true
-    });
-    result
+    convert(s, System::Utf8, System::X)
+}
+
+/// Convert UTF-8 "ĵaŭdo" to plain ASCII "jaudo" by dropping the hats
+///
+/// Useful for URL slugs, file names and search keys. The result can't be
+/// converted back.
+///
+/// ```
+/// assert_eq!(esperanto_text::utf8_to_ascii_reduced("Ĉiuĵaŭde"), "Ciujaude");
+/// ```
+#[cfg(feature = "from-utf8")]
+pub fn utf8_to_ascii_reduced(s: &str) -> String {
+    convert(s, System::Utf8, System::Reduced)
 }
 
 /// Convert UTF-8 "ĵaŭdo" to h-system "jhaudo"
+#[cfg(all(feature = "h-system", feature = "from-utf8"))]
 pub fn utf8_to_h_system(s: &str) -> String {
-    let ac = AhoCorasick::new(FROM_UTF8);
-    let mut result = String::new();
-    ac.replace_all_with(s, &mut result, |m, found, dst| {
-        let leading_capital = match dst.chars().rev().next() {
-            Some(c) if c.is_uppercase() => false,
-            Some(_) => true,
-            None => true,
-        };
-        let (_, tail) = s.split_at(m.end());
-        let capital_follows = match tail.chars().next() {
-            Some(c) if c.is_uppercase() => true,
-            Some(_) => false,
-            None => false,
-        };
-        dst.push_str(match found {
-            "ĉ" => "ch",
-            "ĝ" => "gh",
-            "ĥ" => "hh",
-            "ĵ" => "jh",
-            "ŝ" => "sh",
-            "ŭ" => "u",
-            other => match (other, leading_capital && !capital_follows) {
-                ("Ĉ", false) => "CH",
-                ("Ĝ", false) => "GH",
-                ("Ĥ", false) => "HH",
-                ("Ĵ", false) => "JH",
-                ("Ŝ", false) => "SH",
-                ("Ŭ", false) => "U",
-                ("Ĉ", true) => "Ch",
-                ("Ĝ", true) => "Gh",
-                ("Ĥ", true) => "Hh",
-                ("Ĵ", true) => "Jh",
-                ("Ŝ", true) => "Sh",
-                ("Ŭ", true) => "U",
-                _ => other,
-            }
-        });
-        true
-    });
-    result
+    convert(s, System::Utf8, System::H)
 }
 
 /// Convert x-system "jxauxdo" to UTF-8 "ĵaŭdo"
+#[cfg(all(feature = "x-system", feature = "to-utf8"))]
 pub fn x_system_to_utf8(s: &str) -> String {
-    let ac = AhoCorasickBuilder::new()
-        .ascii_case_insensitive(true)
-        .build(FROM_X_CI);
-    let mut result = String::new();
-    ac.replace_all_with(s, &mut result, |_, found, dst| {
-        dst.push_str(match found {
-            "cx" => "ĉ",
-            "gx" => "ĝ",
-            "hx" => "ĥ",
-            "jx" => "ĵ",
-            "sx" => "ŝ",
-            "ux" => "ŭ",
-            "CX" | "Cx" | "cX" => "Ĉ",
-            "GX" | "Gx" | "gX" => "Ĝ",
-            "HX" | "Hx" | "hX" => "Ĥ",
-            "JX" | "Jx" | "jX" => "Ĵ",
-            "SX" | "Sx" | "sX" => "Ŝ",
-            "UX" | "Ux" | "uX" => "Ŭ",
-            _ => found,
-        });
-        true
-    });
-    result
+    convert(s, System::X, System::Utf8)
 }
 
 /// Convert h-system "jhaudo" to UTF-8 "ĵaŭdo"
+#[cfg(all(feature = "h-system", feature = "to-utf8"))]
 pub fn h_system_to_utf8(s: &str) -> String {
-    let ac = AhoCorasickBuilder::new()
-        .ascii_case_insensitive(true)
-        .match_kind(MatchKind::LeftmostLongest)
-        .build(FROM_H_CI);
-    let mut result = String::new();
-    ac.replace_all_with(s, &mut result, |_, found, dst| {
-        dst.push_str(match found {
-            "ch" => "ĉ",
-            "gh" => "ĝ",
-            "hh" => "ĥ",
-            "jh" => "ĵ",
-            "sh" => "ŝ",
-            "au" => "aŭ",
-            "CH" | "Ch" | "cH" => "Ĉ",
-            "GH" | "Gh" | "gH" => "Ĝ",
-            "HH" | "Hh" | "hH" => "Ĥ",
-            "JH" | "Jh" | "jH" => "Ĵ",
-            "SH" | "Sh" | "sH" => "Ŝ",
-            "AU" => "AŬ",
-            "Au" => "Aŭ",
-            "aU" => "aŬ",
-            // all the word fragments go through with existing casing
-            // and without messing up the legitimate usage of "h"
-            // or the legitimate usage of "au"
-            _ => found,
-        });
-        true
-    });
-    result
+    convert(s, System::H, System::Utf8)
+}
+
+/// Convert h-system "jhaudo" to UTF-8 "ĵaŭdo" by rule alone, without the
+/// list of words to leave alone
+///
+/// Every "ch", "gh", "hh", "jh", "sh" and "au" is converted, so this is only
+/// suitable for text that is known to be pure h-system.
+///
+/// ```
+/// assert_eq!(esperanto_text::h_system_to_utf8_strict("senchava"), "senĉava");
+/// ```
+#[cfg(all(feature = "h-system", feature = "to-utf8"))]
+pub fn h_system_to_utf8_strict(s: &str) -> String {
+    Pipeline::new().from(System::H).strict(true).build().convert(s)
+}
+
+/// Convert x-system "jxauxdo" to h-system "jhaudo" in a single pass
+#[cfg(all(feature = "x-system", feature = "h-system"))]
+pub fn x_system_to_h_system(s: &str) -> String {
+    convert(s, System::X, System::H)
+}
+
+/// Convert h-system "jhaudo" to x-system "jxauxdo" in a single pass
+#[cfg(all(feature = "x-system", feature = "h-system"))]
+pub fn h_system_to_x_system(s: &str) -> String {
+    convert(s, System::H, System::X)
+}
+
+/// Convert text that mixes UTF-8, x-system and h-system to clean UTF-8
+///
+/// x-system and h-system digraphs are both converted in a single pass and any
+/// letters already in UTF-8 are left alone. Where the systems disagree the
+/// x-system wins, so "aux" becomes "aŭ". Decomposed letters such as "c"
+/// followed by a combining circumflex are also composed into "ĉ".
+///
+#[cfg_attr(all(feature = "x-system", feature = "h-system"), doc = "```")]
+#[cfg_attr(not(all(feature = "x-system", feature = "h-system")), doc = "```ignore")]
+/// assert_eq!(
+///     esperanto_text::normalize_to_utf8("mi sxatas la ĉokoladon kaj ankau teon"),
+///     "mi ŝatas la ĉokoladon kaj ankaŭ teon",
+/// );
+/// ```
+pub fn normalize_to_utf8(s: &str) -> String {
+    canonical::chars(s).collect()
+}
+
+/// Convert a string between any two systems
+pub fn convert(s: &str, from: System, to: System) -> String {
+    Transliterator::new(from, to).convert(s)
+}
+
+/// Convert bytes that should be UTF-8 between any two systems, failing if
+/// they aren't UTF-8
+pub fn try_convert(bytes: &[u8], from: System, to: System) -> Result<String, Error> {
+    Transliterator::new(from, to).try_convert(bytes)
+}
+
+/// Convert UTF-16 text between any two systems
+///
+/// See [`Transliterator::convert_utf16`](struct.Transliterator.html#method.convert_utf16)
+/// for how surrogates are handled.
+pub fn convert_utf16(s: &[u16], from: System, to: System) -> Vec<u16> {
+    Transliterator::new(from, to).convert_utf16(s)
+}
+
+/// Convert many strings between two systems, compiling the patterns only once
+pub fn convert_all<I, S>(items: I, from: System, to: System) -> Vec<String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    Transliterator::new(from, to).convert_all(items)
+}
+
+/// Convert many strings between two systems in parallel using rayon
+#[cfg(feature = "rayon")]
+pub fn par_convert_all<S>(items: &[S], from: System, to: System) -> Vec<String>
+where
+    S: AsRef<str> + Sync,
+{
+    Transliterator::new(from, to).par_convert_all(items)
+}
+
+/// Convert one large string between two systems using rayon's thread pool
+#[cfg(feature = "rayon")]
+pub fn par_convert(s: &str, from: System, to: System) -> String {
+    Transliterator::new(from, to).par_convert(s)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[cfg(all(feature = "x-system", feature = "to-utf8"))]
     #[test]
     fn test_x_system_to_utf8_noop() {
         let input = "The quick brown fox jumps over the lazy dog. And my axe.".to_owned();
         assert_eq!(input, x_system_to_utf8(&input));
     }
 
+    #[cfg(all(feature = "x-system", feature = "to-utf8"))]
     #[test]
     fn test_x_system_to_utf8_echo_change() {
         let input = "ehxosxangxo cxiujxauxde EHXOSXANGXO CXIUJXAUXDE";
@@ -244,6 +407,16 @@ mod tests {
         assert_eq!(&x_system_to_utf8(input), expected);
     }
 
+    #[cfg(all(feature = "x-system", feature = "to-utf8"))]
+    #[test]
+    fn test_x_system_to_utf8_foreign_words() {
+        let input = "Linux, AUXILIARY vino el Bordeaux kaj Lux; auxdi la fluxon";
+        let expected = "Linux, AUXILIARY vino el Bordeaux kaj Lux; aŭdi la fluxon";
+        assert_eq!(&x_system_to_utf8(input), expected);
+        assert_eq!(&x_system_to_utf8("lauxlux"), "laŭlŭ");
+    }
+
+    #[cfg(all(feature = "x-system", feature = "to-utf8"))]
     #[test]
     fn test_x_system_to_utf8_mixed_case() {
         let input = "eHxoSxanGxo CxiuJxaUxde ehXosXangXo cXiujXauXde";
@@ -251,12 +424,14 @@ mod tests {
         assert_eq!(&x_system_to_utf8(input), expected);
     }
 
+    #[cfg(all(feature = "x-system", feature = "from-utf8"))]
     #[test]
     fn test_utf8_to_x_system_noop() {
         let input = "The quick brown fox jumps over the lazy dog. And my axe.".to_owned();
         assert_eq!(input, utf8_to_x_system(&input));
     }
 
+    #[cfg(all(feature = "x-system", feature = "from-utf8"))]
     #[test]
     fn test_utf8_to_x_system_echo_change() {
         let input = "eĥoŝanĝo ĉiuĵaŭde EĤOŜANĜO ĈIUĴAŬDE";
@@ -264,12 +439,22 @@ mod tests {
         assert_eq!(&utf8_to_x_system(input), expected);
     }
 
+    #[cfg(all(feature = "x-system", feature = "from-utf8"))]
+    #[test]
+    fn test_utf8_to_x_system_decomposed() {
+        let input = "eh\u{302}os\u{302}ang\u{302}o C\u{302}IUJ\u{302}AU\u{306}DE";
+        let expected = "ehxosxangxo CXIUJXAUXDE";
+        assert_eq!(&utf8_to_x_system(input), expected);
+    }
+
+    #[cfg(all(feature = "h-system", feature = "from-utf8"))]
     #[test]
     fn test_utf8_to_h_system_noop() {
         let input = "The quick brown fox jumps over the lazy dog. And my axe.".to_owned();
         assert_eq!(input, utf8_to_h_system(&input));
     }
 
+    #[cfg(all(feature = "h-system", feature = "from-utf8"))]
     #[test]
     fn test_utf8_to_h_system_echo_change() {
         let input = "eĥoŝanĝo ĉiuĵaŭde EĤOŜANĜO ĈIUĴAŬDE";
@@ -277,12 +462,14 @@ mod tests {
         assert_eq!(&utf8_to_h_system(input), expected);
     }
 
+    #[cfg(all(feature = "h-system", feature = "to-utf8"))]
     #[test]
     fn test_h_system_to_utf8_noop() {
         let input = "The quick brown fox jumps over the lazy dog. And my axe.".to_owned();
         assert_eq!(input, h_system_to_utf8(&input));
     }
 
+    #[cfg(all(feature = "h-system", feature = "to-utf8"))]
     #[test]
     fn test_h_system_to_utf8_echo_change() {
         let input = "ehhoshangho chiujhaude EHHOSHANGHO CHIUJHAUDE";
@@ -290,6 +477,7 @@ mod tests {
         assert_eq!(&h_system_to_utf8(input), expected);
     }
 
+    #[cfg(all(feature = "h-system", feature = "to-utf8"))]
     #[test]
     fn test_h_system_to_utf8_mixed_case() {
         let input = "eHhoShanGho ChiuJhAUde ehHosHangHo cHiujHaUde";
@@ -297,6 +485,7 @@ mod tests {
         assert_eq!(&h_system_to_utf8(input), expected);
     }
 
+    #[cfg(all(feature = "h-system", feature = "to-utf8"))]
     #[test]
     fn test_h_system_ambiguous_h() {
         let input = "Chiuj estas senchavaj ideoj.";
@@ -304,6 +493,25 @@ mod tests {
         assert_eq!(&h_system_to_utf8(input), expected);
     }
 
+    #[cfg(all(feature = "h-system", feature = "to-utf8"))]
+    #[test]
+    fn test_h_system_hyphenated_compounds() {
+        let input = "flug-haveno, aŭtobus-haltejo kaj pra-ulo";
+        assert_eq!(&h_system_to_utf8(&input.replace('ŭ', "u")), input);
+        let joined = "flughaveno, aŭtobushaltejo kaj praulo";
+        assert_eq!(&h_system_to_utf8(&joined.replace('ŭ', "u")), joined);
+    }
+
+    #[cfg(all(feature = "big-dict", feature = "to-utf8"))]
+    #[test]
+    fn test_h_system_root_list() {
+        assert_eq!(
+            h_system_to_utf8("La dishavigo de la gashejtilo chiam shanghas."),
+            "La dishavigo de la gashejtilo ĉiam ŝanĝas."
+        );
+    }
+
+    #[cfg(all(feature = "h-system", feature = "to-utf8"))]
     #[test]
     fn test_h_system_ambiguous_u() {
         let input = "Hierau mi vizitis Nauron.";
@@ -311,6 +519,39 @@ mod tests {
         assert_eq!(&h_system_to_utf8(input), expected);
     }
 
+    #[cfg(all(feature = "x-system", feature = "h-system"))]
+    #[test]
+    fn test_x_system_to_h_system_echo_change() {
+        let input = "ehxosxangxo cxiujxauxde EHXOSXANGXO CXIUJXAUXDE";
+        let expected = "ehhoshangho chiujhaude EHHOSHANGHO CHIUJHAUDE";
+        assert_eq!(&x_system_to_h_system(input), expected);
+    }
+
+    #[cfg(all(feature = "x-system", feature = "h-system"))]
+    #[test]
+    fn test_h_system_to_x_system_echo_change() {
+        let input = "ehhoshangho chiujhaude EHHOSHANGHO CHIUJHAUDE";
+        let expected = "ehxosxangxo cxiujxauxde EHXOSXANGXO CXIUJXAUXDE";
+        assert_eq!(&h_system_to_x_system(input), expected);
+    }
+
+    #[cfg(all(feature = "x-system", feature = "h-system"))]
+    #[test]
+    fn test_h_system_to_x_system_ambiguous() {
+        let input = "Chiuj estas senchavaj kaj taugaj ideoj.";
+        let expected = "Cxiuj estas senchavaj kaj tauxgaj ideoj.";
+        assert_eq!(&h_system_to_x_system(input), expected);
+    }
+
+    #[cfg(all(feature = "x-system", feature = "h-system"))]
+    #[test]
+    fn test_normalize_to_utf8_mixed() {
+        let input = "Cxiuj Chiuj Ĉiuj senchavaj tauxgaj taugaj";
+        let expected = "Ĉiuj Ĉiuj Ĉiuj senchavaj taŭgaj taŭgaj";
+        assert_eq!(&normalize_to_utf8(input), expected);
+    }
+
+    #[cfg(all(feature = "x-system", feature = "from-utf8"))]
     #[test]
     fn test_leading_capital_x_system() {
         let input = "Ĉiuj estas belaj. Ĥ Ŝ Ĝ Ĉ Ĵ Ŭ ĤO ŜO ĜO ĈO ĴO ŬO";
@@ -318,10 +559,29 @@ mod tests {
         assert_eq!(&utf8_to_x_system(input), expected);
     }
 
+    #[cfg(all(feature = "h-system", feature = "from-utf8"))]
     #[test]
     fn test_leading_capital_h_system() {
         let input = "Ĉiuj estas belaj. Ĥ Ŝ Ĝ Ĉ Ĵ Ŭ ĤO ŜO ĜO ĈO ĴO ŬO";
         let expected = "Chiuj estas belaj. Hh Sh Gh Ch Jh U HHO SHO GHO CHO JHO UO";
         assert_eq!(&utf8_to_h_system(input), expected);
     }
+
+    #[cfg(all(feature = "x-system", feature = "h-system", feature = "to-utf8", feature = "from-utf8"))]
+    #[test]
+    fn test_elision_in_verse() {
+        let utf8 = "Ĉe l’ ŝtonoj de l’ Ĥimalajo, laŭ l' aŭtun'\n\
+                    Dank' al Di' ni ĉiuj kantas pri l' ĉarma ĥor'\n\
+                    DE L'ĈIELO ĈU' VI ŜAT' AŬ NE?";
+        let x = "Cxe l’ sxtonoj de l’ Hximalajo, laux l' auxtun'\n\
+                 Dank' al Di' ni cxiuj kantas pri l' cxarma hxor'\n\
+                 DE L'CXIELO CXU' VI SXAT' AUX NE?";
+        let h = "Che l’ shtonoj de l’ Hhimalajo, lau l' autun'\n\
+                 Dank' al Di' ni chiuj kantas pri l' charma hhor'\n\
+                 DE L'CHIELO CHU' VI SHAT' AU NE?";
+        assert_eq!(utf8_to_x_system(utf8), x);
+        assert_eq!(utf8_to_h_system(utf8), h);
+        assert_eq!(x_system_to_utf8(x), utf8);
+        assert_eq!(h_system_to_utf8(h), utf8);
+    }
 }
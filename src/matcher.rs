@@ -0,0 +1,172 @@
+//! Multi-pattern search, using aho-corasick when it is available
+//!
+//! All searches are ASCII case-insensitive and report the leftmost-longest
+//! non-overlapping matches. Without the `aho-corasick` feature a simple
+//! scanner is used instead, which is plenty for the handful of short
+//! patterns needed by the x-system and UTF-8.
+
+#[cfg(feature = "aho-corasick")]
+use aho_corasick::{AhoCorasick, AhoCorasickBuilder, MatchKind};
+
+use crate::pipeline::MatchSpeed;
+
+/// A pattern found in a haystack
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Match {
+    pattern: usize,
+    start: usize,
+    end: usize,
+}
+
+impl Match {
+    pub(crate) fn new(pattern: usize, start: usize, end: usize) -> Match {
+        Match {
+            pattern,
+            start,
+            end,
+        }
+    }
+
+    /// The same match in a haystack that starts `by` bytes earlier
+    pub(crate) fn offset(self, by: usize) -> Match {
+        Match::new(self.pattern, self.start + by, self.end + by)
+    }
+
+    /// Index of the pattern that matched
+    pub(crate) fn pattern(&self) -> usize {
+        self.pattern
+    }
+
+    /// Byte offset where the match starts
+    pub(crate) fn start(&self) -> usize {
+        self.start
+    }
+
+    /// Byte offset just past the end of the match
+    pub(crate) fn end(&self) -> usize {
+        self.end
+    }
+}
+
+/// A compiled set of patterns
+pub(crate) struct Matcher {
+    #[cfg(feature = "aho-corasick")]
+    ac: AhoCorasick,
+    #[cfg(not(feature = "aho-corasick"))]
+    patterns: Vec<Vec<u8>>,
+}
+
+impl Matcher {
+    #[cfg(feature = "aho-corasick")]
+    pub(crate) fn new<P: AsRef<str>>(patterns: &[P], speed: MatchSpeed) -> Matcher {
+        let ac = AhoCorasickBuilder::new()
+            .ascii_case_insensitive(true)
+            .match_kind(MatchKind::LeftmostLongest)
+            .dfa(speed == MatchSpeed::Fast)
+            .build(patterns.iter().map(|p| p.as_ref()));
+        Matcher { ac }
+    }
+
+    #[cfg(not(feature = "aho-corasick"))]
+    pub(crate) fn new<P: AsRef<str>>(patterns: &[P], _speed: MatchSpeed) -> Matcher {
+        let patterns = patterns
+            .iter()
+            .map(|p| p.as_ref().as_bytes().to_ascii_lowercase())
+            .collect();
+        Matcher { patterns }
+    }
+
+    /// Iterate over the matches in `haystack` from left to right
+    pub(crate) fn find_iter<'a>(&'a self, haystack: &'a str) -> FindIter<'a> {
+        #[cfg(feature = "aho-corasick")]
+        let inner = self.ac.find_iter(haystack);
+        #[cfg(not(feature = "aho-corasick"))]
+        let inner = Scanner {
+            patterns: &self.patterns,
+            haystack: haystack.as_bytes(),
+            pos: 0,
+        };
+        FindIter { inner }
+    }
+}
+
+/// Iterator over the matches of a `Matcher`
+pub(crate) struct FindIter<'a> {
+    #[cfg(feature = "aho-corasick")]
+    inner: aho_corasick::FindIter<'a, 'a, usize>,
+    #[cfg(not(feature = "aho-corasick"))]
+    inner: Scanner<'a>,
+}
+
+impl<'a> Iterator for FindIter<'a> {
+    type Item = Match;
+
+    #[cfg(feature = "aho-corasick")]
+    fn next(&mut self) -> Option<Match> {
+        self.inner.next().map(|m| Match {
+            pattern: m.pattern(),
+            start: m.start(),
+            end: m.end(),
+        })
+    }
+
+    #[cfg(not(feature = "aho-corasick"))]
+    fn next(&mut self) -> Option<Match> {
+        self.inner.next()
+    }
+}
+
+/// Tries every pattern at every position, for small pattern sets
+#[cfg(not(feature = "aho-corasick"))]
+struct Scanner<'a> {
+    patterns: &'a [Vec<u8>],
+    haystack: &'a [u8],
+    pos: usize,
+}
+
+#[cfg(not(feature = "aho-corasick"))]
+impl<'a> Iterator for Scanner<'a> {
+    type Item = Match;
+
+    fn next(&mut self) -> Option<Match> {
+        while self.pos < self.haystack.len() {
+            let rest = &self.haystack[self.pos..];
+            let longest = self
+                .patterns
+                .iter()
+                .enumerate()
+                .filter(|(_, p)| !p.is_empty() && rest.len() >= p.len())
+                .filter(|(_, p)| rest[..p.len()].eq_ignore_ascii_case(p))
+                .fold(None, |best: Option<(usize, usize)>, (i, p)| match best {
+                    Some((_, len)) if len >= p.len() => best,
+                    _ => Some((i, p.len())),
+                });
+            if let Some((pattern, len)) = longest {
+                let start = self.pos;
+                self.pos += len;
+                return Some(Match {
+                    pattern,
+                    start,
+                    end: self.pos,
+                });
+            }
+            self.pos += 1;
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_leftmost_longest_case_insensitive() {
+        let m = Matcher::new(&["au", "aux", "ux"], MatchSpeed::Compact);
+        let found: Vec<(usize, usize, usize)> = m
+            .find_iter("AUx ux au")
+            .map(|m| (m.pattern(), m.start(), m.end()))
+            .collect();
+        assert_eq!(found, vec![(1, 0, 3), (2, 4, 6), (0, 7, 9)]);
+    }
+}
@@ -0,0 +1,211 @@
+//! RFC 2047 encoded words, as found in email headers
+
+use std::ops::Range;
+
+use crate::latin3::{latin3_to_utf8, utf8_to_latin3};
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// An encoded word such as "=?UTF-8?Q?=C4=89u?="
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct EncodedWord<'a> {
+    charset: &'a str,
+    base64: bool,
+    text: &'a str,
+}
+
+impl<'a> EncodedWord<'a> {
+    /// The text of the word, if its charset and encoding are understood
+    pub(crate) fn decode(&self) -> Option<String> {
+        let bytes = if self.base64 {
+            base64_decode(self.text)?
+        } else {
+            q_decode(self.text)?
+        };
+        match charset(self.charset)? {
+            Charset::Utf8 => String::from_utf8(bytes).ok(),
+            Charset::Latin1 => Some(bytes.into_iter().map(char::from).collect()),
+            Charset::Latin3 => Some(latin3_to_utf8(&bytes)),
+        }
+    }
+
+    /// Encode `text` the same way as this word, switching to UTF-8 if its
+    /// charset can't hold the text
+    pub(crate) fn encode(&self, text: &str) -> String {
+        let bytes = match charset(self.charset) {
+            Some(Charset::Utf8) => Some(text.as_bytes().to_vec()),
+            Some(Charset::Latin1) if text.chars().all(|c| u32::from(c) < 0x100) => {
+                Some(text.chars().map(|c| c as u8).collect())
+            }
+            Some(Charset::Latin3) => utf8_to_latin3(text).ok(),
+            _ => None,
+        };
+        let (charset, bytes) = match bytes {
+            Some(bytes) => (self.charset, bytes),
+            None => ("UTF-8", text.as_bytes().to_vec()),
+        };
+        let (encoding, text) = if self.base64 {
+            ('B', base64_encode(&bytes))
+        } else {
+            ('Q', q_encode(&bytes))
+        };
+        format!("=?{}?{}?{}?=", charset, encoding, text)
+    }
+}
+
+enum Charset {
+    Utf8,
+    Latin1,
+    Latin3,
+}
+
+/// The charset named by `label`, ignoring any RFC 2231 language suffix
+fn charset(label: &str) -> Option<Charset> {
+    let label = label
+        .split('*')
+        .next()
+        .unwrap_or(label)
+        .to_ascii_lowercase();
+    match label.as_str() {
+        "utf-8" | "utf8" => Some(Charset::Utf8),
+        "iso-8859-1" | "us-ascii" | "latin1" => Some(Charset::Latin1),
+        "iso-8859-3" | "latin3" => Some(Charset::Latin3),
+        _ => None,
+    }
+}
+
+/// Find the encoded words in `s`, with the byte range of each
+pub(crate) fn encoded_words(s: &str) -> Vec<(Range<usize>, EncodedWord<'_>)> {
+    let mut words = Vec::new();
+    let mut pos = 0;
+    while let Some(i) = s[pos..].find("=?") {
+        let start = pos + i;
+        match parse(&s[start..]) {
+            Some((len, word)) => {
+                words.push((start..start + len, word));
+                pos = start + len;
+            }
+            None => pos = start + 2,
+        }
+    }
+    words
+}
+
+/// Parse an encoded word at the start of `s`, returning its length
+fn parse(s: &str) -> Option<(usize, EncodedWord<'_>)> {
+    let rest = s.strip_prefix("=?")?;
+    let mut parts = rest.splitn(3, '?');
+    let charset = parts.next()?;
+    let encoding = parts.next()?;
+    let text_and_rest = parts.next()?;
+    let end = text_and_rest.find("?=")?;
+    let text = &text_and_rest[..end];
+    let base64 = match encoding {
+        "B" | "b" => true,
+        "Q" | "q" => false,
+        _ => return None,
+    };
+    let valid = |t: &str| !t.is_empty() && !t.contains(|c: char| c.is_whitespace() || c == '?');
+    if !valid(charset) || text.contains(|c: char| c.is_whitespace() || c == '?') {
+        return None;
+    }
+    let len = 2 + charset.len() + 1 + encoding.len() + 1 + end + 2;
+    Some((
+        len,
+        EncodedWord {
+            charset,
+            base64,
+            text,
+        },
+    ))
+}
+
+fn q_decode(text: &str) -> Option<Vec<u8>> {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'_' => out.push(b' '),
+            b'=' => {
+                let hex = text.get(i + 1..i + 3)?;
+                out.push(u8::from_str_radix(hex, 16).ok()?);
+                i += 2;
+            }
+            b => out.push(b),
+        }
+        i += 1;
+    }
+    Some(out)
+}
+
+fn q_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len() * 3);
+    for &b in bytes {
+        match b {
+            b' ' => out.push('_'),
+            b if b.is_ascii_alphanumeric() || b"!*+-/".contains(&b) => out.push(char::from(b)),
+            b => out.push_str(&format!("={:02X}", b)),
+        }
+    }
+    out
+}
+
+fn base64_decode(text: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(text.len() * 3 / 4);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for b in text.bytes().filter(|b| *b != b'=') {
+        let value = BASE64.iter().position(|c| *c == b)? as u32;
+        buffer = buffer << 6 | value;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+        }
+    }
+    Some(out)
+}
+
+fn base64_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, b)| n | u32::from(*b) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(char::from(BASE64[(n >> (18 - 6 * i) & 63) as usize]));
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encoded_words() {
+        let s = "Subject: =?UTF-8?Q?=C4=89u_jes?= kaj =?utf-8?b?xIl1?= =?x?= =?koi8-r?Q?a?=";
+        let words = encoded_words(s);
+        assert_eq!(words.len(), 3);
+        assert_eq!(words[0].1.decode().as_deref(), Some("ĉu jes"));
+        assert_eq!(words[1].1.decode().as_deref(), Some("ĉu"));
+        assert_eq!(words[2].1.decode(), None);
+        assert_eq!(words[0].1.encode("cxu jes"), "=?UTF-8?Q?cxu_jes?=");
+        assert_eq!(words[1].1.encode("ĉu"), "=?utf-8?B?xIl1?=");
+    }
+
+    #[test]
+    fn test_base64_round_trip() {
+        for text in &["", "a", "ab", "abc", "ĉiuĵaŭde"] {
+            let encoded = base64_encode(text.as_bytes());
+            assert_eq!(base64_decode(&encoded).unwrap(), text.as_bytes());
+        }
+    }
+}
@@ -0,0 +1,69 @@
+//! Writing "k" for "ĥ" in the roots where modern usage prefers it
+//!
+//! Many international roots were first taken into Esperanto with "ĥ" and are
+//! now usually written with "k", as in "arkivo" and "kemio". This can't be
+//! done letter by letter: "ĥoro" (choir) would become "koro" (heart), so
+//! only the roots listed here are changed.
+
+/// Roots whose "ĥ" is now usually "k"
+///
+/// The letters either side of the "ĥ" are plain, so they read the same in
+/// every system. Left out on purpose are "ĥor", "ĥoler" and "monaĥ", whose
+/// "k" forms are different words ("koro", "kolera", "Monako").
+const MODERN_K_ROOTS: &[&str] = &[
+    "arĥaik",
+    "arĥeolog",
+    "arĥitekt",
+    "arĥiv",
+    "ĥaos",
+    "ĥarakter",
+    "ĥemi",
+    "ĥimer",
+    "ĥinin",
+    "ĥirurg",
+    "ĥlor",
+    "ĥrist",
+    "ĥrom",
+    "ĥronik",
+    "ĥronolog",
+    "meĥanik",
+    "orĥestr",
+    "psiĥ",
+    "teĥnik",
+    "teĥnolog",
+];
+
+/// Whether an "ĥ" between `before` and `after` is in a root that is now
+/// written with "k"
+///
+/// `before` is the text so far and `after` the text following the "ĥ".
+pub(crate) fn modern_k_at(before: &str, after: &str) -> bool {
+    MODERN_K_ROOTS.iter().any(|root| {
+        let (prefix, suffix) = root.split_at(root.find('ĥ').unwrap_or(0));
+        let suffix = &suffix['ĥ'.len_utf8()..];
+        ends_with_ignore_case(before, prefix)
+            && after
+                .get(..suffix.len())
+                .is_some_and(|a| a.eq_ignore_ascii_case(suffix))
+    })
+}
+
+fn ends_with_ignore_case(s: &str, suffix: &str) -> bool {
+    s.len() >= suffix.len()
+        && s.as_bytes()[s.len() - suffix.len()..].eq_ignore_ascii_case(suffix.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_modern_k_at() {
+        assert!(modern_k_at("la ar", "ivo"));
+        assert!(modern_k_at("bio", "EMIO"));
+        assert!(modern_k_at("", "aoso"));
+        assert!(!modern_k_at("", "oro"));
+        assert!(!modern_k_at("mona", "o"));
+        assert!(!modern_k_at("", "em"));
+    }
+}
@@ -0,0 +1,105 @@
+//! Repairing hatted letters that were double-encoded
+//!
+//! When UTF-8 text is decoded as Latin-1 or Windows-1252 and encoded again,
+//! each hatted letter turns into two characters: "ĉ" becomes "Ä‰" and "ŭ"
+//! becomes "Å" followed by a soft hyphen. This was common in old email and
+//! forum software.
+
+use std::sync::OnceLock;
+
+use crate::letter::Letter;
+use crate::matcher::Matcher;
+use crate::pipeline::MatchSpeed;
+
+/// Windows-1252 characters for the bytes 0x80 to 0x9F that appear in the
+/// UTF-8 encoding of a hatted letter. The rest are the same as Latin-1.
+const CP1252: &[(u8, char)] = &[(0x88, 'ˆ'), (0x89, '‰'), (0x9C, 'œ')];
+
+/// The double-encoded forms of every hatted letter and what they stand for
+struct Repairs {
+    matcher: Matcher,
+    letters: Vec<char>,
+}
+
+fn repairs() -> &'static Repairs {
+    static REPAIRS: OnceLock<Repairs> = OnceLock::new();
+    REPAIRS.get_or_init(|| {
+        let mut patterns = Vec::new();
+        let mut letters = Vec::new();
+        for letter in Letter::ALL {
+            for upper in [false, true] {
+                let c = letter.hatted(upper);
+                let mut buf = [0; 4];
+                let bytes = c.encode_utf8(&mut buf).as_bytes();
+                let latin1: String = bytes.iter().map(|b| char::from(*b)).collect();
+                let cp1252: String = bytes.iter().map(|b| cp1252_char(*b)).collect();
+                if cp1252 != latin1 {
+                    patterns.push(cp1252);
+                    letters.push(c);
+                }
+                patterns.push(latin1);
+                letters.push(c);
+            }
+        }
+        Repairs {
+            matcher: Matcher::new(&patterns, MatchSpeed::Compact),
+            letters,
+        }
+    })
+}
+
+fn cp1252_char(b: u8) -> char {
+    CP1252
+        .iter()
+        .find(|(byte, _)| *byte == b)
+        .map_or(char::from(b), |(_, c)| *c)
+}
+
+/// Restore hatted letters that were mangled by being encoded as UTF-8 twice
+///
+/// Both the Latin-1 and Windows-1252 forms are recognised. Everything else,
+/// including any correctly encoded letters, is left alone, so it is safe to
+/// run this on text that is only partly damaged.
+///
+/// ```
+/// use esperanto_text::fix_mojibake;
+///
+/// assert_eq!(fix_mojibake("Ä‰iuÄµaÅ\u{ad}de"), "ĉiuĵaŭde");
+/// assert_eq!(fix_mojibake("ĉu jes?"), "ĉu jes?");
+/// ```
+pub fn fix_mojibake(s: &str) -> String {
+    // Every double-encoded letter starts with "Ä" or "Å"
+    if !s.contains(['Ä', 'Å']) {
+        return s.to_owned();
+    }
+    let repairs = repairs();
+    let mut out = String::with_capacity(s.len());
+    let mut last = 0;
+    for m in repairs.matcher.find_iter(s) {
+        out.push_str(&s[last..m.start()]);
+        out.push(repairs.letters[m.pattern()]);
+        last = m.end();
+    }
+    out.push_str(&s[last..]);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_all_letters_both_encodings() {
+        let letters = "ĉĝĥĵŝŭĈĜĤĴŜŬ";
+        let latin1: String = letters.bytes().map(char::from).collect();
+        let cp1252: String = letters.bytes().map(cp1252_char).collect();
+        assert_eq!(fix_mojibake(&latin1), letters);
+        assert_eq!(fix_mojibake(&cp1252), letters);
+    }
+
+    #[test]
+    fn test_other_text_untouched() {
+        let input = "Ärger in Åland, ĉu ne?";
+        assert_eq!(fix_mojibake(input), input);
+    }
+}
@@ -0,0 +1,178 @@
+//! Splitting words into roots, affixes and grammatical endings
+
+/// Grammatical endings, longest first
+const ENDINGS: &[&str] = &[
+    "ojn", "ajn", "oj", "on", "aj", "an", "en", "as", "is", "os", "us", "o", "a", "e", "i", "u",
+];
+
+/// Prefixes, longest first
+const PREFIXES: &[&str] = &["eks", "mal", "mis", "dis", "pra", "bo", "ek", "fi", "ge", "re"];
+
+/// Suffixes, longest first
+const SUFFIXES: &[&str] = &[
+    "estr", "ant", "ebl", "end", "ind", "ing", "int", "ism", "ist", "obl", "ont", "aĉ", "ad",
+    "aĵ", "an", "ar", "at", "ec", "eg", "ej", "em", "er", "et", "id", "ig", "iĝ", "il", "in", "it",
+    "on", "op", "ot", "uj", "ul", "um",
+];
+
+/// Common roots that begin with a prefix or end with a suffix but are whole
+const ROOTS: &[&str] = &[
+    "bier", "disk", "diskut", "distanc", "ekonomi", "ekskurs", "ekster", "ekzempl", "ekzist",
+    "fil", "film", "fizik", "filozof", "gener", "geografi", "gest", "kant", "lecion", "mister",
+    "nacion", "plant", "rapid", "real", "reg", "region", "regul", "rekomend", "rekord", "rekt",
+    "religi", "respond", "restoraci", "rezult",
+];
+
+/// One part of a word, as found by [`segment`](fn.segment.html)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Morpheme<'a> {
+    /// An affix before the root, like "mal"
+    Prefix(&'a str),
+    /// The root, which may itself be a compound of several roots
+    Root(&'a str),
+    /// An affix after the root, like "ul" or "ej"
+    Suffix(&'a str),
+    /// The grammatical ending, like "o" or "ojn"
+    Ending(&'a str),
+}
+
+/// `word` in lowercase, with the same byte offsets
+fn lowercase(word: &str) -> String {
+    word.chars()
+        .map(|c| match c.to_lowercase().next() {
+            Some(lower) if lower.len_utf8() == c.len_utf8() => lower,
+            _ => c,
+        })
+        .collect()
+}
+
+/// Whether `part` could be a root on its own
+pub(crate) fn is_root_sized(part: &str) -> bool {
+    part.chars().count() >= 2 && part.contains(|c| "aeiou".contains(c))
+}
+
+/// The length in bytes of the grammatical ending of a lowercase word
+pub(crate) fn ending_len(lower: &str) -> usize {
+    ENDINGS
+        .iter()
+        .find(|e| lower.strip_suffix(*e).is_some_and(is_root_sized))
+        .map_or(0, |e| e.len())
+}
+
+/// Remove the grammatical ending from a UTF-8 word
+///
+/// Words too short to have a separate ending, like "la" and "kaj", are
+/// returned as they are.
+///
+/// ```
+/// use esperanto_text::stem;
+///
+/// assert_eq!(stem("domojn"), "dom");
+/// assert_eq!(stem("Lernas"), "Lern");
+/// assert_eq!(stem("kaj"), "kaj");
+/// ```
+pub fn stem(word: &str) -> &str {
+    &word[..word.len() - ending_len(&lowercase(word))]
+}
+
+/// Split a UTF-8 word into prefixes, a root, suffixes and an ending
+///
+/// This uses lists of the affixes and of common roots that only look like
+/// they contain one, so an unusual root may occasionally be split too far.
+/// A compound of two roots, like "vaporŝipo", is returned as one root.
+///
+/// ```
+/// use esperanto_text::{segment, Morpheme};
+///
+/// assert_eq!(
+///     segment("malsanulejoj"),
+///     [
+///         Morpheme::Prefix("mal"),
+///         Morpheme::Root("san"),
+///         Morpheme::Suffix("ul"),
+///         Morpheme::Suffix("ej"),
+///         Morpheme::Ending("oj"),
+///     ]
+/// );
+/// ```
+pub fn segment(word: &str) -> Vec<Morpheme<'_>> {
+    let lower = lowercase(word);
+    let end = word.len() - ending_len(&lower);
+    let mut start = 0;
+    let mut prefixes = Vec::new();
+    while !ROOTS.iter().any(|r| lower[start..end].starts_with(r)) {
+        match PREFIXES.iter().find(|p| {
+            lower[start..end]
+                .strip_prefix(*p)
+                .is_some_and(|rest| rest.chars().count() >= 3 && is_root_sized(rest))
+        }) {
+            Some(p) => {
+                prefixes.push(Morpheme::Prefix(&word[start..start + p.len()]));
+                start += p.len();
+            }
+            None => break,
+        }
+    }
+    let mut root_end = end;
+    let mut suffixes = Vec::new();
+    while !ROOTS.contains(&&lower[start..root_end]) {
+        match SUFFIXES.iter().find(|s| {
+            lower[start..root_end]
+                .strip_suffix(*s)
+                .is_some_and(is_root_sized)
+        }) {
+            Some(s) => {
+                suffixes.push(Morpheme::Suffix(&word[root_end - s.len()..root_end]));
+                root_end -= s.len();
+            }
+            None => break,
+        }
+    }
+    let mut parts = prefixes;
+    if root_end > start {
+        parts.push(Morpheme::Root(&word[start..root_end]));
+    }
+    parts.extend(suffixes.into_iter().rev());
+    if end < word.len() {
+        parts.push(Morpheme::Ending(&word[end..]));
+    }
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roots(word: &str) -> Vec<&str> {
+        segment(word)
+            .into_iter()
+            .filter_map(|m| match m {
+                Morpheme::Root(r) => Some(r),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_stem() {
+        assert_eq!(stem("belajn"), "bel");
+        assert_eq!(stem("Ĉu"), "Ĉu");
+        assert_eq!(stem("estas"), "est");
+        assert_eq!(stem(""), "");
+    }
+
+    #[test]
+    fn test_segment() {
+        assert_eq!(roots("Lernejo"), ["Lern"]);
+        assert_eq!(roots("rezultoj"), ["rezult"]);
+        assert_eq!(roots("ekzemple"), ["ekzempl"]);
+        assert_eq!(roots("rapida"), ["rapid"]);
+        assert_eq!(roots("regiono"), ["region"]);
+        assert_eq!(roots("domo"), ["dom"]);
+        assert_eq!(
+            segment("reveni"),
+            [Morpheme::Prefix("re"), Morpheme::Root("ven"), Morpheme::Ending("i")]
+        );
+        assert_eq!(segment("kaj"), [Morpheme::Root("kaj")]);
+    }
+}
@@ -0,0 +1,97 @@
+//! Telling "aŭ" apart from an "a" that ends one part of a compound word and
+//! a "u" that begins the next
+//!
+//! In the h-system "praulo" could be "pra" + "ulo" or a word containing
+//! "aŭ". Rather than list every such compound, the word is split at the "au"
+//! and each half is checked: the left must be a whole word part ending in
+//! "a", and the right a root beginning with "u" followed by nothing but
+//! suffixes and grammatical endings.
+
+use crate::token::word_at;
+
+/// Word parts of fewer than four letters that can come before a root
+const SHORT_PARTS: &[&str] = &["dua", "ia", "kia", "pra", "tia", "tra"];
+
+/// Roots and suffixes beginning with "u" that can follow another word part
+const U_ROOTS: &[&str] = &[
+    "uj", "ukrain", "ul", "um", "ung", "uni", "unik", "univers", "unu", "uragan", "urb", "urin",
+    "urs", "uson", "util", "utopi", "uz",
+];
+
+/// Suffixes and endings that can finish a word after a root
+const ENDINGS: &[&str] = &[
+    "a", "ad", "an", "ar", "as", "e", "ebl", "ec", "eg", "ej", "em", "et", "i", "ig", "igh", "in",
+    "is", "ist", "j", "n", "o", "os", "u", "us",
+];
+
+/// Roots containing "aŭ", written in the h-system, that would otherwise look
+/// like a split
+const AU_ROOTS: &[&str] = &[
+    "fraul", "kauz", "klauz", "nauz", "pauz", "shaum", "tauz", "traum",
+];
+
+/// Whether the "au" at byte `pos` of `s` straddles two parts of a compound
+/// word, and so is not "aŭ"
+pub(crate) fn splits_au(s: &str, pos: usize) -> bool {
+    let word = word_at(s, pos);
+    let left = s[word.start..pos + 1].to_lowercase();
+    let right = s[pos + 1..word.end].to_lowercase();
+    let is_au_root = AU_ROOTS.iter().any(|root| {
+        let (before, after) = root.split_at(root.find("au").unwrap_or(0) + 1);
+        left.ends_with(before) && right.starts_with(after)
+    });
+    if is_au_root {
+        return false;
+    }
+    (left.chars().count() >= 4 || SHORT_PARTS.contains(&left.as_str()))
+        && U_ROOTS
+            .iter()
+            .any(|root| right.strip_prefix(root).is_some_and(only_endings))
+}
+
+/// Whether `s` is made up of nothing but suffixes and endings
+fn only_endings(s: &str) -> bool {
+    s.is_empty()
+        || ENDINGS
+            .iter()
+            .any(|e| s.strip_prefix(e).is_some_and(only_endings))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn splits(word: &str) -> bool {
+        splits_au(word, word.find("au").unwrap())
+    }
+
+    #[test]
+    fn test_compounds_split() {
+        for word in &[
+            "praulo",
+            "unuaulo",
+            "Grandaursino",
+            "doganaunio",
+            "kakaujo",
+            "tiauloj",
+        ] {
+            assert!(splits(word), "{}", word);
+        }
+    }
+
+    #[test]
+    fn test_au_kept() {
+        for word in &[
+            "ankau",
+            "antauulo",
+            "kauzi",
+            "ekkauzi",
+            "gefrauloj",
+            "traumo",
+            "sapshaumo",
+            "laudi",
+        ] {
+            assert!(!splits(word), "{}", word);
+        }
+    }
+}
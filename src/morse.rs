@@ -0,0 +1,144 @@
+//! Morse code, including the Esperanto codes for the hatted letters
+
+/// The code for each character, written with '.' and '-'
+const CODES: &[(char, &str)] = &[
+    ('a', ".-"), ('b', "-..."), ('c', "-.-."), ('ĉ', "-.-.."), ('d', "-.."), ('e', "."),
+    ('f', "..-."), ('g', "--."), ('ĝ', "--.-."), ('h', "...."), ('ĥ', "----"), ('i', ".."),
+    ('j', ".---"), ('ĵ', ".---."), ('k', "-.-"), ('l', ".-.."), ('m', "--"), ('n', "-."),
+    ('o', "---"), ('p', ".--."), ('q', "--.-"), ('r', ".-."), ('s', "..."), ('ŝ', "...-."),
+    ('t', "-"), ('u', "..-"), ('ŭ', "..--"), ('v', "...-"), ('w', ".--"), ('x', "-..-"),
+    ('y', "-.--"), ('z', "--.."),
+    ('0', "-----"), ('1', ".----"), ('2', "..---"), ('3', "...--"), ('4', "....-"),
+    ('5', "....."), ('6', "-...."), ('7', "--..."), ('8', "---.."), ('9', "----."),
+    ('.', ".-.-.-"), (',', "--..--"), ('?', "..--.."), ('\'', ".----."), ('!', "-.-.--"),
+    ('/', "-..-."), ('(', "-.--."), (')', "-.--.-"), (':', "---..."), (';', "-.-.-."),
+    ('=', "-...-"), ('+', ".-.-."), ('-', "-....-"), ('"', ".-..-."), ('@', ".--.-."),
+];
+
+/// Converts between UTF-8 text and Morse code, with chosen separators
+///
+/// By default letters are separated by a space and words by " / ".
+///
+/// ```
+/// use esperanto_text::Morse;
+///
+/// let morse = Morse::new().letter_separator("|").word_separator(" ");
+/// assert_eq!(morse.encode("ĉu ŝi"), "-.-..|..- ...-.|..");
+/// assert_eq!(morse.decode("-.-..|..- ...-.|.."), "ĉu ŝi");
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Morse {
+    letter_separator: String,
+    word_separator: String,
+}
+
+impl Default for Morse {
+    fn default() -> Morse {
+        Morse {
+            letter_separator: " ".to_owned(),
+            word_separator: " / ".to_owned(),
+        }
+    }
+}
+
+impl Morse {
+    /// Use the default separators
+    pub fn new() -> Morse {
+        Morse::default()
+    }
+
+    /// Set what goes between the letters of a word
+    pub fn letter_separator(mut self, separator: &str) -> Morse {
+        self.letter_separator = separator.to_owned();
+        self
+    }
+
+    /// Set what goes between words
+    pub fn word_separator(mut self, separator: &str) -> Morse {
+        self.word_separator = separator.to_owned();
+        self
+    }
+
+    /// Write `s` in Morse code
+    ///
+    /// Case is lost, and characters that have no code are left out.
+    pub fn encode(&self, s: &str) -> String {
+        s.split_whitespace()
+            .map(|word| {
+                let codes: Vec<&str> = word.chars().filter_map(code).collect();
+                codes.join(&self.letter_separator)
+            })
+            .filter(|word| !word.is_empty())
+            .collect::<Vec<_>>()
+            .join(&self.word_separator)
+    }
+
+    /// Read Morse code back as lowercase text
+    ///
+    /// Dots may also be written '·' or '•', and dashes '−', '–', '—' or '_'.
+    /// A code that isn't known becomes U+FFFD.
+    pub fn decode(&self, s: &str) -> String {
+        let s: String = s
+            .chars()
+            .map(|c| match c {
+                '·' | '•' => '.',
+                '−' | '–' | '—' | '_' => '-',
+                _ => c,
+            })
+            .collect();
+        let decode_word = |word: &str| -> String {
+            word.split(self.letter_separator.as_str())
+                .map(str::trim)
+                .filter(|code| !code.is_empty())
+                .map(|code| {
+                    CODES
+                        .iter()
+                        .find(|(_, c)| *c == code)
+                        .map_or(char::REPLACEMENT_CHARACTER, |(letter, _)| *letter)
+                })
+                .collect()
+        };
+        s.split(self.word_separator.as_str())
+            .map(|word| decode_word(word.trim()))
+            .filter(|word| !word.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// The code for `c`, if it has one
+fn code(c: char) -> Option<&'static str> {
+    let lower = c.to_lowercase().next().unwrap_or(c);
+    CODES.iter().find(|(l, _)| *l == lower).map(|(_, code)| *code)
+}
+
+/// Write UTF-8 text in Morse code, separating letters with a space and words
+/// with " / "
+///
+/// ```
+/// assert_eq!(esperanto_text::utf8_to_morse("Ĝis!"), "--.-. .. ... -.-.--");
+/// ```
+pub fn utf8_to_morse(s: &str) -> String {
+    Morse::new().encode(s)
+}
+
+/// Read Morse code with a space between letters and " / " between words
+///
+/// ```
+/// assert_eq!(esperanto_text::morse_to_utf8("..-- / ----"), "ŭ ĥ");
+/// ```
+pub fn morse_to_utf8(s: &str) -> String {
+    Morse::new().decode(s)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let text = "ĉiuĵaŭde la 3a, ĉu?";
+        assert_eq!(morse_to_utf8(&utf8_to_morse(text)), text);
+        assert_eq!(morse_to_utf8("·−  ........ / ·−−·−·"), "a\u{FFFD} @");
+    }
+}
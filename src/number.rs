@@ -0,0 +1,343 @@
+//! Writing numbers out in Esperanto words
+
+use crate::canonical;
+use crate::{convert, System};
+
+const UNITS: &[&str] = &[
+    "", "unu", "du", "tri", "kvar", "kvin", "ses", "sep", "ok", "naŭ",
+];
+
+/// The names of each power of a million, from a million up
+const SCALES: &[&str] = &["miliono", "miliardo", "biliono", "biliardo", "triliono"];
+
+/// The value of each part that number words are made from, longest first
+///
+/// The powers of a million are given without their noun ending so that
+/// "miliono", "milionoj" and the ordinal "miliona" are all recognised.
+const PARTS: &[(&str, u64)] = &[
+    ("trilion", 1_000_000_000_000_000_000),
+    ("biliard", 1_000_000_000_000_000),
+    ("bilion", 1_000_000_000_000),
+    ("miliard", 1_000_000_000),
+    ("milion", 1_000_000),
+    ("cent", 100),
+    ("kvar", 4),
+    ("kvin", 5),
+    ("dek", 10),
+    ("mil", 1000),
+    ("naŭ", 9),
+    ("nau", 9),
+    ("nul", 0),
+    ("sep", 7),
+    ("ses", 6),
+    ("tri", 3),
+    ("unu", 1),
+    ("du", 2),
+    ("ok", 8),
+];
+
+/// Append the words for `n`, which must be below a thousand
+fn push_hundreds(n: u64, words: &mut Vec<String>) {
+    let (hundreds, tens, units) = (
+        (n / 100) as usize,
+        (n / 10 % 10) as usize,
+        (n % 10) as usize,
+    );
+    if hundreds > 0 {
+        words.push(format!(
+            "{}cent",
+            if hundreds > 1 { UNITS[hundreds] } else { "" }
+        ));
+    }
+    if tens > 0 {
+        words.push(format!("{}dek", if tens > 1 { UNITS[tens] } else { "" }));
+    }
+    if units > 0 {
+        words.push(UNITS[units].to_owned());
+    }
+}
+
+/// The words for `n`, one number word per item
+fn words(n: u64) -> Vec<String> {
+    if n == 0 {
+        return vec!["nul".to_owned()];
+    }
+    let mut words = Vec::new();
+    // Millions and above are nouns, as in "du milionoj"
+    let mut rest = n;
+    for (i, name) in SCALES.iter().enumerate().rev() {
+        let scale = 1000u64.pow(i as u32 + 2);
+        let count = rest / scale;
+        if count > 0 {
+            words.extend(self::words(count));
+            let plural = if count > 1 { "j" } else { "" };
+            words.push(format!("{}{}", name, plural));
+        }
+        rest %= scale;
+    }
+    let thousands = rest / 1000;
+    if thousands > 1 {
+        push_hundreds(thousands, &mut words);
+    }
+    if thousands > 0 {
+        words.push("mil".to_owned());
+    }
+    push_hundreds(rest % 1000, &mut words);
+    words
+}
+
+/// Write a number out in UTF-8 Esperanto words
+///
+/// ```
+/// use esperanto_text::number_to_words;
+///
+/// assert_eq!(number_to_words(123), "cent dudek tri");
+/// assert_eq!(number_to_words(2019), "du mil dek naŭ");
+/// assert_eq!(number_to_words(3_000_001), "tri milionoj unu");
+/// ```
+pub fn number_to_words(n: u64) -> String {
+    words(n).join(" ")
+}
+
+/// Write a number out in Esperanto words in the given system
+///
+#[cfg_attr(feature = "x-system", doc = "```")]
+#[cfg_attr(not(feature = "x-system"), doc = "```ignore")]
+/// use esperanto_text::{number_to_words_in, System};
+///
+/// assert_eq!(number_to_words_in(9, System::X), "naux");
+/// ```
+pub fn number_to_words_in(n: u64, to: System) -> String {
+    convert(&number_to_words(n), System::Utf8, to)
+}
+
+/// Write an ordinal number out in UTF-8 Esperanto words
+///
+/// The number words are joined with hyphens and given the adjective ending.
+///
+/// ```
+/// use esperanto_text::ordinal_to_words;
+///
+/// assert_eq!(ordinal_to_words(3), "tria");
+/// assert_eq!(ordinal_to_words(21), "dudek-unua");
+/// assert_eq!(ordinal_to_words(1_000_000), "unu-miliona");
+/// ```
+pub fn ordinal_to_words(n: u64) -> String {
+    let cardinal = words(n).join("-");
+    let stem = cardinal.trim_end_matches('j').trim_end_matches('o');
+    format!("{}a", stem)
+}
+
+/// Write an ordinal number out in Esperanto words in the given system
+pub fn ordinal_to_words_in(n: u64, to: System) -> String {
+    convert(&ordinal_to_words(n), System::Utf8, to)
+}
+
+/// Split a lowercase number word into the values of its parts, allowing a
+/// noun or adjective ending after the last one
+///
+/// Each value is paired with whether it was given a plural ending.
+fn parts(word: &str, values: &mut Vec<(u64, bool)>) -> Option<()> {
+    let mut rest = word;
+    while !rest.is_empty() {
+        match PARTS.iter().find(|(part, _)| rest.starts_with(part)) {
+            Some((part, value)) => {
+                values.push((*value, false));
+                rest = &rest[part.len()..];
+            }
+            None => {
+                let ending = rest.trim_start_matches(['o', 'a']);
+                let ending_ok = ending.len() < rest.len()
+                    && rest.len() - ending.len() == 1
+                    && matches!(ending, "" | "j" | "n" | "jn");
+                if let Some(last) = values.last_mut() {
+                    last.1 = ending.starts_with('j');
+                }
+                return if ending_ok { Some(()) } else { None };
+            }
+        }
+    }
+    Some(())
+}
+
+/// Read a number written in Esperanto words, in any system
+///
+/// The words may be separated by spaces or hyphens or written together, and
+/// ordinals like "dudek-tria" give the number they are based on. Returns
+/// `None` if anything isn't a number word, if the words aren't in an order
+/// that writes a number, as in "mil mil" or "dudek dek", if a plural like
+/// "milionoj" has no count of more than one, or if the number is too large.
+///
+#[cfg_attr(feature = "x-system", doc = "```")]
+#[cfg_attr(not(feature = "x-system"), doc = "```ignore")]
+/// use esperanto_text::words_to_number;
+///
+/// assert_eq!(words_to_number("du mil dudek kvar"), Some(2024));
+/// assert_eq!(words_to_number("NAUXCENT"), Some(900));
+/// assert_eq!(words_to_number("la tria"), None);
+/// ```
+pub fn words_to_number(s: &str) -> Option<u64> {
+    let text: String = canonical::chars(s).flat_map(char::to_lowercase).collect();
+    let mut values = Vec::new();
+    for word in text.split(|c: char| c.is_whitespace() || c == '-') {
+        parts(word, &mut values)?;
+    }
+    if values.is_empty() {
+        return None;
+    }
+    // Units multiply the part after them, as in "dudek" and "du mil". Within
+    // each group below a thousand, and between the powers of a million, each
+    // part must be smaller than the one before.
+    let (mut total, mut thousands, mut hundreds, mut unit) = (0u64, None, 0u64, None);
+    let (mut smallest_ten, mut smallest_scale) = (u64::MAX, u64::MAX);
+    for (value, plural) in values {
+        match value {
+            0..=9 if unit.is_some() => return None,
+            0..=9 => unit = Some(value),
+            10 | 100 if value >= smallest_ten => return None,
+            10 | 100 => {
+                hundreds += unit.take().unwrap_or(1) * value;
+                smallest_ten = value;
+            }
+            1000 if thousands.is_some() => return None,
+            1000 => {
+                let count = hundreds + unit.take().unwrap_or(0);
+                thousands = Some(count.max(1) * 1000);
+                hundreds = 0;
+                smallest_ten = u64::MAX;
+            }
+            _ if value >= smallest_scale || thousands.is_some() => return None,
+            _ => {
+                let count = hundreds + unit.take().unwrap_or(0);
+                if plural && count <= 1 {
+                    return None;
+                }
+                total = total.checked_add(count.max(1).checked_mul(value)?)?;
+                hundreds = 0;
+                smallest_ten = u64::MAX;
+                smallest_scale = value;
+            }
+        }
+    }
+    total.checked_add(thousands.unwrap_or(0) + hundreds + unit.unwrap_or(0))
+}
+
+/// Replace ordinals written with digits, like "3-a" or "21an", with words
+///
+/// Any "j" or "n" after the adjective ending is kept, so "la 2-ajn" becomes
+/// "la duajn". Digits without an ordinal ending are left as they are.
+///
+/// ```
+/// use esperanto_text::expand_ordinals;
+///
+/// assert_eq!(expand_ordinals("la 3-a de majo, 2020"), "la tria de majo, 2020");
+/// ```
+pub fn expand_ordinals(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(|c: char| c.is_ascii_digit()) {
+        let digits_end = rest[start..]
+            .find(|c: char| !c.is_ascii_digit())
+            .map_or(rest.len(), |i| start + i);
+        let after = &rest[digits_end..];
+        let marker = if after.starts_with("-a") {
+            2
+        } else {
+            usize::from(after.starts_with('a'))
+        };
+        let suffix_end = marker
+            + after[marker..]
+                .find(|c: char| c != 'j' && c != 'n')
+                .map_or(after.len() - marker, |i| i);
+        let suffix = &after[marker..suffix_end];
+        let whole_word = !after[suffix_end..].starts_with(char::is_alphanumeric);
+        let preceded = rest[..start].ends_with(char::is_alphanumeric);
+        match rest[start..digits_end].parse::<u64>() {
+            Ok(n) if marker > 0 && suffix.len() <= 2 && whole_word && !preceded => {
+                out.push_str(&rest[..start]);
+                out.push_str(&ordinal_to_words(n));
+                out.push_str(suffix);
+                rest = &after[suffix_end..];
+            }
+            _ => {
+                out.push_str(&rest[..digits_end]);
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_number_to_words() {
+        assert_eq!(number_to_words(0), "nul");
+        assert_eq!(number_to_words(10), "dek");
+        assert_eq!(number_to_words(1000), "mil");
+        assert_eq!(number_to_words(1999), "mil naŭcent naŭdek naŭ");
+        assert_eq!(number_to_words(512_000), "kvincent dek du mil");
+        assert_eq!(number_to_words(1_000_000_000), "unu miliardo");
+        assert_eq!(
+            number_to_words(u64::MAX),
+            "dek ok trilionoj kvarcent kvardek ses biliardoj sepcent kvardek kvar bilionoj \
+             sepdek tri miliardoj sepcent naŭ milionoj kvincent kvindek unu mil sescent \
+             dek kvin"
+        );
+    }
+
+    #[test]
+    fn test_words_to_number() {
+        for n in &[
+            0,
+            7,
+            10,
+            19,
+            100,
+            123,
+            1000,
+            1999,
+            512_000,
+            3_000_001,
+            u64::MAX,
+        ] {
+            assert_eq!(words_to_number(&number_to_words(*n)), Some(*n));
+            assert_eq!(words_to_number(&ordinal_to_words(*n)), Some(*n));
+        }
+        assert_eq!(words_to_number("dudektri"), Some(23));
+        assert_eq!(words_to_number("du milionojn"), Some(2_000_000));
+        assert_eq!(words_to_number("nauh"), None);
+        assert_eq!(words_to_number("du tri"), None);
+        assert_eq!(words_to_number(""), None);
+        assert_eq!(words_to_number("dudek trilionoj"), None);
+    }
+
+    #[test]
+    fn test_words_out_of_order() {
+        assert_eq!(words_to_number("mil mil"), None);
+        assert_eq!(words_to_number("cent cent"), None);
+        assert_eq!(words_to_number("dudek dek"), None);
+        assert_eq!(words_to_number("dek cent"), None);
+        assert_eq!(words_to_number("miliono miliardo"), None);
+        assert_eq!(words_to_number("mil milionoj"), None);
+        assert_eq!(words_to_number("milionoj"), None);
+        assert_eq!(words_to_number("unu milionoj"), None);
+        assert_eq!(words_to_number("miliono"), Some(1_000_000));
+        assert_eq!(words_to_number("du milionoj mil"), Some(2_001_000));
+        assert_eq!(words_to_number("cent mil"), Some(100_000));
+    }
+
+    #[cfg(feature = "x-system")]
+    #[test]
+    fn test_ordinals() {
+        assert_eq!(ordinal_to_words(1000), "mila");
+        assert_eq!(ordinal_to_words_in(9, System::X), "nauxa");
+        assert_eq!(
+            expand_ordinals("la 1-an, la 22aj, 3ab kaj x4-a"),
+            "la unuan, la dudek-duaj, 3ab kaj x4-a"
+        );
+    }
+}
@@ -0,0 +1,202 @@
+//! Repairing hatted letters that OCR software misread
+
+use std::sync::OnceLock;
+
+use crate::letter::Letter;
+use crate::matcher::Matcher;
+use crate::pipeline::MatchSpeed;
+
+/// Common OCR misreadings of Esperanto text and what they stand for
+///
+/// Patterns made only of ASCII letters are whole words that lost the breve
+/// of their final "ŭ". The ASCII letters of every pattern match in either
+/// case.
+pub const OCR_CONFUSIONS: &[(&str, &str)] = &[
+    // ĉ
+    ("6", "ĉ"), ("é", "ĉ"), ("É", "Ĉ"), ("ć", "ĉ"), ("Ć", "Ĉ"),
+    // ĝ
+    ("g\u{300}", "ĝ"), ("ğ", "ĝ"), ("Ğ", "Ĝ"), ("ǵ", "ĝ"), ("Ǵ", "Ĝ"),
+    // ĥ
+    ("h\u{300}", "ĥ"), ("ħ", "ĥ"), ("Ħ", "Ĥ"),
+    // ĵ
+    ("j\u{300}", "ĵ"),
+    // ŝ
+    ("s\u{300}", "ŝ"), ("ś", "ŝ"), ("Ś", "Ŝ"), ("ş", "ŝ"), ("Ş", "Ŝ"),
+    // ŭ
+    ("û", "ŭ"), ("Û", "Ŭ"), ("ù", "ŭ"), ("Ù", "Ŭ"), ("ü", "ŭ"), ("Ü", "Ŭ"),
+    ("au", "aŭ"), ("ankau", "ankaŭ"), ("antau", "antaŭ"), ("adiau", "adiaŭ"), ("ambau", "ambaŭ"),
+    ("baldau", "baldaŭ"), ("hierau", "hieraŭ"), ("kvazau", "kvazaŭ"), ("morgau", "morgaŭ"),
+    ("preskau", "preskaŭ"),
+];
+
+/// Repairs the misreadings in a table of OCR confusions
+///
+/// The table starts as [`OCR_CONFUSIONS`](constant.OCR_CONFUSIONS.html)
+/// and more can be added for a particular scanner or typeface. A pattern of
+/// ASCII letters is only replaced as a whole word. Any other pattern is only
+/// replaced next to a letter and never next to a digit, so numbers and
+/// ordinals like "6a" stay as they are. Letters with a circumflex are not
+/// put at the end of a word or in a word with "q", "w", "x" or "y", which
+/// keeps names like "Pelé" and "Québec".
+///
+/// ```
+/// use esperanto_text::OcrRepair;
+///
+/// let repair = OcrRepair::new().with("8", "ŝ");
+/// assert_eq!(repair.repair("8i iris 6e la 8a?"), "ŝi iris ĉe la 8a?");
+/// ```
+#[derive(Default)]
+pub struct OcrRepair {
+    table: Vec<(String, String)>,
+    matcher: OnceLock<Matcher>,
+}
+
+impl OcrRepair {
+    /// Use the built-in table of confusions
+    pub fn new() -> OcrRepair {
+        OcrRepair::empty().with_table(OCR_CONFUSIONS)
+    }
+
+    /// Start with no confusions at all
+    pub fn empty() -> OcrRepair {
+        OcrRepair::default()
+    }
+
+    /// Also replace `from` with `to`
+    pub fn with(mut self, from: &str, to: &str) -> OcrRepair {
+        if !from.is_empty() {
+            self.table.push((from.to_owned(), to.to_owned()));
+            self.matcher = OnceLock::new();
+        }
+        self
+    }
+
+    /// Also make every replacement in `table`
+    pub fn with_table(self, table: &[(&str, &str)]) -> OcrRepair {
+        table
+            .iter()
+            .fold(self, |repair, (from, to)| repair.with(from, to))
+    }
+
+    /// Repair the misreadings in `s`
+    ///
+    /// Anything that doesn't match the table is left alone.
+    pub fn repair(&self, s: &str) -> String {
+        let matcher = self.matcher.get_or_init(|| {
+            let patterns: Vec<&str> = self.table.iter().map(|(from, _)| from.as_str()).collect();
+            Matcher::new(&patterns, MatchSpeed::Compact)
+        });
+        let mut out = String::with_capacity(s.len());
+        let mut last = 0;
+        for m in matcher.find_iter(s) {
+            let found = &s[m.start()..m.end()];
+            let replacement = &self.table[m.pattern()].1;
+            if !replaceable(s, m.start(), m.end(), replacement) {
+                continue;
+            }
+            out.push_str(&s[last..m.start()]);
+            push_matching_case(replacement, found, &mut out);
+            last = m.end();
+        }
+        out.push_str(&s[last..]);
+        out
+    }
+}
+
+/// Whether `s[start..end]` is in a place where it should be replaced with
+/// `replacement`
+fn replaceable(s: &str, start: usize, end: usize, replacement: &str) -> bool {
+    let (found, rest) = (&s[start..end], &s[end..]);
+    let before = s[..start].chars().next_back();
+    let after = rest.chars().next();
+    // The letters of the word that follow
+    let letters_after = rest
+        .find(|c: char| !c.is_alphabetic())
+        .unwrap_or(rest.len());
+    let is_letter = |c: Option<char>| c.is_some_and(char::is_alphabetic);
+    let is_digit = |c: Option<char>| c.is_some_and(|c| c.is_ascii_digit());
+    if found.bytes().all(|b| b.is_ascii_alphabetic()) {
+        return !is_letter(before) && !is_letter(after);
+    }
+    if is_digit(before) || is_digit(after) || !is_letter(before) && !is_letter(after) {
+        return false;
+    }
+    // No Esperanto word ends in a letter with a circumflex, so one there is
+    // more likely part of a name like "Pelé"
+    let circumflex = replacement
+        .chars()
+        .next_back()
+        .and_then(Letter::from_hatted)
+        .is_some_and(|(letter, _)| letter != Letter::U);
+    if circumflex && !is_letter(after) {
+        return false;
+    }
+    // Nor does one have a "q", "w", "x" or "y", as "Québec" does
+    let word_start = s[..start]
+        .char_indices()
+        .rev()
+        .find(|(_, c)| !c.is_alphabetic())
+        .map_or(0, |(i, c)| i + c.len_utf8());
+    if s[word_start..end + letters_after]
+        .chars()
+        .any(|c| matches!(c.to_ascii_lowercase(), 'q' | 'w' | 'x' | 'y'))
+    {
+        return false;
+    }
+    // An ordinal like "6a" or "6ajn" is a number, not a word
+    let ordinal = found.starts_with(|c: char| c.is_ascii_digit())
+        && !is_letter(before)
+        && ["a", "aj", "an", "ajn"].contains(&&rest[..letters_after]);
+    !ordinal
+}
+
+/// Push `replacement`, capitalised like `found` if it starts with a capital
+fn push_matching_case(replacement: &str, found: &str, out: &mut String) {
+    let letters = found.chars().filter(|c| c.is_ascii_alphabetic()).count();
+    if letters > 1 && !found.chars().any(|c| c.is_ascii_lowercase()) {
+        out.push_str(&replacement.to_uppercase());
+    } else if found.starts_with(|c: char| c.is_ascii_uppercase()) {
+        let mut chars = replacement.chars();
+        out.extend(chars.next().into_iter().flat_map(char::to_uppercase));
+        out.push_str(chars.as_str());
+    } else {
+        out.push_str(replacement);
+    }
+}
+
+/// Repair common OCR misreadings of hatted letters, like "6" or "é" for "ĉ"
+///
+/// This uses the built-in table of confusions. See
+/// [`OcrRepair`](struct.OcrRepair.html) to add more.
+///
+/// ```
+/// use esperanto_text::fix_ocr;
+///
+/// assert_eq!(
+///     fix_ocr("6iuj venis, Ankau la g\u{300}ardenisto."),
+///     "ĉiuj venis, Ankaŭ la ĝardenisto."
+/// );
+/// assert_eq!(fix_ocr("En 1996 mi havis 6 katojn"), "En 1996 mi havis 6 katojn");
+/// ```
+pub fn fix_ocr(s: &str) -> String {
+    static REPAIR: OnceLock<OcrRepair> = OnceLock::new();
+    REPAIR.get_or_init(OcrRepair::new).repair(s)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fix_ocr() {
+        assert_eq!(
+            fix_ocr("AU la s\u{300}ipo 6e la haveno"),
+            "AŬ la ŝipo ĉe la haveno"
+        );
+        assert_eq!(fix_ocr("Laura kaj Nassau"), "Laura kaj Nassau");
+        assert_eq!(fix_ocr("adiaû, 3û kaj 6-a"), "adiaŭ, 3û kaj 6-a");
+        let names = "André kaj Pelé trinkis en la Café de Québec";
+        assert_eq!(fix_ocr(names), names);
+        assert_eq!(fix_ocr("éiuj ŝipoj"), "ĉiuj ŝipoj");
+    }
+}
@@ -0,0 +1,146 @@
+//! Finding characters and spellings that don't belong in Esperanto text
+
+use std::ops::Range;
+
+use crate::letter::Letter;
+
+/// A problem found by [`validate_orthography`](fn.validate_orthography.html)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Issue {
+    /// The byte range of the problem in the text
+    pub range: Range<usize>,
+    /// What is wrong there
+    pub kind: IssueKind,
+}
+
+/// The kinds of problem found by [`validate_orthography`](fn.validate_orthography.html)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum IssueKind {
+    /// A letter that isn't in the Esperanto alphabet, like "q", "w" or "y",
+    /// or an "x" that isn't part of an x-system digraph
+    ForeignLetter,
+    /// A letter with a diacritic from another language, like "č" or "é"
+    ForeignDiacritic,
+    /// A letter marked twice, like "cxh" or "ĉx"
+    MalformedDigraph,
+}
+
+/// Whether `c` is a lowercase letter of the Esperanto alphabet
+fn is_esperanto(c: char) -> bool {
+    "abcĉdefgĝhĥijĵklmnoprsŝtuŭvz".contains(c)
+}
+
+/// Whether `c` is a Latin letter, with or without a diacritic
+fn is_latin(c: char) -> bool {
+    c.is_ascii_alphabetic() || ('\u{C0}'..='\u{24F}').contains(&c) && c.is_alphabetic()
+}
+
+/// Find the characters and spellings in `s` that don't belong in Esperanto
+///
+/// UTF-8, the x-system and the h-system are all accepted, as are letters
+/// written with a combining circumflex or breve.
+///
+/// ```
+/// use esperanto_text::{validate_orthography, IssueKind};
+///
+/// let issues = validate_orthography("Ĉu vi ŝatas kafé? cxhiam!");
+/// let kinds: Vec<IssueKind> = issues.iter().map(|i| i.kind).collect();
+/// assert_eq!(kinds, [IssueKind::ForeignDiacritic, IssueKind::MalformedDigraph]);
+/// assert_eq!(issues[1].range, 21..24);
+/// ```
+pub fn validate_orthography(s: &str) -> Vec<Issue> {
+    let chars: Vec<(usize, char)> = s.char_indices().collect();
+    let lower = |i: usize| {
+        chars
+            .get(i)
+            .map(|(_, c)| c.to_lowercase().next().unwrap_or(*c))
+    };
+    let end = |i: usize| chars.get(i).map_or(s.len(), |(p, _)| *p);
+    let mut issues = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let (start, c) = chars[i];
+        let c = c.to_lowercase().next().unwrap_or(c);
+        let marked = match (c, lower(i + 1)) {
+            (base, Some('x')) if Letter::from_base(base).is_some() => Some(2),
+            (hatted, _) if Letter::from_hatted(hatted).is_some() => Some(1),
+            (base, Some('\u{302}')) if "cghjs".contains(base) => Some(2),
+            ('u', Some('\u{306}')) => Some(2),
+            _ => None,
+        };
+        if let Some(len) = marked {
+            // Another marker straight after a letter that already has one
+            let extra = match lower(i + len) {
+                Some('x') => 1,
+                Some('h') if len == 2 && lower(i + 1) == Some('x') => 1,
+                Some('\u{302}') | Some('\u{306}') => 1,
+                _ => 0,
+            };
+            if extra > 0 {
+                let range = start..end(i + len + extra);
+                issues.push(Issue {
+                    range,
+                    kind: IssueKind::MalformedDigraph,
+                });
+            }
+            i += len + extra;
+            continue;
+        }
+        let kind = match c {
+            c if is_esperanto(c) || !c.is_alphabetic() => None,
+            '\u{300}'..='\u{36F}' => Some(IssueKind::ForeignDiacritic),
+            c if c.is_ascii() => Some(IssueKind::ForeignLetter),
+            c if is_latin(c) => Some(IssueKind::ForeignDiacritic),
+            _ => Some(IssueKind::ForeignLetter),
+        };
+        // A combining mark other than the circumflex and breve handled above
+        let kind = match lower(i + 1) {
+            Some('\u{300}'..='\u{36F}') if kind.is_none() && is_esperanto(c) => {
+                i += 1;
+                Some(IssueKind::ForeignDiacritic)
+            }
+            _ => kind,
+        };
+        if let Some(kind) = kind {
+            issues.push(Issue {
+                range: start..end(i + 1),
+                kind,
+            });
+        }
+        i += 1;
+    }
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(s: &str) -> Vec<(&str, IssueKind)> {
+        validate_orthography(s)
+            .into_iter()
+            .map(|issue| (&s[issue.range], issue.kind))
+            .collect()
+    }
+
+    #[test]
+    fn test_valid_text() {
+        assert!(kinds("Ĉiuĵaŭde sxi chiam venas, c\u{302}u? 42!").is_empty());
+        assert!(kinds("AUXTO kaj ŬO").is_empty());
+    }
+
+    #[test]
+    fn test_issues() {
+        assert_eq!(
+            kinds("weekend, taxi, Čapek, e\u{301}, ĉx, SXH"),
+            [
+                ("w", IssueKind::ForeignLetter),
+                ("x", IssueKind::ForeignLetter),
+                ("Č", IssueKind::ForeignDiacritic),
+                ("e\u{301}", IssueKind::ForeignDiacritic),
+                ("ĉx", IssueKind::MalformedDigraph),
+                ("SXH", IssueKind::MalformedDigraph),
+            ]
+        );
+    }
+}
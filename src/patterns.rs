@@ -0,0 +1,100 @@
+/*!
+The tables of patterns the converters match, for tools that need to agree
+with this crate.
+
+Every pattern is lowercase and matched without regard to ASCII case, except
+for [`utf8`](fn.utf8.html), which lists both cases. Exception fragments are
+parts of words that look like digraphs but aren't, and are left alone when
+they appear anywhere in a word unless
+[`anchored_exceptions`](fn.anchored_exceptions.html) says otherwise.
+
+*/
+#![cfg_attr(all(feature = "x-system", feature = "h-system"), doc = "```")]
+#![cfg_attr(not(all(feature = "x-system", feature = "h-system")), doc = "```ignore")]
+/*!
+use esperanto_text::patterns;
+
+assert!(patterns::x_system().contains(&"cx"));
+assert!(patterns::h_exceptions().contains(&"ashund"));
+```
+*/
+
+/// The x-system digraphs, like "cx"
+#[cfg(feature = "x-system")]
+pub fn x_system() -> &'static [&'static str] {
+    crate::FROM_X_CI
+}
+
+/// Fragments of foreign words containing "x", like "taxi", that aren't
+/// x-system digraphs
+#[cfg(feature = "x-system")]
+pub fn x_exceptions() -> &'static [&'static str] {
+    crate::X_EXCEPTIONS_CI
+}
+
+/// The h-system digraphs, like "ch", and the "au" that is read as "aŭ"
+#[cfg(feature = "h-system")]
+pub fn h_system() -> &'static [&'static str] {
+    let (h, au) = crate::H_EXCEPTION_COUNTS;
+    &crate::FROM_H_CI[h + au..]
+}
+
+/// Fragments of words where an "h" after a letter isn't a digraph, like
+/// the "sh" of "dishakigi"
+#[cfg(feature = "h-system")]
+pub fn h_exceptions() -> &'static [&'static str] {
+    &crate::FROM_H_CI[..crate::H_EXCEPTION_COUNTS.0]
+}
+
+/// Fragments of words where "au" isn't read as "aŭ" in the h-system
+#[cfg(feature = "h-system")]
+pub fn au_exceptions() -> &'static [&'static str] {
+    let (h, au) = crate::H_EXCEPTION_COUNTS;
+    &crate::FROM_H_CI[h..h + au]
+}
+
+/// Exception fragments that only count at the start or end of a word
+///
+/// Each is the fragment, whether it must start a word and whether it must
+/// end one.
+pub fn anchored_exceptions() -> &'static [(&'static str, bool, bool)] {
+    crate::EXCEPTION_ANCHORS
+}
+
+/// The hatted letters in UTF-8, in both cases
+pub fn utf8() -> &'static [&'static str] {
+    crate::FROM_UTF8
+}
+
+/// The decomposed forms of the hatted letters, each a base letter and a
+/// combining diacritic
+pub fn decomposed() -> &'static [&'static str] {
+    crate::FROM_NFD_CI
+}
+
+/// The caret after the letter, like "c^", and "u~"
+pub fn postfix_caret() -> &'static [&'static str] {
+    crate::FROM_POSTFIX_CARET_CI
+}
+
+/// The caret before the letter, like "^c"
+pub fn prefix_caret() -> &'static [&'static str] {
+    crate::FROM_PREFIX_CARET_CI
+}
+
+/// The apostrophe after the letter, like "c'"
+pub fn apostrophe() -> &'static [&'static str] {
+    crate::FROM_APOSTROPHE_CI
+}
+
+#[cfg(all(test, feature = "h-system"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_h_tables() {
+        assert_eq!(h_system(), ["ch", "gh", "hh", "jh", "sh", "au"]);
+        assert!(h_exceptions().iter().all(|e| e.contains('h')));
+        assert!(au_exceptions().iter().all(|e| e.contains("au")));
+    }
+}
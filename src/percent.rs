@@ -0,0 +1,97 @@
+//! Percent escapes, as found in URLs and form data
+
+use std::ops::Range;
+
+/// Whether `b` may appear in a URL without being escaped
+fn is_unreserved(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b"-._~".contains(&b)
+}
+
+/// Whether `s` starts with an escape such as "%C4"
+fn is_escape(s: &[u8]) -> bool {
+    s.len() >= 3 && s[0] == b'%' && s[1].is_ascii_hexdigit() && s[2].is_ascii_hexdigit()
+}
+
+/// Find each escape in `s`, such as "%C4"
+pub(crate) fn escapes(s: &str) -> Vec<Range<usize>> {
+    let bytes = s.as_bytes();
+    (0..bytes.len())
+        .filter(|&i| is_escape(&bytes[i..]))
+        .map(|i| i..i + 3)
+        .collect()
+}
+
+/// Find each run of escapes and unreserved characters in `s` that contains
+/// at least one escape, such as "%C4%89iuj" in "?q=%C4%89iuj"
+pub(crate) fn segments(s: &str) -> Vec<Range<usize>> {
+    let bytes = s.as_bytes();
+    let mut segments = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let start = i;
+        let mut escaped = false;
+        loop {
+            if is_escape(&bytes[i..]) {
+                escaped = true;
+                i += 3;
+            } else if i < bytes.len() && is_unreserved(bytes[i]) {
+                i += 1;
+            } else {
+                break;
+            }
+        }
+        if escaped {
+            segments.push(start..i);
+        }
+        if i == start {
+            i += 1;
+        }
+    }
+    segments
+}
+
+/// The text of a segment, if its escapes make valid UTF-8
+pub(crate) fn decode(segment: &str) -> Option<String> {
+    let bytes = segment.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if is_escape(&bytes[i..]) {
+            out.push(u8::from_str_radix(&segment[i + 1..i + 3], 16).ok()?);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(out).ok()
+}
+
+/// Escape everything in `text` but the unreserved characters
+pub(crate) fn encode(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() * 3);
+    for b in text.bytes() {
+        if is_unreserved(b) {
+            out.push(char::from(b));
+        } else {
+            out.push_str(&format!("%{:02X}", b));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_segments() {
+        let s = "/vikio/%C4%88IUJ?q=sxipo%20kaj&r=100%";
+        let found: Vec<&str> = segments(s).into_iter().map(|r| &s[r]).collect();
+        assert_eq!(found, ["%C4%88IUJ", "sxipo%20kaj"]);
+        assert_eq!(decode(found[0]).as_deref(), Some("ĈIUJ"));
+        assert_eq!(decode("%C4"), None);
+        assert_eq!(encode("ŝipo kaj"), "%C5%9Dipo%20kaj");
+        assert_eq!(escapes("a%20b%2%41"), [1..4, 7..10]);
+    }
+}
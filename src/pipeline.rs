@@ -0,0 +1,1113 @@
+//! Builder for configuring a `Transliterator`
+
+use std::io::{self, Read};
+#[cfg(feature = "h-system")]
+use std::sync::Arc;
+
+use crate::decoder::Anchor;
+use crate::error::Error;
+use crate::exceptions::{parse_exception, read_exceptions};
+use crate::letter::{Letter, LetterSet};
+#[cfg(feature = "h-system")]
+use crate::lexicon::{Lexicon, SharedLexicon};
+use crate::{System, Transliterator};
+
+/// Settings shared by a `Pipeline` and the `Transliterator` it builds
+#[derive(Clone, Debug)]
+pub(crate) struct Options {
+    pub(crate) from: System,
+    pub(crate) to: System,
+    pub(crate) normalize_unicode: bool,
+    pub(crate) normalize_confusables: bool,
+    pub(crate) output_form: UnicodeForm,
+    pub(crate) casing: CasingPolicy,
+    pub(crate) soft_hyphens: Invisible,
+    pub(crate) zero_width: Invisible,
+    pub(crate) protect_foreign_words: bool,
+    pub(crate) protected: Vec<String>,
+    pub(crate) exceptions: Vec<(String, Anchor)>,
+    pub(crate) removed_exceptions: Vec<String>,
+    pub(crate) protect_proper_nouns: bool,
+    pub(crate) skip_links: bool,
+    pub(crate) mime_words: bool,
+    pub(crate) percent_escapes: PercentEscapes,
+    pub(crate) esperanto_only: Option<f32>,
+    pub(crate) skip_social: bool,
+    pub(crate) escape: Option<char>,
+    pub(crate) au_to_breve: bool,
+    pub(crate) w_for_breve: bool,
+    pub(crate) modern_k: bool,
+    pub(crate) letters: LetterSet,
+    pub(crate) mapping: Vec<(Letter, String)>,
+    pub(crate) braced_escapes: bool,
+    #[cfg(feature = "h-system")]
+    pub(crate) plain_h: bool,
+    #[cfg(feature = "h-system")]
+    pub(crate) strict: bool,
+    #[cfg(feature = "h-system")]
+    pub(crate) lexicon: Option<SharedLexicon>,
+    pub(crate) match_speed: MatchSpeed,
+}
+
+impl Options {
+    /// How to treat `c`, if it is an invisible formatting character
+    pub(crate) fn invisible(&self, c: char) -> Invisible {
+        match c {
+            '\u{AD}' => self.soft_hyphens,
+            c if is_zero_width(c) => self.zero_width,
+            _ => Invisible::Literal,
+        }
+    }
+
+    /// Whether `c` is an invisible character that patterns can match across
+    pub(crate) fn is_invisible(&self, c: char) -> bool {
+        self.invisible(c) != Invisible::Literal
+    }
+
+    /// Whether any invisible characters are to be matched across
+    pub(crate) fn has_invisibles(&self) -> bool {
+        self.soft_hyphens != Invisible::Literal || self.zero_width != Invisible::Literal
+    }
+
+    /// Add an exception fragment, which may be anchored
+    ///
+    /// Entries that aren't a single fragment are ignored.
+    pub(crate) fn add_exception(&mut self, entry: &str) {
+        if let Some((fragment, anchor)) = parse_exception(entry.trim()) {
+            self.removed_exceptions
+                .retain(|f| !f.eq_ignore_ascii_case(&fragment));
+            self.exceptions.push((fragment, anchor));
+        }
+    }
+
+    /// Remove an exception fragment, whether built in or added
+    pub(crate) fn remove_exception(&mut self, entry: &str) {
+        if let Some((fragment, _)) = parse_exception(entry.trim()) {
+            self.exceptions
+                .retain(|(f, _)| !f.eq_ignore_ascii_case(&fragment));
+            self.removed_exceptions.push(fragment);
+        }
+    }
+
+    /// The lowercase replacement for `letter` in the output, if it has one
+    pub(crate) fn mapped(&self, letter: Letter) -> Option<&str> {
+        self.mapping
+            .iter()
+            .find(|(l, _)| *l == letter)
+            .map(|(_, replacement)| replacement.as_str())
+    }
+
+    /// Refuse options that don't work together or can have no effect
+    fn check(&self) -> Result<(), Error> {
+        let unsupported = |reason| Err(Error::UnsupportedOptions(reason));
+        if self
+            .esperanto_only
+            .is_some_and(|threshold| !(0.0..=1.0).contains(&threshold))
+        {
+            return unsupported("the Esperanto threshold must be between 0 and 1");
+        }
+        if self
+            .escape
+            .is_some_and(|c| c.is_alphanumeric() || c.is_whitespace())
+        {
+            return unsupported("the escape can't be a letter, digit or space");
+        }
+        if self.output_form == UnicodeForm::Nfd && self.to != System::Utf8 {
+            return unsupported("decomposed output needs UTF-8 output");
+        }
+        if self.braced_escapes && self.to != System::UnicodeEscape {
+            return unsupported("braced escapes need Unicode escape output");
+        }
+        if self.w_for_breve && self.from == System::Utf8 && self.to == System::Utf8 {
+            return unsupported("\"w\" for \"ŭ\" needs a system other than UTF-8");
+        }
+        #[cfg(feature = "h-system")]
+        {
+            if self.plain_h && self.from != System::H && self.to != System::H {
+                return unsupported("plain \"h\" for \"ĥ\" needs the h-system");
+            }
+            if self.strict && self.from != System::H {
+                return unsupported("strict reading needs h-system input");
+            }
+            if self.strict && self.lexicon.is_some() {
+                return unsupported("strict reading doesn't use a lexicon");
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether any invisible characters are to be removed from the output
+    pub(crate) fn removes_invisibles(&self) -> bool {
+        self.soft_hyphens == Invisible::Remove || self.zero_width == Invisible::Remove
+    }
+}
+
+/// Whether `c` is a lowercase letter of the Esperanto alphabet without a hat
+fn is_esperanto_letter(c: char) -> bool {
+    c.is_ascii_lowercase() && !matches!(c, 'q' | 'w' | 'x' | 'y')
+}
+
+/// Whether `c` is a zero-width space, joiner or non-joiner
+pub(crate) fn is_zero_width(c: char) -> bool {
+    matches!(c, '\u{200B}'..='\u{200D}' | '\u{2060}' | '\u{FEFF}')
+}
+
+/// How to treat invisible formatting characters that may appear inside words
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Invisible {
+    /// Treat them like any other character, so patterns can't match across them
+    #[default]
+    Literal,
+    /// Match patterns across them and keep them in the output
+    ///
+    /// One that was inside a converted digraph is written just after it.
+    Transparent,
+    /// Match patterns across them and leave them out of the output
+    Remove,
+}
+
+/// How to treat percent escapes such as "%C4%89", as found in URLs and form data
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum PercentEscapes {
+    /// Treat them like any other text
+    #[default]
+    Literal,
+    /// Leave the escapes as they are, converting only the text around them
+    Keep,
+    /// Decode each run of escapes along with the URL characters around it,
+    /// convert it, and escape it again
+    ///
+    /// A run whose escapes aren't valid UTF-8 is left as it is.
+    Convert,
+}
+
+/// How hatted letters are written in UTF-8 output
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum UnicodeForm {
+    /// A single precomposed character, e.g. "ĉ"
+    #[default]
+    Nfc,
+    /// The base letter followed by a combining diacritic, e.g. "c\u{302}"
+    Nfd,
+}
+
+/// Trade-off between memory use and speed for the compiled patterns
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum MatchSpeed {
+    /// A small automaton that is quick to build, suited to embedded and WASM
+    #[default]
+    Compact,
+    /// A DFA that uses more memory and takes longer to build but matches faster
+    Fast,
+}
+
+/// How a capital hatted letter is written as an x-system or h-system digraph
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum CasingPolicy {
+    /// "CX" within an all-caps word and "Cx" anywhere else
+    #[default]
+    MatchContext,
+    /// Always "Cx"
+    AlwaysTitle,
+    /// Always "CX"
+    AlwaysUpper,
+}
+
+/// Builds a `Transliterator` with non-default behaviour
+///
+/// All the options are compiled into the resulting converter, so the text is
+/// still processed in a single pass however many are enabled.
+///
+#[cfg_attr(all(feature = "x-system", feature = "h-system"), doc = "```")]
+#[cfg_attr(not(all(feature = "x-system", feature = "h-system")), doc = "```ignore")]
+/// use esperanto_text::{Pipeline, System};
+///
+/// let t = Pipeline::new()
+///     .from(System::H)
+///     .normalize_unicode(true)
+///     .to(System::X)
+///     .build();
+/// assert_eq!(t.convert("chu vi s\u{302}atas g\u{302}in?"), "cxu vi sxatas gxin?");
+/// ```
+#[derive(Clone, Debug)]
+pub struct Pipeline {
+    options: Options,
+}
+
+impl Pipeline {
+    /// Start a pipeline that converts UTF-8 to UTF-8, changing nothing
+    pub fn new() -> Pipeline {
+        Pipeline {
+            options: Options {
+                from: System::Utf8,
+                to: System::Utf8,
+                normalize_unicode: false,
+                normalize_confusables: false,
+                output_form: UnicodeForm::Nfc,
+                casing: CasingPolicy::MatchContext,
+                soft_hyphens: Invisible::Literal,
+                zero_width: Invisible::Literal,
+                protect_foreign_words: true,
+                protected: Vec::new(),
+                exceptions: Vec::new(),
+                removed_exceptions: Vec::new(),
+                protect_proper_nouns: false,
+                skip_links: false,
+                mime_words: false,
+                percent_escapes: PercentEscapes::Literal,
+                esperanto_only: None,
+                skip_social: false,
+                escape: None,
+                au_to_breve: true,
+                w_for_breve: false,
+                modern_k: false,
+                letters: LetterSet::ALL,
+                mapping: Vec::new(),
+                braced_escapes: false,
+                #[cfg(feature = "h-system")]
+                plain_h: false,
+                #[cfg(feature = "h-system")]
+                strict: false,
+                #[cfg(feature = "h-system")]
+                lexicon: None,
+                match_speed: MatchSpeed::Compact,
+            },
+        }
+    }
+
+    /// Set the system of the input text
+    pub fn from(mut self, system: System) -> Pipeline {
+        self.options.from = system;
+        self
+    }
+
+    /// Set the system of the output text
+    pub fn to(mut self, system: System) -> Pipeline {
+        self.options.to = system;
+        self
+    }
+
+    /// Compose letters written with a combining diacritic (NFD) even when the
+    /// input and output systems are the same
+    ///
+    /// For example "c" followed by U+0302 COMBINING CIRCUMFLEX ACCENT becomes
+    /// "ĉ". Decomposed letters are always recognised when converting between
+    /// two different systems.
+    pub fn normalize_unicode(mut self, enabled: bool) -> Pipeline {
+        self.options.normalize_unicode = enabled;
+        self
+    }
+
+    /// Also read lookalike characters, such as "č" or "ǔ", as hatted letters
+    ///
+    /// The full list is [`CONFUSABLES`](constant.CONFUSABLES.html).
+    ///
+    #[cfg_attr(feature = "x-system", doc = "```")]
+    #[cfg_attr(not(feature = "x-system"), doc = "```ignore")]
+    /// use esperanto_text::{Pipeline, System};
+    ///
+    /// let t = Pipeline::new().normalize_confusables(true).to(System::X).build();
+    /// assert_eq!(t.convert("čiuj laǔ"), "cxiuj laux");
+    /// ```
+    pub fn normalize_confusables(mut self, enabled: bool) -> Pipeline {
+        self.options.normalize_confusables = enabled;
+        self
+    }
+
+    /// Choose how hatted letters are written when the output is UTF-8
+    ///
+    /// The default is [`UnicodeForm::Nfc`](enum.UnicodeForm.html). Some older
+    /// filesystems and databases expect `UnicodeForm::Nfd` instead. Letters
+    /// already in the input are rewritten to match, so converting UTF-8 to
+    /// UTF-8 changes the form of every hatted letter.
+    ///
+    #[cfg_attr(feature = "x-system", doc = "```")]
+    #[cfg_attr(not(feature = "x-system"), doc = "```ignore")]
+    /// use esperanto_text::{Pipeline, System, UnicodeForm};
+    ///
+    /// let t = Pipeline::new()
+    ///     .from(System::X)
+    ///     .output_form(UnicodeForm::Nfd)
+    ///     .build();
+    /// assert_eq!(t.convert("cxu"), "c\u{302}u");
+    /// ```
+    pub fn output_form(mut self, form: UnicodeForm) -> Pipeline {
+        self.options.output_form = form;
+        self
+    }
+
+    /// Choose how capitals are written when the output is the x-system or h-system
+    ///
+    /// The default, [`CasingPolicy::MatchContext`](enum.CasingPolicy.html),
+    /// looks at the rest of the word. Publications with a fixed house style
+    /// can choose to always write "Cx" or always "CX" instead.
+    pub fn casing(mut self, policy: CasingPolicy) -> Pipeline {
+        self.options.casing = policy;
+        self
+    }
+
+    /// Choose how to treat soft hyphens (U+00AD)
+    ///
+    /// Text extracted from typeset documents often has soft hyphens inside
+    /// words, which stop "c\u{AD}h" from being read as a digraph unless they
+    /// are made transparent.
+    ///
+    #[cfg_attr(feature = "x-system", doc = "```")]
+    #[cfg_attr(not(feature = "x-system"), doc = "```ignore")]
+    /// use esperanto_text::{Invisible, Pipeline, System};
+    ///
+    /// let t = Pipeline::new()
+    ///     .from(System::X)
+    ///     .soft_hyphens(Invisible::Remove)
+    ///     .build();
+    /// assert_eq!(t.convert("sxan\u{AD}gxo"), "ŝanĝo");
+    /// ```
+    pub fn soft_hyphens(mut self, handling: Invisible) -> Pipeline {
+        self.options.soft_hyphens = handling;
+        self
+    }
+
+    /// Choose how to treat zero-width characters
+    ///
+    /// These are U+200B ZERO WIDTH SPACE, U+200C ZERO WIDTH NON-JOINER,
+    /// U+200D ZERO WIDTH JOINER, U+2060 WORD JOINER and U+FEFF ZERO WIDTH
+    /// NO-BREAK SPACE. Text pasted from the web sometimes has them between
+    /// letters, where they stop digraphs from being recognised.
+    pub fn zero_width(mut self, handling: Invisible) -> Pipeline {
+        self.options.zero_width = handling;
+        self
+    }
+
+    /// Choose whether to leave alone foreign words that look like x-system
+    ///
+    /// By default, words such as "Linux", "auxiliary" and "Bordeaux" are not
+    /// converted when reading the x-system. Text known to be pure Esperanto
+    /// can turn this off.
+    ///
+    #[cfg_attr(feature = "x-system", doc = "```")]
+    #[cfg_attr(not(feature = "x-system"), doc = "```ignore")]
+    /// use esperanto_text::{Pipeline, System};
+    ///
+    /// let t = Pipeline::new().from(System::X).build();
+    /// assert_eq!(t.convert("Linux estas auxtoro"), "Linux estas aŭtoro");
+    /// let t = Pipeline::new()
+    ///     .from(System::X)
+    ///     .protect_foreign_words(false)
+    ///     .build();
+    /// assert_eq!(t.convert("Linux"), "Linŭ");
+    /// ```
+    pub fn protect_foreign_words(mut self, protect: bool) -> Pipeline {
+        self.options.protect_foreign_words = protect;
+        self
+    }
+
+    /// Never change any of these words, whatever the systems
+    ///
+    /// Useful for names, usernames and code identifiers. Each is matched
+    /// wherever it appears, ignoring ASCII case, and passes through as it was
+    /// written. This can be called more than once to add more words.
+    ///
+    #[cfg_attr(feature = "h-system", doc = "```")]
+    #[cfg_attr(not(feature = "h-system"), doc = "```ignore")]
+    /// use esperanto_text::{Pipeline, System};
+    ///
+    /// let t = Pipeline::new()
+    ///     .from(System::H)
+    ///     .protect(["Sasha", "chmod"])
+    ///     .build();
+    /// assert_eq!(t.convert("Sasha uzis chmod hierau"), "Sasha uzis chmod hieraŭ");
+    /// ```
+    pub fn protect<I, S>(mut self, words: I) -> Pipeline
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.options
+            .protected
+            .extend(words.into_iter().map(Into::into));
+        self
+    }
+
+    /// Add exception fragments read from `reader` to those built in
+    ///
+    /// The list has the same format as the files in the crate's `data/`
+    /// directory: one fragment per line, with anything after a '#' ignored.
+    /// A leading '^' means the fragment must begin a word and a trailing '$'
+    /// that it must end one. Wherever a fragment is found in x-system or
+    /// h-system input it passes through unchanged, so one list can hold
+    /// h-system words, "au" words and foreign words containing "x".
+    ///
+    #[cfg_attr(feature = "h-system", doc = "```")]
+    #[cfg_attr(not(feature = "h-system"), doc = "```ignore")]
+    /// use esperanto_text::{Pipeline, System};
+    ///
+    /// let list = "# Our words\nmachbox\n^flux$\n";
+    /// let t = Pipeline::new().from(System::H).exceptions_from(list.as_bytes())?.build();
+    /// assert_eq!(t.convert("Machbox chiam"), "Machbox ĉiam");
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn exceptions_from<R: Read>(mut self, reader: R) -> io::Result<Pipeline> {
+        self.options.exceptions.extend(read_exceptions(reader)?);
+        Ok(self)
+    }
+
+    /// Add one exception fragment to those built in
+    ///
+    /// The fragment may be anchored with '^' and '$' as described in
+    /// [`exceptions_from`](#method.exceptions_from).
+    ///
+    #[cfg_attr(feature = "h-system", doc = "```")]
+    #[cfg_attr(not(feature = "h-system"), doc = "```ignore")]
+    /// use esperanto_text::{Pipeline, System};
+    ///
+    /// let builtin = Pipeline::new().from(System::H).build();
+    /// assert_eq!(builtin.convert("Ashley venis"), "Aŝley venis");
+    ///
+    /// let t = Pipeline::new()
+    ///     .from(System::H)
+    ///     .add_exception("ashley")
+    ///     .build();
+    /// assert_eq!(t.convert("Ashley venis"), "Ashley venis");
+    /// ```
+    pub fn add_exception(mut self, fragment: &str) -> Pipeline {
+        self.options.add_exception(fragment);
+        self
+    }
+
+    /// Stop leaving alone an exception fragment, built in or added
+    ///
+    #[cfg_attr(feature = "h-system", doc = "```")]
+    #[cfg_attr(not(feature = "h-system"), doc = "```ignore")]
+    /// use esperanto_text::{Pipeline, System};
+    ///
+    /// let t = Pipeline::new()
+    ///     .from(System::H)
+    ///     .remove_exception("ashund")
+    ///     .build();
+    /// assert_eq!(t.convert("chashundo"), "ĉaŝundo");
+    /// ```
+    pub fn remove_exception(mut self, fragment: &str) -> Pipeline {
+        self.options.remove_exception(fragment);
+        self
+    }
+
+    /// Leave alone capitalised words that don't start a sentence
+    ///
+    /// This keeps foreign names such as "Charles" or "Washington" intact when
+    /// reading the x-system or h-system, at the cost of also skipping any
+    /// Esperanto names, like "Chinio", written that way. For a fixed set of
+    /// names use [`protect`](#method.protect) instead.
+    ///
+    #[cfg_attr(feature = "h-system", doc = "```")]
+    #[cfg_attr(not(feature = "h-system"), doc = "```ignore")]
+    /// use esperanto_text::{Pipeline, System};
+    ///
+    /// let t = Pipeline::new()
+    ///     .from(System::H)
+    ///     .protect_proper_nouns(true)
+    ///     .build();
+    /// assert_eq!(t.convert("Chu Charles shatas Sheffield?"), "Ĉu Charles ŝatas Sheffield?");
+    /// ```
+    pub fn protect_proper_nouns(mut self, enabled: bool) -> Pipeline {
+        self.options.protect_proper_nouns = enabled;
+        self
+    }
+
+    /// Leave alone URLs, email addresses and domain names
+    ///
+    /// Anything separated by whitespace that starts with a scheme such as
+    /// "https://" or with "www.", that contains an "@" followed by a domain,
+    /// or that starts with a domain name like "github.com" is passed through.
+    ///
+    #[cfg_attr(feature = "h-system", doc = "```")]
+    #[cfg_attr(not(feature = "h-system"), doc = "```ignore")]
+    /// use esperanto_text::{Pipeline, System};
+    ///
+    /// let t = Pipeline::new().from(System::H).skip_links(true).build();
+    /// assert_eq!(t.convert("Vidu github.com/auth hierau"), "Vidu github.com/auth hieraŭ");
+    /// ```
+    pub fn skip_links(mut self, enabled: bool) -> Pipeline {
+        self.options.skip_links = enabled;
+        self
+    }
+
+    /// Choose how to treat percent escapes, as found in URLs and form data
+    ///
+    /// By default they are ordinary text, so a hex digit next to a letter may
+    /// be read as part of a digraph.
+    ///
+    #[cfg_attr(feature = "x-system", doc = "```")]
+    #[cfg_attr(not(feature = "x-system"), doc = "```ignore")]
+    /// use esperanto_text::{PercentEscapes, Pipeline, System};
+    ///
+    /// let t = Pipeline::new()
+    ///     .from(System::X)
+    ///     .percent_escapes(PercentEscapes::Convert)
+    ///     .build();
+    /// assert_eq!(t.convert("?q=%C4%88IUJ+sxipo"), "?q=%C4%88IUJ+ŝipo");
+    /// assert_eq!(t.convert("?q=sxipo%20kaj"), "?q=%C5%9Dipo%20kaj");
+    /// ```
+    pub fn percent_escapes(mut self, handling: PercentEscapes) -> Pipeline {
+        self.options.percent_escapes = handling;
+        self
+    }
+
+    /// Convert the text inside RFC 2047 encoded words, as in email headers
+    ///
+    /// Each encoded word, such as "=?UTF-8?Q?=C4=89u?=", is decoded,
+    /// converted and encoded again in the same way. If its charset can't hold
+    /// the converted text, UTF-8 is used instead. Words in charsets other
+    /// than UTF-8, ISO 8859-1 and ISO 8859-3 are left as they are.
+    ///
+    #[cfg_attr(feature = "x-system", doc = "```")]
+    #[cfg_attr(not(feature = "x-system"), doc = "```ignore")]
+    /// use esperanto_text::{Pipeline, System};
+    ///
+    /// let t = Pipeline::new().to(System::X).mime_encoded_words(true).build();
+    /// assert_eq!(
+    ///     t.convert("Subject: =?UTF-8?Q?=C4=89u_vi?= venos?"),
+    ///     "Subject: =?UTF-8?Q?cxu_vi?= venos?"
+    /// );
+    /// ```
+    pub fn mime_encoded_words(mut self, enabled: bool) -> Pipeline {
+        self.options.mime_words = enabled;
+        self
+    }
+
+    /// Only convert sentences that look like Esperanto
+    ///
+    /// Each sentence is scored with
+    /// [`esperanto_likelihood`](fn.esperanto_likelihood.html) and left as it
+    /// is if the score is below `threshold`, so that quotations in other
+    /// languages aren't mangled. About 0.5 suits most text. Very short
+    /// sentences give less reliable scores.
+    ///
+    #[cfg_attr(feature = "x-system", doc = "```")]
+    #[cfg_attr(not(feature = "x-system"), doc = "```ignore")]
+    /// use esperanto_text::{Pipeline, System};
+    ///
+    /// let t = Pipeline::new().from(System::X).only_esperanto(0.5).build();
+    /// assert_eq!(
+    ///     t.convert("Li skribis: \"The auxiliary unit was checked.\" Cxu vi komprenas lin?"),
+    ///     "Li skribis: \"The auxiliary unit was checked.\" Ĉu vi komprenas lin?"
+    /// );
+    /// ```
+    pub fn only_esperanto(mut self, threshold: f32) -> Pipeline {
+        self.options.esperanto_only = Some(threshold);
+        self
+    }
+
+    /// Leave alone @mentions, #hashtags and :emoji: shortcodes
+    ///
+    #[cfg_attr(feature = "x-system", doc = "```")]
+    #[cfg_attr(not(feature = "x-system"), doc = "```ignore")]
+    /// use esperanto_text::{Pipeline, System};
+    ///
+    /// let t = Pipeline::new().from(System::X).skip_social(true).build();
+    /// assert_eq!(t.convert("@auxtoro: cxu #sxanco?"), "@auxtoro: ĉu #sxanco?");
+    /// ```
+    pub fn skip_social(mut self, enabled: bool) -> Pipeline {
+        self.options.skip_social = enabled;
+        self
+    }
+
+    /// Escape letters that would otherwise be misread as a digraph
+    ///
+    /// When writing the x-system or h-system, `escape` is put between a
+    /// letter and a following "x" (or "h", or the "u" of "au") that aren't a
+    /// digraph, and is doubled wherever it appears by itself. When reading,
+    /// the same escapes are understood and removed. With the same `escape` in
+    /// both directions, UTF-8 text such as "Linux" survives a round trip
+    /// through the x-system.
+    ///
+    /// The h-system still loses information: it has no way to write "ŭ" except
+    /// after "a", and a hatted letter that happens to spell one of its
+    /// exception words, such as "senĉava", comes back without its hat.
+    ///
+    #[cfg_attr(feature = "x-system", doc = "```")]
+    #[cfg_attr(not(feature = "x-system"), doc = "```ignore")]
+    /// use esperanto_text::{Pipeline, System};
+    ///
+    /// let to_x = Pipeline::new().to(System::X).escape('\\').build();
+    /// let from_x = Pipeline::new().from(System::X).escape('\\').build();
+    /// let x = to_x.convert("Linux kaj ŝanĝo");
+    /// assert_eq!(x, "Linu\\x kaj sxangxo");
+    /// assert_eq!(from_x.convert(&x), "Linux kaj ŝanĝo");
+    /// ```
+    pub fn escape(mut self, escape: char) -> Pipeline {
+        self.options.escape = Some(escape);
+        self
+    }
+
+    /// Choose whether reading the h-system turns "au" into "aŭ"
+    ///
+    /// This is on by default, since h-system text usually drops the breve.
+    /// Turn it off for text that marks "ŭ" some other way, where the rule
+    /// would only damage names and loanwords. The digraphs are unaffected.
+    ///
+    #[cfg_attr(feature = "h-system", doc = "```")]
+    #[cfg_attr(not(feature = "h-system"), doc = "```ignore")]
+    /// use esperanto_text::{Pipeline, System};
+    ///
+    /// let t = Pipeline::new().from(System::H).au_to_breve(false).build();
+    /// assert_eq!(t.convert("Chu Paul?"), "Ĉu Paul?");
+    /// ```
+    pub fn au_to_breve(mut self, enabled: bool) -> Pipeline {
+        self.options.au_to_breve = enabled;
+        self
+    }
+
+    /// Read every h-system digraph and "au" by rule alone
+    ///
+    /// The exception list, the checks for compound words and any lexicon are
+    /// all skipped, so "senchava" becomes "senĉava". This suits text that is
+    /// known to be pure h-system, such as the output of another program.
+    ///
+    /// ```
+    /// use esperanto_text::{Pipeline, System};
+    ///
+    /// let t = Pipeline::new().from(System::H).strict(true).build();
+    /// assert_eq!(t.convert("senchava praulo"), "senĉava praŭlo");
+    /// ```
+    #[cfg(feature = "h-system")]
+    pub fn strict(mut self, enabled: bool) -> Pipeline {
+        self.options.strict = enabled;
+        self
+    }
+
+    /// Write "ŭ" as "w" in the x-system and h-system, as in "antaw"
+    ///
+    /// When reading either system, "aw" and "ew" are also read as "aŭ" and
+    /// "eŭ", alongside the system's usual spelling.
+    ///
+    #[cfg_attr(all(feature = "x-system", feature = "h-system"), doc = "```")]
+    #[cfg_attr(not(all(feature = "x-system", feature = "h-system")), doc = "```ignore")]
+    /// use esperanto_text::{Pipeline, System};
+    ///
+    /// let t = Pipeline::new().from(System::X).w_for_breve(true).build();
+    /// assert_eq!(t.convert("antaw kaj hodiaux en Ewropo"), "antaŭ kaj hodiaŭ en Eŭropo");
+    /// let t = Pipeline::new().to(System::H).w_for_breve(true).build();
+    /// assert_eq!(t.convert("Ĉiuĵaŭde"), "Chiujhawde");
+    /// ```
+    pub fn w_for_breve(mut self, enabled: bool) -> Pipeline {
+        self.options.w_for_breve = enabled;
+        self
+    }
+
+    /// Write "k" for "ĥ" in roots where modern usage prefers it
+    ///
+    /// Only a curated list of roots is changed, such as "arĥivo" and
+    /// "ĥemio". Roots where "k" would make a different word, like "ĥoro"
+    /// (choir) and "koro" (heart), keep their "ĥ". This works in any system,
+    /// including from UTF-8 to UTF-8.
+    ///
+    #[cfg_attr(feature = "x-system", doc = "```")]
+    #[cfg_attr(not(feature = "x-system"), doc = "```ignore")]
+    /// use esperanto_text::{Pipeline, System};
+    ///
+    /// let t = Pipeline::new().to(System::Utf8).modern_k(true).build();
+    /// assert_eq!(t.convert("Arĥivo pri bioĥemio kaj ĥoro"), "Arkivo pri biokemio kaj ĥoro");
+    /// let t = Pipeline::new().from(System::X).to(System::X).modern_k(true).build();
+    /// assert_eq!(t.convert("TEHXNIKO kaj hxoro"), "TEKNIKO kaj hxoro");
+    /// ```
+    pub fn modern_k(mut self, enabled: bool) -> Pipeline {
+        self.options.modern_k = enabled;
+        self
+    }
+
+    /// Only convert the letters in `letters`, leaving the others as they are
+    ///
+    /// This works in both directions. For example, some styles keep "ŭ" in
+    /// proper nouns while converting the other letters, or only "ŭ" needs
+    /// fixing.
+    ///
+    #[cfg_attr(feature = "x-system", doc = "```")]
+    #[cfg_attr(not(feature = "x-system"), doc = "```ignore")]
+    /// use esperanto_text::{LetterSet, Pipeline, System};
+    ///
+    /// let t = Pipeline::new().to(System::X).letters(LetterSet::CIRCUMFLEX).build();
+    /// assert_eq!(t.convert("Ĉu Eŭropo?"), "Cxu Eŭropo?");
+    /// let t = Pipeline::new().from(System::X).letters(LetterSet::U).build();
+    /// assert_eq!(t.convert("cxu auxto"), "cxu aŭto");
+    /// ```
+    pub fn letters(mut self, letters: LetterSet) -> Pipeline {
+        self.options.letters = letters;
+        self
+    }
+
+    /// Write each hatted letter in the table as the string it is paired with,
+    /// instead of as the target system would
+    ///
+    /// Replacements are given in lowercase, and are capitalised to match the
+    /// letter they stand for in the same way as x-system digraphs. Letters not
+    /// in the table are written as usual, and a letter given again replaces
+    /// its earlier mapping.
+    ///
+    /// A letter mapped to nothing, two letters mapped to the same string and
+    /// a letter mapped to a single letter of the Esperanto alphabet, like "c"
+    /// for "ĉ", are refused, since the output couldn't be read back. Clashes
+    /// with how the target system writes the letters left out of the table
+    /// aren't checked.
+    ///
+    #[cfg_attr(feature = "x-system", doc = "```")]
+    #[cfg_attr(not(feature = "x-system"), doc = "```ignore")]
+    /// use esperanto_text::{Pipeline, System};
+    ///
+    /// let t = Pipeline::new()
+    ///     .to(System::X)
+    ///     .mapping(&[('ĥ', "x"), ('ŭ', "w"), ('ĉ', "cc")])?
+    ///     .build();
+    /// assert_eq!(t.convert("Ĉu ĥoro aŭ ŝipo?"), "Ccu xoro aw sxipo?");
+    /// assert!(Pipeline::new().mapping(&[('ĉ', "c")]).is_err());
+    /// # Ok::<(), esperanto_text::Error>(())
+    /// ```
+    pub fn mapping(mut self, table: &[(char, &str)]) -> Result<Pipeline, Error> {
+        for &(c, replacement) in table {
+            let (letter, _) = Letter::from_hatted(c).ok_or(Error::NotHatted(c))?;
+            let replacement = replacement.to_lowercase();
+            self.options.mapping.retain(|(l, _)| *l != letter);
+            self.options.mapping.push((letter, replacement));
+        }
+        for (i, (letter, replacement)) in self.options.mapping.iter().enumerate() {
+            let c = letter.hatted(false);
+            let mut chars = replacement.chars();
+            match (chars.next(), chars.next()) {
+                (None, _) => return Err(Error::EmptyMapping(c)),
+                (Some(plain), None) if is_esperanto_letter(plain) => {
+                    return Err(Error::AmbiguousMapping(c, plain))
+                }
+                _ => {}
+            }
+            if let Some((other, _)) = self.options.mapping[..i]
+                .iter()
+                .find(|(_, r)| r == replacement)
+            {
+                return Err(Error::AmbiguousMapping(c, other.hatted(false)));
+            }
+        }
+        Ok(self)
+    }
+
+    /// Write Unicode escapes in Rust's braced style, as in `\u{0109}`
+    ///
+    /// This only affects output in
+    /// [`System::UnicodeEscape`](enum.System.html#variant.UnicodeEscape),
+    /// which otherwise writes `\u0109` as JSON and JavaScript do.
+    ///
+    /// ```
+    /// use esperanto_text::{Pipeline, System};
+    ///
+    /// let t = Pipeline::new().to(System::UnicodeEscape).braced_escapes(true).build();
+    /// assert_eq!(t.convert("ĉu"), "\\u{0109}u");
+    /// ```
+    pub fn braced_escapes(mut self, enabled: bool) -> Pipeline {
+        self.options.braced_escapes = enabled;
+        self
+    }
+
+    /// Write "ĥ" as a plain "h" in the h-system, as some older texts do
+    ///
+    /// When writing the h-system, "ĥ" becomes "h" rather than "hh". When
+    /// reading it, a few roots that only exist with "ĥ", such as "ĥaos" and
+    /// "ĉeĥ", are recognised with a plain "h". A root that is also another
+    /// word with "h", like "ĥoro" (choir) and "horo" (hour), stays as "h".
+    ///
+    /// ```
+    /// use esperanto_text::{Pipeline, System};
+    ///
+    /// let t = Pipeline::new().from(System::H).plain_h(true).build();
+    /// assert_eq!(t.convert("Chehoj en haoso je la horo"), "Ĉeĥoj en ĥaoso je la horo");
+    /// let t = Pipeline::new().to(System::H).plain_h(true).build();
+    /// assert_eq!(t.convert("eĥoŝanĝo"), "ehoshangho");
+    /// ```
+    #[cfg(feature = "h-system")]
+    pub fn plain_h(mut self, enabled: bool) -> Pipeline {
+        self.options.plain_h = enabled;
+        self
+    }
+
+    /// Choose between a compact automaton and a faster DFA
+    ///
+    /// The default is [`MatchSpeed::Compact`](enum.MatchSpeed.html). Batch
+    /// jobs converting large volumes of text may prefer `MatchSpeed::Fast`.
+    /// This has no effect without the `aho-corasick` feature.
+    pub fn match_speed(mut self, speed: MatchSpeed) -> Pipeline {
+        self.options.match_speed = speed;
+        self
+    }
+
+    /// Decide which h-system words to leave alone with `lexicon` instead of
+    /// the built-in list
+    ///
+    /// See [`Lexicon`](trait.Lexicon.html) for an example.
+    #[cfg(feature = "h-system")]
+    pub fn lexicon<L: Lexicon + 'static>(mut self, lexicon: L) -> Pipeline {
+        self.options.lexicon = Some(SharedLexicon(Arc::new(lexicon)));
+        self
+    }
+
+    /// Compile the configured converter
+    pub fn build(self) -> Transliterator {
+        Transliterator::with_options(self.options)
+    }
+
+    /// Compile the configured converter, unless some of the options don't
+    /// work together or would have no effect
+    ///
+    /// [`build`](#method.build) accepts any combination and does its best.
+    ///
+    #[cfg_attr(feature = "x-system", doc = "```")]
+    #[cfg_attr(not(feature = "x-system"), doc = "```ignore")]
+    /// use esperanto_text::{Error, Pipeline, System, UnicodeForm};
+    ///
+    /// let t = Pipeline::new().to(System::X).try_build()?;
+    /// assert_eq!(t.convert("ĉu"), "cxu");
+    /// let nfd_x = Pipeline::new().to(System::X).output_form(UnicodeForm::Nfd);
+    /// assert!(matches!(nfd_x.try_build(), Err(Error::UnsupportedOptions(_))));
+    /// # Ok::<(), Error>(())
+    /// ```
+    pub fn try_build(self) -> Result<Transliterator, Error> {
+        self.options.check()?;
+        Ok(self.build())
+    }
+}
+
+impl Default for Pipeline {
+    fn default() -> Pipeline {
+        Pipeline::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "h-system")]
+    use crate::BuiltinLexicon;
+
+    #[test]
+    fn test_default_pipeline_is_noop() {
+        let t = Pipeline::new().build();
+        assert_eq!(t.convert("ĉu cxu chu"), "ĉu cxu chu");
+    }
+
+    #[cfg(feature = "x-system")]
+    #[test]
+    fn test_decomposed_input() {
+        let t = Pipeline::new()
+            .from(System::Utf8)
+            .to(System::X)
+            .normalize_unicode(true)
+            .build();
+        assert_eq!(t.convert("C\u{302}iuj u\u{306}"), "Cxiuj ux");
+    }
+
+    #[cfg(feature = "x-system")]
+    #[test]
+    fn test_nfd_output_round_trip() {
+        let input = "eĥoŝanĝo ĉiuĵaŭde EĤOŜANĜO ĈIUĴAŬDE";
+        let to_nfd = Pipeline::new().output_form(UnicodeForm::Nfd).build();
+        let decomposed = to_nfd.convert(input);
+        assert_eq!(decomposed.chars().filter(|c| *c == '\u{302}').count(), 10);
+        assert_eq!(decomposed.chars().filter(|c| *c == '\u{306}').count(), 2);
+        assert_eq!(to_nfd.convert(&decomposed), decomposed);
+
+        let to_nfc = Pipeline::new().normalize_unicode(true).build();
+        assert_eq!(to_nfc.convert(&decomposed), input);
+
+        let x = Pipeline::new().to(System::X).build().convert(&decomposed);
+        let from_x = Pipeline::new()
+            .from(System::X)
+            .output_form(UnicodeForm::Nfd)
+            .build();
+        assert_eq!(from_x.convert(&x), decomposed);
+    }
+
+    #[cfg(feature = "x-system")]
+    #[test]
+    fn test_confusables() {
+        let t = Pipeline::new().normalize_confusables(true).build();
+        assert_eq!(
+            t.convert("Čiuj ŠIPOJ, C\u{30C}u laȗ?"),
+            "Ĉiuj ŜIPOJ, Ĉu laŭ?"
+        );
+        let plain = Pipeline::new().to(System::X).build();
+        assert_eq!(plain.convert("čiuj"), "čiuj");
+    }
+
+    #[cfg(feature = "x-system")]
+    #[test]
+    fn test_zero_width() {
+        let input = "c\u{200B}xu s\u{200D}xi";
+        let with = |handling| Pipeline::new().from(System::X).zero_width(handling).build();
+        assert_eq!(with(Invisible::Literal).convert(input), input);
+        assert_eq!(
+            with(Invisible::Transparent).convert(input),
+            "ĉ\u{200B}u ŝ\u{200D}i"
+        );
+        assert_eq!(with(Invisible::Remove).convert(input), "ĉu ŝi");
+    }
+
+    #[cfg(feature = "x-system")]
+    #[test]
+    fn test_protected_words_every_direction() {
+        let t = Pipeline::new()
+            .from(System::Utf8)
+            .to(System::X)
+            .protect(["ŝipo_id", ""])
+            .build();
+        assert_eq!(t.convert("ŝipo ŝipo_id"), "sxipo ŝipo_id");
+        let t = Pipeline::new().from(System::X).protect(["auxBuf"]).build();
+        assert_eq!(t.convert("auxbuf kaj auxdi"), "auxbuf kaj aŭdi");
+    }
+
+    #[cfg(all(feature = "x-system", feature = "h-system"))]
+    #[test]
+    fn test_exceptions_from_reader() {
+        let list = "^sux$\nkauk # (ŝ)\n";
+        let t =
+            Transliterator::with_exceptions_from(System::X, System::Utf8, list.as_bytes()).unwrap();
+        assert_eq!(t.convert("Sux suxno"), "Sux sŭno");
+        let t =
+            Transliterator::with_exceptions_from(System::H, System::Utf8, list.as_bytes()).unwrap();
+        assert_eq!(t.convert("shkauko"), "ŝkauko");
+    }
+
+    #[cfg(all(feature = "x-system", feature = "h-system"))]
+    #[test]
+    fn test_w_for_breve_mixed_conventions() {
+        let t = Pipeline::new().from(System::H).w_for_breve(true).build();
+        assert_eq!(
+            t.convert("hodiaw kaj hierau, Windows kaj Kuwait"),
+            "hodiaŭ kaj hieraŭ, Windows kaj Kuwait"
+        );
+        let t = Pipeline::new().to(System::X).w_for_breve(true).build();
+        assert_eq!(t.convert("ANTAŬ ĉirkaŭ"), "ANTAW cxirkaw");
+    }
+
+    #[cfg(feature = "x-system")]
+    #[test]
+    fn test_mapping() {
+        let t = Pipeline::new()
+            .from(System::X)
+            .mapping(&[('Ĉ', "ch"), ('ŭ', "w")])
+            .unwrap()
+            .build();
+        assert_eq!(t.convert("Cxu CXIUJ auxtoj?"), "Chu CHIUJ awtoj?");
+        let same = Pipeline::new().mapping(&[('ĉ', "ch")]).unwrap().build();
+        assert_eq!(same.convert("ĉu"), "chu");
+        assert_eq!(
+            Pipeline::new().mapping(&[('ĉ', "x"), ('ĝ', "X")]).unwrap_err(),
+            Error::AmbiguousMapping('ĝ', 'ĉ')
+        );
+        assert_eq!(
+            Pipeline::new().mapping(&[('c', "cx")]).unwrap_err(),
+            Error::NotHatted('c')
+        );
+        assert_eq!(
+            Pipeline::new().mapping(&[('ŝ', "")]).unwrap_err(),
+            Error::EmptyMapping('ŝ')
+        );
+    }
+
+    #[cfg(all(feature = "x-system", feature = "h-system"))]
+    #[test]
+    fn test_try_build() {
+        let refused = |pipeline: Pipeline| {
+            matches!(
+                pipeline.try_build(),
+                Err(Error::UnsupportedOptions(_))
+            )
+        };
+        assert!(refused(Pipeline::new().from(System::X).strict(true)));
+        assert!(refused(Pipeline::new().from(System::H).strict(true).lexicon(BuiltinLexicon)));
+        assert!(refused(Pipeline::new().to(System::X).braced_escapes(true)));
+        assert!(refused(Pipeline::new().to(System::X).escape('x')));
+        assert!(refused(Pipeline::new().only_esperanto(f32::NAN)));
+        assert!(refused(Pipeline::new().w_for_breve(true)));
+        assert!(!refused(Pipeline::new().from(System::H).plain_h(true).strict(true)));
+    }
+
+    #[cfg(feature = "h-system")]
+    #[test]
+    fn test_modern_k_with_plain_h() {
+        let t = Pipeline::new()
+            .from(System::H)
+            .plain_h(true)
+            .modern_k(true)
+            .build();
+        assert_eq!(t.convert("haoso kaj Hirurgo"), "kaoso kaj Kirurgo");
+    }
+
+    #[cfg(all(feature = "x-system", feature = "h-system"))]
+    #[test]
+    fn test_escaped_round_trips() {
+        let inputs = [
+            "Linux, AUXILIARY kaj \\ux ŝanĝo",
+            "Charles, Paulo kaj sĥ ĉh senchava \\ch hieraŭ",
+        ];
+        for system in [System::X, System::H] {
+            let to = Pipeline::new().to(system).escape('\\').build();
+            let from = Pipeline::new().from(system).escape('\\').build();
+            for input in inputs {
+                assert_eq!(from.convert(&to.convert(input)), input);
+            }
+        }
+        let to_h = Pipeline::new().to(System::H).escape('\\').build();
+        assert_eq!(to_h.convert("sĥ Paulo"), "s\\hh Pa\\ulo");
+    }
+
+    #[cfg(feature = "x-system")]
+    #[test]
+    fn test_alphabetic_escape_in_other_case() {
+        let t = Pipeline::new().from(System::X).escape('X').build();
+        assert_eq!(t.convert("cxx"), "cx");
+        assert_eq!(t.convert("cXx xx"), "cx x");
+    }
+
+    #[cfg(all(feature = "x-system", feature = "h-system"))]
+    #[test]
+    fn test_fast_matches_compact() {
+        let input = "Chiuj estas senchavaj kaj taugaj ideoj. EHHOSHANGHO";
+        let compact = Pipeline::new().from(System::H).to(System::X).build();
+        let fast = Pipeline::new()
+            .from(System::H)
+            .to(System::X)
+            .match_speed(MatchSpeed::Fast)
+            .build();
+        assert_eq!(fast.convert(input), compact.convert(input));
+    }
+
+    #[cfg(feature = "x-system")]
+    #[test]
+    fn test_decomposed_recognised_by_default() {
+        let t = Pipeline::new().from(System::Utf8).to(System::X).build();
+        assert_eq!(t.convert("c\u{302}u"), "cxu");
+    }
+
+    #[test]
+    fn test_same_system_composed_only_when_normalizing() {
+        let plain = Pipeline::new().build();
+        assert_eq!(plain.convert("c\u{302}u"), "c\u{302}u");
+        let normalizing = Pipeline::new().normalize_unicode(true).build();
+        assert_eq!(normalizing.convert("c\u{302}u"), "ĉu");
+    }
+
+    #[cfg(feature = "h-system")]
+    #[test]
+    fn test_lexicon_replaces_builtin_list() {
+        struct Nothing;
+        impl Lexicon for Nothing {
+            fn contains_root(&self, _: &str) -> bool {
+                false
+            }
+        }
+        let input = "senchava Saudaarabujo praulo";
+        let t = Pipeline::new().from(System::H).lexicon(Nothing).build();
+        assert_eq!(t.convert(input), "senĉava Saŭdaarabujo praulo");
+        let t = Pipeline::new()
+            .from(System::H)
+            .lexicon(BuiltinLexicon)
+            .build();
+        assert_eq!(t.convert(input), input);
+    }
+}
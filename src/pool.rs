@@ -0,0 +1,113 @@
+//! Sharing compiled converters between threads
+
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock, RwLock};
+
+use crate::{System, Transliterator};
+
+/// A lazily filled set of converters, one per pair of systems
+///
+/// Each converter is compiled the first time it is asked for and then shared
+/// by every caller. A lock is only taken to look the converter up; the
+/// conversions themselves run concurrently without any locking, since a
+/// `Transliterator` is `Send + Sync` and converts through `&self`.
+///
+#[cfg_attr(feature = "x-system", doc = "```")]
+#[cfg_attr(not(feature = "x-system"), doc = "```ignore")]
+/// use esperanto_text::{System, TransliteratorPool};
+/// use std::thread;
+///
+/// let handles: Vec<_> = (0..4)
+///     .map(|_| {
+///         thread::spawn(|| {
+///             let pool = TransliteratorPool::global();
+///             pool.convert("cxu", System::X, System::Utf8)
+///         })
+///     })
+///     .collect();
+/// for h in handles {
+///     assert_eq!(h.join().unwrap(), "ĉu");
+/// }
+/// ```
+#[derive(Default)]
+pub struct TransliteratorPool {
+    converters: RwLock<HashMap<(System, System), Arc<Transliterator>>>,
+}
+
+impl TransliteratorPool {
+    /// Create an empty pool
+    pub fn new() -> TransliteratorPool {
+        TransliteratorPool::default()
+    }
+
+    /// A pool shared by the whole process
+    pub fn global() -> &'static TransliteratorPool {
+        static GLOBAL: OnceLock<TransliteratorPool> = OnceLock::new();
+        GLOBAL.get_or_init(TransliteratorPool::new)
+    }
+
+    /// Get a handle to the converter between two systems, compiling it if needed
+    pub fn get(&self, from: System, to: System) -> Arc<Transliterator> {
+        // The map is never left half-updated, so a poisoned lock is still usable
+        let converters = self
+            .converters
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(t) = converters.get(&(from, to)) {
+            return Arc::clone(t);
+        }
+        drop(converters);
+
+        let mut converters = self
+            .converters
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        Arc::clone(
+            converters
+                .entry((from, to))
+                .or_insert_with(|| Arc::new(Transliterator::new(from, to))),
+        )
+    }
+
+    /// Convert a string using the pool's converter for these systems
+    pub fn convert(&self, s: &str, from: System, to: System) -> String {
+        self.get(from, to).convert(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_converters_are_thread_safe() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Transliterator>();
+        assert_send_sync::<TransliteratorPool>();
+    }
+
+    #[cfg(all(feature = "x-system", feature = "h-system"))]
+    #[test]
+    fn test_pool_reuses_converter() {
+        let pool = TransliteratorPool::new();
+        let a = pool.get(System::H, System::Utf8);
+        let b = pool.get(System::H, System::Utf8);
+        assert!(Arc::ptr_eq(&a, &b));
+        assert!(!Arc::ptr_eq(&a, &pool.get(System::X, System::Utf8)));
+    }
+
+    #[cfg(all(feature = "x-system", feature = "h-system"))]
+    #[test]
+    fn test_pool_concurrent_conversions() {
+        let pool = TransliteratorPool::new();
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    for _ in 0..100 {
+                        assert_eq!(pool.convert("chu", System::H, System::X), "cxu");
+                    }
+                });
+            }
+        });
+    }
+}
@@ -0,0 +1,88 @@
+/*!
+Opt-in extension trait for converting string slices in method-call style.
+
+*/
+#![cfg_attr(
+    all(
+        feature = "x-system",
+        feature = "h-system",
+        feature = "to-utf8",
+        feature = "from-utf8"
+    ),
+    doc = "```"
+)]
+#![cfg_attr(
+    not(all(
+        feature = "x-system",
+        feature = "h-system",
+        feature = "to-utf8",
+        feature = "from-utf8"
+    )),
+    doc = "```ignore"
+)]
+/*!
+use esperanto_text::prelude::*;
+
+assert_eq!("sxangxo".x_to_utf8(), "ŝanĝo");
+assert_eq!("ŝanĝo".to_h_system(), "shangho");
+```
+*/
+
+/// Conversion methods available on any `str` once the prelude is imported
+pub trait EsperantoExt {
+    /// Convert x-system "jxauxdo" to UTF-8 "ĵaŭdo"
+    #[cfg(all(feature = "x-system", feature = "to-utf8"))]
+    fn x_to_utf8(&self) -> String;
+
+    /// Convert h-system "jhaudo" to UTF-8 "ĵaŭdo"
+    #[cfg(all(feature = "h-system", feature = "to-utf8"))]
+    fn h_to_utf8(&self) -> String;
+
+    /// Convert UTF-8 "ĵaŭdo" to x-system "jxauxdo"
+    #[cfg(all(feature = "x-system", feature = "from-utf8"))]
+    fn to_x_system(&self) -> String;
+
+    /// Convert UTF-8 "ĵaŭdo" to h-system "jhaudo"
+    #[cfg(all(feature = "h-system", feature = "from-utf8"))]
+    fn to_h_system(&self) -> String;
+}
+
+impl EsperantoExt for str {
+    #[cfg(all(feature = "x-system", feature = "to-utf8"))]
+    fn x_to_utf8(&self) -> String {
+        crate::x_system_to_utf8(self)
+    }
+
+    #[cfg(all(feature = "h-system", feature = "to-utf8"))]
+    fn h_to_utf8(&self) -> String {
+        crate::h_system_to_utf8(self)
+    }
+
+    #[cfg(all(feature = "x-system", feature = "from-utf8"))]
+    fn to_x_system(&self) -> String {
+        crate::utf8_to_x_system(self)
+    }
+
+    #[cfg(all(feature = "h-system", feature = "from-utf8"))]
+    fn to_h_system(&self) -> String {
+        crate::utf8_to_h_system(self)
+    }
+}
+
+#[cfg(all(test, feature = "h-system", feature = "to-utf8"))]
+mod tests {
+    use super::*;
+
+    #[cfg(all(feature = "x-system", feature = "from-utf8"))]
+    #[test]
+    fn test_ext_chained_in_iterator() {
+        let words = ["cxu".to_owned(), "sxi".to_owned()];
+        let converted: Vec<String> = words.iter().map(|w| w.x_to_utf8().to_h_system()).collect();
+        assert_eq!(converted, vec!["chu", "shi"]);
+    }
+
+    #[test]
+    fn test_ext_h_to_utf8() {
+        assert_eq!("Chiuj estas senchavaj".h_to_utf8(), "Ĉiuj estas senchavaj");
+    }
+}
@@ -0,0 +1,113 @@
+//! Checking that converting a text and converting it back loses nothing
+
+use std::ops::Range;
+
+use crate::token::runs;
+use crate::{System, Transliterator};
+
+/// The outcome of converting a text to another system and back
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RoundTrip {
+    /// The text in the other system
+    pub converted: String,
+    /// Byte ranges of the original text that came back different
+    ///
+    /// Each range covers a whole word, or the text between two words.
+    pub divergent: Vec<Range<usize>>,
+}
+
+impl RoundTrip {
+    /// Whether the text came back exactly as it was
+    pub fn is_lossless(&self) -> bool {
+        self.divergent.is_empty()
+    }
+}
+
+/// Convert `s` from `from` to `to` and back again, reporting what changed
+///
+#[cfg_attr(all(feature = "x-system", feature = "h-system"), doc = "```")]
+#[cfg_attr(not(all(feature = "x-system", feature = "h-system")), doc = "```ignore")]
+/// use esperanto_text::{verify_roundtrip, System};
+///
+/// let text = "Ĉu Linux funkcias?";
+/// let check = verify_roundtrip(text, System::Utf8, System::X);
+/// assert_eq!(check.converted, "Cxu Linux funkcias?");
+/// assert!(check.is_lossless());
+///
+/// let check = verify_roundtrip("Ĉu senĉava?", System::Utf8, System::H);
+/// assert_eq!(check.divergent, vec![4..12]);
+/// ```
+pub fn verify_roundtrip(s: &str, from: System, to: System) -> RoundTrip {
+    let forward = Transliterator::new(from, to);
+    let back = Transliterator::new(to, from);
+    verify_roundtrip_with(s, &forward, &back)
+}
+
+/// Like [`verify_roundtrip`](fn.verify_roundtrip.html), with converters built
+/// elsewhere, for example with escaping turned on
+pub fn verify_roundtrip_with(
+    s: &str,
+    forward: &Transliterator,
+    back: &Transliterator,
+) -> RoundTrip {
+    let converted = forward.convert(s);
+    let returned = back.convert(&converted);
+    RoundTrip {
+        divergent: divergent_runs(s, &returned),
+        converted,
+    }
+}
+
+/// Byte ranges of `a` whose words or gaps differ from those of `b`
+///
+/// Conversion never joins or splits words, so the runs of the two texts
+/// line up one to one. Should the counts differ anyway, everything from the
+/// first mismatch on is reported.
+fn divergent_runs(a: &str, b: &str) -> Vec<Range<usize>> {
+    let mut divergent = Vec::new();
+    let mut runs_b = runs(b);
+    let mut pos = 0;
+    for (run, _) in runs(a) {
+        let range = pos..pos + run.len();
+        pos += run.len();
+        match runs_b.next() {
+            Some((other, _)) if other == run => (),
+            Some(_) => divergent.push(range),
+            None => {
+                divergent.push(range.start..a.len());
+                return divergent;
+            }
+        }
+    }
+    if runs_b.next().is_some() {
+        divergent.push(a.len()..a.len());
+    }
+    divergent
+}
+
+#[cfg(all(test, feature = "x-system"))]
+mod tests {
+    use super::*;
+    use crate::Pipeline;
+
+    #[cfg(feature = "h-system")]
+    #[test]
+    fn test_lossy_words_reported() {
+        let text = "Ĉu Linux aŭ Unix? Ŝi diris senĉava";
+        let check = verify_roundtrip(text, System::Utf8, System::H);
+        let words: Vec<&str> = check.divergent.iter().map(|r| &text[r.clone()]).collect();
+        assert_eq!(words, vec!["senĉava"]);
+
+        let check = verify_roundtrip(text, System::Utf8, System::X);
+        assert!(check.is_lossless());
+    }
+
+    #[test]
+    fn test_escaping_makes_lossless() {
+        let text = "auxiliary cx";
+        assert!(!verify_roundtrip(text, System::Utf8, System::X).is_lossless());
+        let forward = Pipeline::new().to(System::X).escape('\\').build();
+        let back = Pipeline::new().from(System::X).escape('\\').build();
+        assert!(verify_roundtrip_with(text, &forward, &back).is_lossless());
+    }
+}
@@ -0,0 +1,137 @@
+//! Splitting text into sentences
+
+use std::ops::Range;
+
+/// Abbreviations that never end a sentence, in lowercase without their
+/// final full stop
+const ABBREVIATIONS: &[&str] = &[
+    "bv", "ĉ", "d-ro", "d-rino", "ekz", "f-ino", "kp", "n-ro", "p", "pp", "prof", "resp", "s-ino",
+    "s-ro", "sinj", "t.e", "t.n", "vd",
+];
+
+/// Whether `c` can follow the end of a sentence before the space, like a
+/// closing quotation mark or bracket
+fn is_closing(c: char) -> bool {
+    matches!(c, '"' | '\'' | ')' | ']' | '»' | '”' | '’')
+}
+
+/// Whether the full stop at byte `dot` of `s` is part of an abbreviation or
+/// an initial rather than the end of a sentence
+fn is_abbreviation(s: &str, dot: usize) -> bool {
+    let start = s[..dot]
+        .char_indices()
+        .rev()
+        .find(|(_, c)| c.is_whitespace() || matches!(c, '(' | '"' | '«' | '“'))
+        .map_or(0, |(i, c)| i + c.len_utf8());
+    let word = s[start..dot].to_lowercase();
+    let mut chars = s[start..dot].chars();
+    let initial = matches!((chars.next(), chars.next()), (Some(c), None) if c.is_uppercase());
+    initial || ABBREVIATIONS.contains(&word.as_str())
+}
+
+/// Find the sentences in `s`, as byte ranges without surrounding whitespace
+///
+/// A sentence ends with ".", "!", "?" or "…", and any closing quotation
+/// marks or brackets, before a space and a capital letter, digit or opening
+/// quotation mark. Abbreviations like "ekz." and "s-ro" and initials like
+/// "L." don't end a sentence, while "k.t.p." does if a capital follows. A
+/// blank line always ends one.
+///
+/// ```
+/// use esperanto_text::split_sentences;
+///
+/// let text = "Mi vidis ekz. S-ron Petro. Li diris: \"Saluton!\" Kaj foriris.";
+/// let sentences: Vec<&str> = split_sentences(text).into_iter().map(|r| &text[r]).collect();
+/// assert_eq!(
+///     sentences,
+///     ["Mi vidis ekz. S-ron Petro.", "Li diris: \"Saluton!\"", "Kaj foriris."]
+/// );
+/// ```
+pub fn split_sentences(s: &str) -> Vec<Range<usize>> {
+    let mut boundaries = Vec::new();
+    let chars: Vec<(usize, char)> = s.char_indices().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        let (pos, c) = chars[i];
+        if c == '\n' {
+            let blank = chars[i + 1..]
+                .iter()
+                .take_while(|(_, c)| c.is_whitespace())
+                .any(|(_, c)| *c == '\n');
+            if blank {
+                boundaries.push(pos);
+            }
+        }
+        if !matches!(c, '.' | '!' | '?' | '…') {
+            i += 1;
+            continue;
+        }
+        let mut end = i + 1;
+        while end < chars.len()
+            && (matches!(chars[end].1, '.' | '!' | '?' | '…') || is_closing(chars[end].1))
+        {
+            end += 1;
+        }
+        let mut next = end;
+        while next < chars.len() && chars[next].1.is_whitespace() {
+            next += 1;
+        }
+        let starts_sentence = match chars.get(next) {
+            Some((_, c)) => {
+                next > end
+                    && (c.is_uppercase()
+                        || c.is_ascii_digit()
+                        || matches!(c, '"' | '«' | '“' | '('))
+            }
+            None => false,
+        };
+        if starts_sentence && !(c == '.' && end == i + 1 && is_abbreviation(s, pos)) {
+            boundaries.push(chars.get(end).map_or(s.len(), |(p, _)| *p));
+        }
+        i = end;
+    }
+    boundaries.push(s.len());
+    let mut sentences = Vec::new();
+    let mut start = 0;
+    for end in boundaries {
+        let text = &s[start..end];
+        let trimmed_start = start + (text.len() - text.trim_start().len());
+        let trimmed_end = start + text.trim_end().len();
+        if trimmed_start < trimmed_end {
+            sentences.push(trimmed_start..trimmed_end);
+        }
+        start = end;
+    }
+    sentences
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(s: &str) -> Vec<&str> {
+        split_sentences(s).into_iter().map(|r| &s[r]).collect()
+    }
+
+    #[test]
+    fn test_split_sentences() {
+        assert_eq!(
+            split("Pomoj, piroj k.t.p. Ĉio estis freŝa. D-ro L. L. Zamenhof naskiĝis en 1859."),
+            [
+                "Pomoj, piroj k.t.p.",
+                "Ĉio estis freŝa.",
+                "D-ro L. L. Zamenhof naskiĝis en 1859."
+            ]
+        );
+        assert_eq!(
+            split("Ĉu vere?! Jes... 3 fojojn."),
+            ["Ĉu vere?!", "Jes...", "3 fojojn."]
+        );
+        assert_eq!(split("Titolo\n\nla teksto"), ["Titolo", "la teksto"]);
+        assert_eq!(
+            split("t.e. la unua. ne la dua"),
+            ["t.e. la unua. ne la dua"]
+        );
+        assert_eq!(split("  "), Vec::<&str>::new());
+    }
+}
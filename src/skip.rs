@@ -0,0 +1,146 @@
+//! Finding spans of text, such as links and hashtags, that must not be converted
+
+use std::ops::Range;
+
+/// Characters that may surround a token without being part of it
+const OPENERS: &[char] = &['(', '[', '{', '<', '"', '\'', '“', '‘', '«'];
+const CLOSERS: &[char] = &[
+    ')', ']', '}', '>', '"', '\'', '”', '’', '»', '.', ',', ';', ':', '!', '?',
+];
+
+/// Iterate over the whitespace-separated tokens of `s` with their byte ranges,
+/// trimmed of surrounding brackets, quotes and punctuation
+fn tokens(s: &str) -> impl Iterator<Item = Range<usize>> + '_ {
+    let mut pos = 0;
+    s.split_inclusive(char::is_whitespace)
+        .filter_map(move |piece| {
+            let start = pos;
+            pos += piece.len();
+            let body = piece.trim_end();
+            let lead = body.len() - body.trim_start_matches(OPENERS).len();
+            let trimmed = body[lead..].trim_end_matches(CLOSERS);
+            if trimmed.is_empty() {
+                return None;
+            }
+            Some(start + lead..start + lead + trimmed.len())
+        })
+}
+
+/// Byte ranges of the URLs, email addresses and domain names in `s`
+pub(crate) fn link_spans(s: &str) -> Vec<Range<usize>> {
+    tokens(s).filter(|r| is_link(&s[r.clone()])).collect()
+}
+
+/// Whether a token is a URL, email address or domain name
+fn is_link(token: &str) -> bool {
+    if let Some(i) = token.find("://") {
+        return i > 0 && token[..i].chars().all(|c| c.is_ascii_alphanumeric());
+    }
+    if token.len() > 4 && token[..4].eq_ignore_ascii_case("www.") {
+        return true;
+    }
+    if let Some((local, domain)) = token.split_once('@') {
+        return !local.is_empty() && is_domain(domain);
+    }
+    is_domain(token.split('/').next().unwrap_or(""))
+}
+
+/// Whether `host` looks like a domain name such as "example.com"
+fn is_domain(host: &str) -> bool {
+    let labels: Vec<&str> = host.split('.').collect();
+    let tld = labels[labels.len() - 1];
+    labels.len() > 1
+        && labels
+            .iter()
+            .all(|l| !l.is_empty() && l.chars().all(|c| c.is_ascii_alphanumeric() || c == '-'))
+        && (2..=6).contains(&tld.len())
+        && tld.chars().all(|c| c.is_ascii_lowercase())
+}
+
+/// Byte ranges of the @mentions, #hashtags and :emoji: codes in `s`
+pub(crate) fn social_spans(s: &str) -> Vec<Range<usize>> {
+    let mut spans = Vec::new();
+    let mut chars = s.char_indices().peekable();
+    let mut prev = None;
+    while let Some((i, c)) = chars.next() {
+        let at_boundary = !prev.is_some_and(is_name_char);
+        prev = Some(c);
+        if !at_boundary || !matches!(c, '@' | '#' | ':') {
+            continue;
+        }
+        let rest = &s[i + 1..];
+        let end = if c == ':' {
+            // Shortcodes need a closing colon
+            let len = rest.find(|c| !is_shortcode_char(c)).unwrap_or(rest.len());
+            if len == 0 || !rest[len..].starts_with(':') {
+                continue;
+            }
+            i + len + 2
+        } else {
+            let len = rest.find(|c| !is_name_char(c)).unwrap_or(rest.len());
+            if len == 0 {
+                continue;
+            }
+            i + len + 1
+        };
+        spans.push(i..end);
+        while chars.peek().is_some_and(|(j, _)| *j < end) {
+            prev = chars.next().map(|(_, c)| c);
+        }
+    }
+    spans
+}
+
+/// Whether `c` can be part of a username or hashtag
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Whether `c` can be part of an emoji shortcode like ":thumbs_up:"
+fn is_shortcode_char(c: char) -> bool {
+    c.is_ascii_lowercase() || c.is_ascii_digit() || "_+-".contains(c)
+}
+
+/// Whether `start..end` overlaps any of `spans`
+pub(crate) fn overlaps(spans: &[Range<usize>], start: usize, end: usize) -> bool {
+    spans.iter().any(|r| r.start < end && start < r.end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn links(s: &str) -> Vec<&str> {
+        link_spans(s).into_iter().map(|r| &s[r]).collect()
+    }
+
+    #[test]
+    fn test_link_spans() {
+        let s = "Vidu (https://github.com/auth), github.com/auth aux skribu al sasha@example.org. \
+                 Kaj www.chielo.net!";
+        assert_eq!(
+            links(s),
+            vec![
+                "https://github.com/auth",
+                "github.com/auth",
+                "sasha@example.org",
+                "www.chielo.net"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_social_spans() {
+        let s = "@sasha_ch: #ĉiuj #flughaveno :smile_cat: :+1: sasha@ex.com 10:30:00 #";
+        let found: Vec<&str> = social_spans(s).into_iter().map(|r| &s[r]).collect();
+        assert_eq!(
+            found,
+            vec!["@sasha_ch", "#ĉiuj", "#flughaveno", ":smile_cat:", ":+1:"]
+        );
+    }
+
+    #[test]
+    fn test_not_links() {
+        assert!(links("k.t.p. Fino.Sekva frazo, 3.14 kaj @sasha").is_empty());
+    }
+}
@@ -0,0 +1,184 @@
+//! Preparing text for a speech synthesizer
+
+use crate::canonical;
+use crate::ipa::utf8_to_ipa;
+use crate::number::{expand_ordinals, number_to_words};
+
+/// Abbreviations written with full stops and what they stand for
+const DOTTED: &[(&str, &str)] = &[
+    ("k.t.p.", "kaj tiel plu"), ("k.s.", "kaj simile"), ("k.a.", "kaj aliaj"),
+    ("t.e.", "tio estas"), ("t.n.", "tiel nomata"), ("ekz.", "ekzemple"), ("bv.", "bonvolu"),
+    ("kp.", "komparu"), ("vd.", "vidu"), ("prof.", "profesoro"), ("resp.", "respektive"),
+    ("ĉ.", "ĉirkaŭ"),
+];
+
+/// Abbreviations that end a list, and so often end a sentence too
+const LIST_ENDINGS: &[&str] = &["k.t.p.", "k.s.", "k.a."];
+
+/// Abbreviations with a hyphen, which take the noun endings, and the roots
+/// they stand for
+const HYPHENATED: &[(&str, &str)] = &[
+    ("d-rin", "doktorin"), ("d-r", "doktor"), ("s-in", "sinjorin"), ("s-r", "sinjor"),
+    ("f-in", "fraŭlin"), ("n-r", "numer"),
+];
+
+/// The noun endings a hyphenated abbreviation can have, longest first
+const ENDINGS: &[&str] = &["ojn", "oj", "on", "o"];
+
+/// Prepare Esperanto text to be read aloud by a speech synthesizer
+///
+/// The text is read in any system and written in UTF-8, with common
+/// abbreviations like "k.t.p." and "d-ro" expanded and numbers written out
+/// in words. Ordinals like "3-a" become "tria".
+///
+#[cfg_attr(feature = "x-system", doc = "```")]
+#[cfg_attr(not(feature = "x-system"), doc = "```ignore")]
+/// use esperanto_text::prepare_for_tts;
+///
+/// assert_eq!(
+///     prepare_for_tts("D-ro Zamenhof naskigxis en 1859, la 15-an de decembro."),
+///     "Doktoro Zamenhof naskiĝis en mil okcent kvindek naŭ, la dek-kvinan de decembro."
+/// );
+/// assert_eq!(prepare_for_tts("pomoj, piroj k.t.p."), "pomoj, piroj kaj tiel plu.");
+/// ```
+pub fn prepare_for_tts(s: &str) -> String {
+    let text: String = canonical::chars(s).collect();
+    let text = expand_abbreviations(&text);
+    let text = expand_ordinals(&text);
+    expand_numbers(&text)
+}
+
+/// Prepare Esperanto text for a speech synthesizer, transcribed into IPA
+///
+/// This is [`prepare_for_tts`](fn.prepare_for_tts.html) followed by
+/// [`utf8_to_ipa`](fn.utf8_to_ipa.html).
+///
+/// ```
+/// use esperanto_text::prepare_for_tts_ipa;
+///
+/// assert_eq!(prepare_for_tts_ipa("S-ro Ŝmit"), "sinˈjo.ro ʃmit");
+/// ```
+pub fn prepare_for_tts_ipa(s: &str) -> String {
+    utf8_to_ipa(&prepare_for_tts(s))
+}
+
+/// Replace the abbreviations in `s` with the words they stand for
+fn expand_abbreviations(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut pos = 0;
+    while pos < s.len() {
+        let at_word_start = !s[..pos].ends_with(char::is_alphanumeric);
+        let found = if at_word_start {
+            abbreviation_at(&s[pos..])
+        } else {
+            None
+        };
+        match found {
+            Some((len, expansion)) => {
+                let rest = &s[pos + len..];
+                if s[pos..].starts_with(char::is_uppercase) {
+                    let mut chars = expansion.chars();
+                    out.extend(chars.next().into_iter().flat_map(char::to_uppercase));
+                    out.push_str(chars.as_str());
+                } else {
+                    out.push_str(&expansion);
+                }
+                // The full stop of an abbreviation can also end its sentence
+                let abbreviation = s[pos..pos + len].to_lowercase();
+                if abbreviation.ends_with('.') && ends_sentence(&abbreviation, rest) {
+                    out.push('.');
+                }
+                pos += len;
+            }
+            None => {
+                let c = s[pos..].chars().next().unwrap_or_default();
+                out.push(c);
+                pos += c.len_utf8();
+            }
+        }
+    }
+    out
+}
+
+/// The length of the abbreviation at the start of `s` and its expansion
+fn abbreviation_at(s: &str) -> Option<(usize, String)> {
+    // No abbreviation is longer than ten characters, including what follows
+    let head = &s[..s.char_indices().nth(10).map_or(s.len(), |(i, _)| i)];
+    let lower = head.to_lowercase();
+    // Lowercasing only changes the length of letters outside Esperanto
+    if lower.len() != head.len() {
+        return None;
+    }
+    let whole_word = |len: usize| !lower[len..].starts_with(char::is_alphanumeric);
+    for (abbreviation, expansion) in DOTTED {
+        if lower.starts_with(abbreviation) && whole_word(abbreviation.len()) {
+            return Some((abbreviation.len(), (*expansion).to_owned()));
+        }
+    }
+    for (abbreviation, root) in HYPHENATED {
+        let rest = match lower.strip_prefix(abbreviation) {
+            Some(rest) => rest,
+            None => continue,
+        };
+        for ending in ENDINGS {
+            let len = abbreviation.len() + ending.len();
+            if rest.starts_with(ending) && whole_word(len) {
+                return Some((len, format!("{}{}", root, ending)));
+            }
+        }
+    }
+    None
+}
+
+/// Whether `abbreviation` ends its sentence, given the text after it
+///
+/// Like [`split_sentences`](fn.split_sentences.html), only abbreviations
+/// that end a list can end a sentence before a capital letter.
+fn ends_sentence(abbreviation: &str, rest: &str) -> bool {
+    let next = rest.trim_start();
+    next.is_empty()
+        || LIST_ENDINGS.contains(&abbreviation)
+            && rest.len() > next.len()
+            && next.starts_with(char::is_uppercase)
+}
+
+/// Write out each run of digits in `s` as a number in words
+fn expand_numbers(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(start) = rest.find(|c: char| c.is_ascii_digit()) {
+        let end = rest[start..]
+            .find(|c: char| !c.is_ascii_digit())
+            .map_or(rest.len(), |i| start + i);
+        out.push_str(&rest[..start]);
+        match rest[start..end].parse::<u64>() {
+            Ok(n) => out.push_str(&number_to_words(n)),
+            // Too long to be a number, so read it digit by digit
+            Err(_) => {
+                let digits: Vec<String> = rest[start..end]
+                    .bytes()
+                    .map(|d| number_to_words(u64::from(d - b'0')))
+                    .collect();
+                out.push_str(&digits.join(" "));
+            }
+        }
+        rest = &rest[end..];
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_abbreviations() {
+        assert_eq!(
+            prepare_for_tts("Mi vidis s-inon Kim kaj d-rojn, ekz. Petro k.t.p. Poste ni iris."),
+            "Mi vidis sinjorinon Kim kaj doktorojn, ekzemple Petro kaj tiel plu. Poste ni iris."
+        );
+        assert_eq!(prepare_for_tts("d-rox kaj sk.t.p."), "d-rox kaj sk.t.p.");
+        assert_eq!(prepare_for_tts("N-ro 7"), "Numero sep");
+    }
+}
@@ -0,0 +1,83 @@
+//! Spelling words out letter by letter with the Esperanto letter names
+
+/// The name of each lowercase letter, including the foreign letters
+const NAMES: &[(char, &str)] = &[
+    ('a', "a"), ('b', "bo"), ('c', "co"), ('ĉ', "ĉo"), ('d', "do"), ('e', "e"), ('f', "fo"),
+    ('g', "go"), ('ĝ', "ĝo"), ('h', "ho"), ('ĥ', "ĥo"), ('i', "i"), ('j', "jo"), ('ĵ', "ĵo"),
+    ('k', "ko"), ('l', "lo"), ('m', "mo"), ('n', "no"), ('o', "o"), ('p', "po"), ('q', "kuo"),
+    ('r', "ro"), ('s', "so"), ('ŝ', "ŝo"), ('t', "to"), ('u', "u"), ('ŭ', "ŭo"), ('v', "vo"),
+    ('w', "duobla vo"), ('x', "ikso"), ('y', "ipsilono"), ('z', "zo"),
+];
+
+/// Spell out `word` with the name of each letter, as when spelling over the
+/// phone
+///
+/// The names are separated by ", ". Case is lost, and anything that isn't a
+/// letter, such as a digit or hyphen, is given as it is.
+///
+/// ```
+/// use esperanto_text::spell_out;
+///
+/// assert_eq!(spell_out("ĉu"), "ĉo, u");
+/// assert_eq!(spell_out("Ŝaŭmo"), "ŝo, a, ŭo, mo, o");
+/// ```
+pub fn spell_out(word: &str) -> String {
+    word.chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| {
+            let lower = c.to_lowercase().next().unwrap_or(c);
+            match NAMES.iter().find(|(l, _)| *l == lower) {
+                Some((_, name)) => (*name).to_owned(),
+                None => c.to_string(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Read a word back from the names of its letters
+///
+/// The names may be separated by commas, spaces or both. Returns `None` if
+/// any of them isn't a letter name or a single character.
+///
+/// ```
+/// use esperanto_text::parse_spelling;
+///
+/// assert_eq!(parse_spelling("ĉo, u").as_deref(), Some("ĉu"));
+/// assert_eq!(parse_spelling("duobla vo a ikso").as_deref(), Some("wax"));
+/// assert_eq!(parse_spelling("ĉo, foo"), None);
+/// ```
+pub fn parse_spelling(s: &str) -> Option<String> {
+    let lower = s.to_lowercase();
+    let mut tokens = lower
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|t| !t.is_empty())
+        .peekable();
+    let mut word = String::new();
+    while let Some(token) = tokens.next() {
+        // Only "duobla vo" takes two words
+        if token == "duobla" && tokens.peek() == Some(&"vo") {
+            tokens.next();
+            word.push('w');
+            continue;
+        }
+        match NAMES.iter().find(|(_, name)| *name == token) {
+            Some((letter, _)) => word.push(*letter),
+            None if token.chars().count() == 1 => word.push_str(token),
+            None => return None,
+        }
+    }
+    Some(word)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        for word in &["ĉiuĵaŭde", "ĥoro-2", "weekend"] {
+            assert_eq!(parse_spelling(&spell_out(word)).as_deref(), Some(*word));
+        }
+    }
+}
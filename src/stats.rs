@@ -0,0 +1,39 @@
+//! Counting which rules decided the output of a conversion
+
+use std::collections::BTreeMap;
+
+/// How often each kind of rule fired while converting
+///
+/// Fill one in with
+/// [`Transliterator::convert_with_stats`](struct.Transliterator.html#method.convert_with_stats).
+/// Passing the same `RuleStats` to many conversions adds up the counts for a
+/// whole corpus.
+///
+#[cfg_attr(feature = "h-system", doc = "```")]
+#[cfg_attr(not(feature = "h-system"), doc = "```ignore")]
+/// use esperanto_text::{RuleStats, System, Transliterator};
+///
+/// let t = Transliterator::new(System::H, System::Utf8);
+/// let mut stats = RuleStats::default();
+/// t.convert_with_stats("senchava chambro", &mut stats);
+/// t.convert_with_stats("la praulo estis senchava", &mut stats);
+/// assert_eq!(stats.exceptions["senchav"], 2);
+/// assert_eq!(stats.digraphs, 1);
+/// assert_eq!(stats.compounds, 1);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RuleStats {
+    /// How many times each exception fragment left text alone, by fragment
+    /// in lowercase
+    pub exceptions: BTreeMap<String, usize>,
+    /// Digraphs that were converted to a hatted letter
+    pub digraphs: usize,
+    /// "au" or "aux" converted to "aŭ"
+    pub breves: usize,
+    /// h-system digraphs and "au" left alone because they are in a compound
+    /// word or a word the lexicon knows
+    pub compounds: usize,
+    /// Matches left alone because they were in a link, mention, proper noun
+    /// or other skipped text
+    pub skipped: usize,
+}
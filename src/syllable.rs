@@ -0,0 +1,127 @@
+//! Splitting Esperanto words into syllables
+
+/// Whether `c` is a lowercase Esperanto vowel, the nucleus of a syllable
+pub(crate) fn is_vowel(c: char) -> bool {
+    matches!(c, 'a' | 'e' | 'i' | 'o' | 'u')
+}
+
+/// Whether `a` followed by `b` can begin a syllable, as in "tr" or "bl"
+fn is_onset_cluster(a: char, b: char) -> bool {
+    "bcĉdfgĝkpstv".contains(a) && (b == 'l' || b == 'r')
+}
+
+/// The index in `letters` where each syllable begins
+///
+/// The letters must be lowercase. Of the consonants between two vowels only
+/// the last begins the next syllable, or the last two if they are a stop or
+/// fricative followed by "l" or "r". A word without vowels is one syllable.
+pub(crate) fn syllable_starts(letters: &[char]) -> Vec<usize> {
+    let vowels: Vec<usize> = (0..letters.len())
+        .filter(|&i| is_vowel(letters[i]))
+        .collect();
+    let mut starts = vec![0];
+    for pair in vowels.windows(2) {
+        let (first, next) = (pair[0], pair[1]);
+        let start = match next - first - 1 {
+            0 => next,
+            1 => next - 1,
+            _ if is_onset_cluster(letters[next - 2], letters[next - 1]) => next - 2,
+            _ => next - 1,
+        };
+        starts.push(start);
+    }
+    starts
+}
+
+/// The byte offset in `word` where each syllable begins
+fn starts(word: &str) -> Vec<usize> {
+    let (offsets, letters): (Vec<usize>, Vec<char>) = word
+        .char_indices()
+        .map(|(i, c)| (i, c.to_lowercase().next().unwrap_or(c)))
+        .unzip();
+    syllable_starts(&letters)
+        .into_iter()
+        .filter_map(|i| offsets.get(i).copied())
+        .collect()
+}
+
+/// Split a UTF-8 word into its syllables
+///
+/// Each syllable has one vowel. Of the consonants between two vowels only
+/// the last begins the next syllable, unless the last two are a pair like
+/// "tr" or "bl" that can begin one together. Text in another system should
+/// be converted to UTF-8 first, so that a digraph isn't split.
+///
+/// ```
+/// use esperanto_text::syllabify;
+///
+/// assert_eq!(syllabify("Esperanto"), ["Es", "pe", "ran", "to"]);
+/// assert_eq!(syllabify("ekstreme"), ["eks", "tre", "me"]);
+/// assert_eq!(syllabify("aŭtobuso"), ["aŭ", "to", "bu", "so"]);
+/// ```
+pub fn syllabify(word: &str) -> Vec<&str> {
+    if word.is_empty() {
+        return Vec::new();
+    }
+    let mut starts = starts(word);
+    starts.push(word.len());
+    starts.windows(2).map(|w| &word[w[0]..w[1]]).collect()
+}
+
+/// The byte offsets in a UTF-8 word where it may be hyphenated at the end of
+/// a line
+///
+/// These are the syllable breaks found by [`syllabify`](fn.syllabify.html),
+/// except that at least two letters are kept on each side.
+///
+/// ```
+/// use esperanto_text::hyphenation_points;
+///
+/// assert_eq!(hyphenation_points("ŝanĝiĝo"), [4, 7]);
+/// assert_eq!(hyphenation_points("ideo"), Vec::<usize>::new());
+/// ```
+pub fn hyphenation_points(word: &str) -> Vec<usize> {
+    let chars_before = |i: usize| word[..i].chars().count();
+    let total = word.chars().count();
+    starts(word)
+        .into_iter()
+        .filter(|&i| chars_before(i) >= 2 && total - chars_before(i) >= 2)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(word: &str) -> String {
+        let letters: Vec<char> = word.chars().collect();
+        let starts = syllable_starts(&letters);
+        let mut out = String::new();
+        for (i, c) in letters.iter().enumerate() {
+            if i > 0 && starts.contains(&i) {
+                out.push('-');
+            }
+            out.push(*c);
+        }
+        out
+    }
+
+    #[test]
+    fn test_syllable_starts() {
+        assert_eq!(split("esperanto"), "es-pe-ran-to");
+        assert_eq!(split("ekstra"), "eks-tra");
+        assert_eq!(split("kiuj"), "ki-uj");
+        assert_eq!(split("aŭtobuso"), "aŭ-to-bu-so");
+        assert_eq!(split("ŝtrumpo"), "ŝtrum-po");
+        assert_eq!(split("kaj"), "kaj");
+    }
+
+    #[test]
+    fn test_syllabify() {
+        assert_eq!(syllabify(""), Vec::<&str>::new());
+        assert_eq!(syllabify("ŜTRUMPO"), ["ŜTRUM", "PO"]);
+        assert_eq!(syllabify("pfff"), ["pfff"]);
+        assert_eq!(hyphenation_points("lingvo"), [4]);
+        assert_eq!(hyphenation_points("ekstreme"), [3, 6]);
+    }
+}
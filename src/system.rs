@@ -0,0 +1,55 @@
+//! The ways of writing Esperanto text supported by this crate
+
+/// A way of writing the Esperanto letters that carry diacritics
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum System {
+    /// Proper UTF-8 characters, e.g. "ĵaŭdo"
+    Utf8,
+    /// x-system, e.g. "jxauxdo"
+    #[cfg(feature = "x-system")]
+    X,
+    /// h-system, e.g. "jhaudo"
+    #[cfg(feature = "h-system")]
+    H,
+    /// A caret after the letter, as in old email and Usenet posts, e.g.
+    /// "j^au^do"
+    ///
+    /// "u~" is also read as "ŭ", and so is a caret before the letter, since
+    /// archives often mix the two.
+    PostfixCaret,
+    /// A caret before the letter, e.g. "^ja^udo"
+    ///
+    /// A caret after the letter is also read, as for `PostfixCaret`.
+    PrefixCaret,
+    /// An apostrophe after the letter, e.g. "j'au'do"
+    ///
+    /// An apostrophe at the end of a word is read as elision, as in "pac'",
+    /// rather than as a hat. Only "au'" and "eu'" are read as "ŭ" there.
+    Apostrophe,
+    /// Plain ASCII letters with the hats dropped, e.g. "jaudo"
+    ///
+    /// This is meant as an output, for slugs, file names and search keys.
+    /// The hats can't be recovered, so reading it changes nothing.
+    Reduced,
+    /// HTML character references, e.g. "&#309;a&#365;do"
+    ///
+    /// Decimal, hexadecimal and named references such as "&jcirc;" are read.
+    /// Other references, like "&amp;", are left as they are.
+    Html,
+    /// LaTeX accent commands, e.g. `\^{\j}a\u{u}do`
+    ///
+    /// The braces may be left out when reading, as in `\^c`.
+    Latex,
+    /// Unicode escapes as in JSON and JavaScript, e.g. `\u0135a\u016ddo`
+    ///
+    /// Rust's braced escapes, as in `\u{135}`, are read too, and can be
+    /// written with
+    /// [`Pipeline::braced_escapes`](struct.Pipeline.html#method.braced_escapes).
+    UnicodeEscape,
+    /// The Cyrillic script, e.g. "жаўдо"
+    ///
+    /// Every letter is written in Cyrillic, not only the hatted ones, so any
+    /// Latin text such as names and links is converted as well.
+    Cyrillic,
+}
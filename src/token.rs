@@ -0,0 +1,230 @@
+//! Splitting text into words and the text between them
+
+use std::ops::Range;
+
+use crate::pipeline::is_zero_width;
+
+/// Whether `c` can be part of a word
+///
+/// Combining diacritics count as word characters so that decomposed letters
+/// like "c" followed by U+0302 stay in one piece, as do soft hyphens and
+/// zero-width characters, which converters may be asked to match across. No
+/// pattern used by the converters contains anything else, so converting text
+/// word by word gives the same result as converting it whole.
+///
+/// Apostrophes are not word characters. In Esperanto they mark elision, as in
+/// "l' espero" or "dank' al", so they always end a word.
+pub(crate) fn is_word_char(c: char) -> bool {
+    c.is_alphabetic() || ('\u{300}'..='\u{36f}').contains(&c) || c == '\u{AD}' || is_zero_width(c)
+}
+
+/// Iterate over alternating runs of word and non-word characters
+///
+/// Each item is the run and whether it is a word.
+pub(crate) fn runs(s: &str) -> Runs<'_> {
+    Runs { rest: s }
+}
+
+pub(crate) struct Runs<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Iterator for Runs<'a> {
+    type Item = (&'a str, bool);
+
+    fn next(&mut self) -> Option<(&'a str, bool)> {
+        let first = self.rest.chars().next()?;
+        let word = is_word_char(first);
+        let end = self
+            .rest
+            .char_indices()
+            .find(|(_, c)| is_word_char(*c) != word)
+            .map_or(self.rest.len(), |(i, _)| i);
+        let (run, rest) = self.rest.split_at(end);
+        self.rest = rest;
+        Some((run, word))
+    }
+}
+
+/// What a [`Token`](struct.Token.html) is made of
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TokenKind {
+    /// Letters, as the converters see a word
+    ///
+    /// This includes combining diacritics, soft hyphens and zero-width
+    /// characters, but not apostrophes or hyphens.
+    Word,
+    /// Whitespace
+    Space,
+    /// Anything else, such as punctuation, digits and symbols
+    Punct,
+}
+
+impl TokenKind {
+    /// The kind of token that `c` belongs to
+    fn of(c: char) -> TokenKind {
+        if is_word_char(c) {
+            TokenKind::Word
+        } else if c.is_whitespace() {
+            TokenKind::Space
+        } else {
+            TokenKind::Punct
+        }
+    }
+}
+
+/// A run of characters of the same kind, as found by [`tokens`](fn.tokens.html)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Token<'a> {
+    /// What the token is made of
+    pub kind: TokenKind,
+    /// The text of the token
+    pub text: &'a str,
+    /// The byte offset where the token starts in the text
+    pub start: usize,
+}
+
+/// Split text into words, whitespace and punctuation, the same way the
+/// converters split it into words
+///
+/// Every character belongs to exactly one token, so the tokens join back
+/// into the original text.
+///
+/// ```
+/// use esperanto_text::{tokens, TokenKind};
+///
+/// let found: Vec<(TokenKind, &str)> = tokens("Dank' al vi!").map(|t| (t.kind, t.text)).collect();
+/// assert_eq!(
+///     found,
+///     [
+///         (TokenKind::Word, "Dank"),
+///         (TokenKind::Punct, "'"),
+///         (TokenKind::Space, " "),
+///         (TokenKind::Word, "al"),
+///         (TokenKind::Space, " "),
+///         (TokenKind::Word, "vi"),
+///         (TokenKind::Punct, "!"),
+///     ]
+/// );
+/// ```
+pub fn tokens(s: &str) -> Tokens<'_> {
+    Tokens { s, pos: 0 }
+}
+
+/// Iterator over the tokens of a text, returned by [`tokens`](fn.tokens.html)
+#[derive(Clone, Debug)]
+pub struct Tokens<'a> {
+    s: &'a str,
+    pos: usize,
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        let rest = &self.s[self.pos..];
+        let kind = TokenKind::of(rest.chars().next()?);
+        let len = rest
+            .char_indices()
+            .find(|(_, c)| TokenKind::of(*c) != kind)
+            .map_or(rest.len(), |(i, _)| i);
+        let token = Token {
+            kind,
+            text: &rest[..len],
+            start: self.pos,
+        };
+        self.pos += len;
+        Some(token)
+    }
+}
+
+/// The byte range of the word containing byte `pos` of `s`
+pub(crate) fn word_at(s: &str, pos: usize) -> Range<usize> {
+    let start = s[..pos]
+        .char_indices()
+        .rev()
+        .find(|(_, c)| !is_word_char(*c))
+        .map_or(0, |(i, c)| i + c.len_utf8());
+    let end = s[pos..]
+        .find(|c| !is_word_char(c))
+        .map_or(s.len(), |i| pos + i);
+    start..end
+}
+
+/// Whether the word containing byte `pos` of `s` looks like a proper noun
+///
+/// That is a capitalised word, such as "Charles", that doesn't begin a
+/// sentence. All-caps words and words after ".", "!", "?" or "…" (or at the
+/// very start of `s`) are not counted, since their capital says nothing.
+pub(crate) fn in_proper_noun(s: &str, pos: usize) -> bool {
+    let Range { start, end } = word_at(s, pos);
+    let mut letters = s[start..end].chars();
+    let capitalised =
+        letters.next().is_some_and(char::is_uppercase) && letters.any(char::is_lowercase);
+    let before = s[..start].chars().rev().find(|c| !is_lead_in(*c));
+    let sentence_start = before.is_none_or(|c| ".!?…".contains(c));
+    capitalised && !sentence_start
+}
+
+/// Where the end of `s` that [`in_proper_noun`] looks back at begins
+///
+/// Keeping `s[lookback_start(s)..]` in front of the text that follows is
+/// enough to tell whether a word there begins a sentence.
+pub(crate) fn lookback_start(s: &str) -> usize {
+    s.char_indices()
+        .rev()
+        .find(|(_, c)| !is_lead_in(*c))
+        .map_or(s.len(), |(i, _)| i)
+}
+
+/// Whether `c` can come between the end of a sentence and the next word,
+/// like a space or an opening quotation mark
+fn is_lead_in(c: char) -> bool {
+    c.is_whitespace() || "\"'“‘«([".contains(c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_in_proper_noun() {
+        let s = "Chu Charles venis? \"Sheffield\" estas en Anglio, SHI diris.";
+        let at = |word: &str| in_proper_noun(s, s.find(word).unwrap() + 1);
+        assert!(!at("Chu"));
+        assert!(at("Charles"));
+        assert!(!at("Sheffield"));
+        assert!(at("Anglio"));
+        assert!(!at("SHI"));
+        assert!(!at("diris"));
+    }
+
+    #[test]
+    fn test_tokens_match_runs() {
+        let s = "Ĉu vi, s\u{302}i?  \u{AD}42 cxu-ne";
+        let words: Vec<&str> = tokens(s)
+            .filter(|t| t.kind == TokenKind::Word)
+            .map(|t| t.text)
+            .collect();
+        let from_runs: Vec<&str> = runs(s).filter(|(_, w)| *w).map(|(r, _)| r).collect();
+        assert_eq!(words, from_runs);
+        assert_eq!(tokens(s).map(|t| t.text).collect::<String>(), s);
+        assert!(tokens(s).all(|t| &s[t.start..t.start + t.text.len()] == t.text));
+    }
+
+    #[test]
+    fn test_runs() {
+        let found: Vec<(&str, bool)> = runs("Ĉu vi, s\u{302}i?").collect();
+        assert_eq!(
+            found,
+            vec![
+                ("Ĉu", true),
+                (" ", false),
+                ("vi", true),
+                (", ", false),
+                ("s\u{302}i", true),
+                ("?", false),
+            ]
+        );
+    }
+}
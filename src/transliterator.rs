@@ -0,0 +1,1804 @@
+//! A reusable converter between two systems
+
+use std::borrow::Cow;
+use std::convert::Infallible;
+use std::io::{self, Read, Write};
+use std::ops::Range;
+use std::sync::OnceLock;
+
+#[cfg(feature = "allocator-api2")]
+use allocator_api2::{alloc::Allocator, vec::Vec as AllocVec};
+
+use crate::cancel::CancelToken;
+use crate::cyrillic::{has_latin, to_cyrillic};
+#[cfg(feature = "h-system")]
+use crate::decoder::{base_letter, PLAIN_H_ROOTS};
+use crate::decoder::{digraph_markers, Decoder, Rule};
+#[cfg(feature = "big-dict")]
+use crate::dictionary::Dictionary;
+use crate::error::Error;
+use crate::html;
+use crate::language::esperanto_likelihood;
+use crate::letter::Letter;
+use crate::mime::encoded_words;
+use crate::modern::modern_k_at;
+#[cfg(feature = "h-system")]
+use crate::morphology::splits_au;
+use crate::percent;
+use crate::pipeline::{CasingPolicy, Invisible, Options, PercentEscapes, Pipeline, UnicodeForm};
+use crate::sentence::split_sentences;
+use crate::skip::{link_spans, overlaps, social_spans};
+use crate::stats::RuleStats;
+#[cfg(feature = "h-system")]
+use crate::token::word_at;
+use crate::token::{in_proper_noun, is_word_char, lookback_start};
+use crate::unicode_escape;
+use crate::System;
+
+/// Converts text from one system to another, reusing its compiled patterns
+///
+/// Building the patterns is far more expensive than using them, so when
+/// converting many strings it is best to create one `Transliterator` and keep
+/// it around.
+///
+/// A `Transliterator` is `Send + Sync` and never mutates itself while
+/// converting, so one instance can serve any number of threads at once, for
+/// example behind an `Arc` or from a
+/// [`TransliteratorPool`](struct.TransliteratorPool.html).
+///
+#[cfg_attr(feature = "x-system", doc = "```")]
+#[cfg_attr(not(feature = "x-system"), doc = "```ignore")]
+/// use esperanto_text::{System, Transliterator};
+///
+/// let t = Transliterator::new(System::X, System::Utf8);
+/// assert_eq!(t.convert("cxu"), "ĉu");
+/// assert_eq!(t.convert("sxangxo"), "ŝanĝo");
+/// ```
+pub struct Transliterator {
+    options: Options,
+    /// The compiled patterns, or `None` if conversion changes nothing
+    ///
+    /// This is filled in by the constructors, and again on next use after
+    /// the exceptions are changed.
+    decoder: OnceLock<Option<Decoder>>,
+}
+
+impl Transliterator {
+    /// Compile a converter from the `from` system to the `to` system
+    ///
+    /// Use a [`Pipeline`](struct.Pipeline.html) to change other settings.
+    pub fn new(from: System, to: System) -> Transliterator {
+        Pipeline::new().from(from).to(to).build()
+    }
+
+    /// Create a converter that also leaves alone the exception fragments
+    /// listed in `reader`
+    ///
+    /// See [`Pipeline::exceptions_from`](struct.Pipeline.html#method.exceptions_from)
+    /// for the format. To read a file, pass it opened with `File::open`.
+    pub fn with_exceptions_from<R: Read>(
+        from: System,
+        to: System,
+        reader: R,
+    ) -> io::Result<Transliterator> {
+        Ok(Pipeline::new()
+            .from(from)
+            .to(to)
+            .exceptions_from(reader)?
+            .build())
+    }
+
+    pub(crate) fn with_options(options: Options) -> Transliterator {
+        let t = Transliterator {
+            options,
+            decoder: OnceLock::new(),
+        };
+        t.decoder();
+        t
+    }
+
+    /// The compiled patterns, building them if the exceptions have changed
+    fn decoder(&self) -> Option<&Decoder> {
+        self.decoder
+            .get_or_init(|| {
+                let options = &self.options;
+                let unchanged = options.from == options.to
+                    && !options.normalize_unicode
+                    && !options.normalize_confusables
+                    && options.output_form == UnicodeForm::Nfc
+                    && !options.removes_invisibles()
+                    && !options.modern_k
+                    && options.mapping.is_empty();
+                if unchanged {
+                    None
+                } else {
+                    Some(Decoder::new(options))
+                }
+            })
+            .as_ref()
+    }
+
+    /// Leave `fragment` alone wherever it is found in x-system or h-system
+    /// input, as if it were in the built-in exception lists
+    ///
+    /// The fragment may be anchored with '^' and '$' as described in
+    /// [`Pipeline::exceptions_from`](struct.Pipeline.html#method.exceptions_from).
+    /// The patterns are rebuilt the next time the converter is used.
+    ///
+    #[cfg_attr(feature = "h-system", doc = "```")]
+    #[cfg_attr(not(feature = "h-system"), doc = "```ignore")]
+    /// use esperanto_text::{System, Transliterator};
+    ///
+    /// let mut t = Transliterator::new(System::H, System::Utf8);
+    /// t.add_exception("kauk");
+    /// t.remove_exception("ashund");
+    /// assert_eq!(t.convert("kauko chashundo"), "kauko ĉaŝundo");
+    /// ```
+    pub fn add_exception(&mut self, fragment: &str) {
+        self.options.add_exception(fragment);
+        self.decoder = OnceLock::new();
+    }
+
+    /// Stop leaving `fragment` alone, whether it is a built-in exception or
+    /// one that was added
+    ///
+    /// Any '^' and '$' anchors are ignored. The patterns are rebuilt the next
+    /// time the converter is used.
+    pub fn remove_exception(&mut self, fragment: &str) {
+        self.options.remove_exception(fragment);
+        self.decoder = OnceLock::new();
+    }
+
+    /// The system this converter reads
+    pub fn from(&self) -> System {
+        self.options.from
+    }
+
+    /// The system this converter writes
+    pub fn to(&self) -> System {
+        self.options.to
+    }
+
+    /// Convert a single string
+    pub fn convert(&self, s: &str) -> String {
+        self.convert_with_capacity_hint(s, self.estimate_capacity(s))
+    }
+
+    /// Convert a single string into a buffer of at least `capacity` bytes
+    ///
+    /// The converter already reserves a reasonable estimate of the output
+    /// size. Callers who know more about their data, for example that it is
+    /// dense with h-system "au" that will each grow by a byte, can avoid
+    /// reallocations by asking for more.
+    pub fn convert_with_capacity_hint(&self, s: &str, capacity: usize) -> String {
+        match self.convert_chunked(s, capacity, None, |_| Ok::<(), Infallible>(())) {
+            Ok(result) => result,
+            Err(never) => match never {},
+        }
+    }
+
+    /// Guess how many bytes of output converting `s` will produce
+    ///
+    /// A hatted letter takes two bytes in UTF-8, the same as an x-system or
+    /// h-system digraph, so most directions preserve the length exactly or
+    /// shrink slightly. Reading the h-system is the exception: every "au"
+    /// gains a byte, whether it becomes "aŭ" or "aux".
+    fn estimate_capacity(&self, s: &str) -> usize {
+        match self.options.from {
+            #[cfg(feature = "h-system")]
+            System::H if self.options.to != System::H => s.len() + s.len() / 32,
+            _ => s.len(),
+        }
+    }
+
+    /// Convert a single string, borrowing it if nothing would change
+    ///
+    /// Only the input up to the first change is scanned before deciding, and
+    /// text with no possible matches at all is rejected by a quick pre-check,
+    /// so the common no-op case neither allocates nor copies.
+    ///
+    #[cfg_attr(feature = "x-system", doc = "```")]
+    #[cfg_attr(not(feature = "x-system"), doc = "```ignore")]
+    /// use esperanto_text::{System, Transliterator};
+    /// use std::borrow::Cow;
+    ///
+    /// let t = Transliterator::new(System::X, System::Utf8);
+    /// assert!(matches!(t.convert_cow("plain text"), Cow::Borrowed(_)));
+    /// assert_eq!(t.convert_cow("cxu"), "ĉu");
+    /// ```
+    pub fn convert_cow<'a>(&self, s: &'a str) -> Cow<'a, str> {
+        if self.changes(s) {
+            Cow::Owned(self.convert(s))
+        } else {
+            Cow::Borrowed(s)
+        }
+    }
+
+    /// Convert bytes that should be UTF-8, failing if they aren't
+    ///
+    #[cfg_attr(feature = "x-system", doc = "```")]
+    #[cfg_attr(not(feature = "x-system"), doc = "```ignore")]
+    /// use esperanto_text::{Error, System, Transliterator};
+    ///
+    /// let t = Transliterator::new(System::X, System::Utf8);
+    /// assert_eq!(t.try_convert(b"cxu").unwrap(), "ĉu");
+    /// assert_eq!(
+    ///     t.try_convert(b"cx\xffu"),
+    ///     Err(Error::InvalidBytes { position: 2 })
+    /// );
+    /// ```
+    pub fn try_convert(&self, bytes: &[u8]) -> Result<String, Error> {
+        let s = std::str::from_utf8(bytes).map_err(|e| Error::InvalidBytes {
+            position: e.valid_up_to(),
+        })?;
+        Ok(self.convert(s))
+    }
+
+    /// Convert a string into `buf`, returning the length of the output
+    ///
+    /// Nothing is written if the output doesn't fit, and the error gives the
+    /// length needed.
+    ///
+    #[cfg_attr(feature = "x-system", doc = "```")]
+    #[cfg_attr(not(feature = "x-system"), doc = "```ignore")]
+    /// use esperanto_text::{Error, System, Transliterator};
+    ///
+    /// let t = Transliterator::new(System::Utf8, System::X);
+    /// let mut buf = [0; 8];
+    /// let len = t.try_convert_into("ĉu", &mut buf)?;
+    /// assert_eq!(&buf[..len], b"cxu");
+    /// assert_eq!(
+    ///     t.try_convert_into("ĉiuj ŝipoj", &mut buf),
+    ///     Err(Error::BufferOverflow { needed: 12 })
+    /// );
+    /// # Ok::<(), Error>(())
+    /// ```
+    pub fn try_convert_into(&self, s: &str, buf: &mut [u8]) -> Result<usize, Error> {
+        let output = self.convert_cow(s);
+        let dst = buf
+            .get_mut(..output.len())
+            .ok_or(Error::BufferOverflow {
+                needed: output.len(),
+            })?;
+        dst.copy_from_slice(output.as_bytes());
+        Ok(output.len())
+    }
+
+    /// Whether `s` contains anything that conversion might change, according
+    /// to a cheap pre-check that can give false positives
+    pub(crate) fn may_match(&self, s: &str) -> bool {
+        self.decoder().is_some_and(|d| d.may_match(s))
+    }
+
+    /// Whether converting `s` would produce anything different
+    fn changes(&self, s: &str) -> bool {
+        let decoder = match self.decoder() {
+            Some(d) if d.may_match(s) => d,
+            _ => return false,
+        };
+        if self.options.to == System::Cyrillic {
+            return has_latin(s);
+        }
+        // Matches may span invisible characters and unconverted text may need
+        // escaping, so don't try to be exact
+        if self.escape().is_some()
+            || self.options.esperanto_only.is_some()
+            || self.options.mime_words && s.contains("=?")
+            || self.options.percent_escapes == PercentEscapes::Convert && s.contains('%')
+            || self.options.has_invisibles() && s.chars().any(|c| self.options.is_invisible(c))
+        {
+            return true;
+        }
+        decoder
+            .find_iter(s)
+            .any(|m| match decoder.rules[m.pattern()] {
+                rule if !self.converts_letter(rule) => false,
+                Rule::Keep => false,
+                Rule::Letter(Letter::H) if self.options.modern_k => true,
+                Rule::Letter(letter) if self.options.mapped(letter).is_some() => true,
+                // A letter that is already UTF-8 in the output form stays as it is
+                Rule::Letter(_) if self.options.to == System::Utf8 => {
+                    !is_in_form(&s[m.start()..m.end()], self.options.output_form)
+                }
+                Rule::Letter(_)
+                | Rule::Breve
+                | Rule::Escaped
+                | Rule::EscapedHh
+                | Rule::PlainH(_)
+                | Rule::Plain(_) => true,
+            })
+    }
+
+    /// Convert a single string, giving up if `token` is cancelled
+    ///
+    /// The token is checked regularly while working through large inputs. If
+    /// it has been cancelled the partial output is discarded.
+    pub fn convert_cancellable(&self, s: &str, token: &CancelToken) -> Result<String, Error> {
+        self.convert_cancellable_with_progress(s, token, |_, _| ())
+    }
+
+    /// Convert a single string, adding up in `stats` which rules decided the
+    /// output
+    ///
+    /// See [`RuleStats`](struct.RuleStats.html) for an example.
+    pub fn convert_with_stats(&self, s: &str, stats: &mut RuleStats) -> String {
+        self.convert_counting(s, Some(stats))
+    }
+
+    /// Convert a single string, adding to `stats` if there are any
+    fn convert_counting(&self, s: &str, stats: Option<&mut RuleStats>) -> String {
+        let capacity = self.estimate_capacity(s);
+        match self.convert_chunked(s, capacity, stats, |_| Ok::<(), Infallible>(())) {
+            Ok(result) => result,
+            Err(never) => match never {},
+        }
+    }
+
+    /// Add the rule that decided one match to `stats`
+    ///
+    /// `matched` is the rule the decoder found and `rule` the one applied.
+    fn record(&self, stats: Option<&mut RuleStats>, matched: Rule, rule: Rule, found: &str) {
+        let stats = match stats {
+            Some(stats) => stats,
+            None => return,
+        };
+        match (matched, rule) {
+            _ if !self.converts_letter(matched) => stats.skipped += 1,
+            (Rule::Keep, _) => *stats.exceptions.entry(found.to_lowercase()).or_default() += 1,
+            (_, Rule::Keep) => stats.compounds += 1,
+            (Rule::Letter(_), _) if found.is_ascii() => stats.digraphs += 1,
+            (Rule::PlainH(_), _) => stats.digraphs += 1,
+            (Rule::Breve, _) => stats.breves += 1,
+            _ => (),
+        }
+    }
+
+    /// Convert a single string, reporting how far through the input it is
+    ///
+    /// `progress` is called with the number of bytes processed so far and the
+    /// total length of the input, first with zero and last with the total.
+    ///
+    #[cfg_attr(feature = "x-system", doc = "```")]
+    #[cfg_attr(not(feature = "x-system"), doc = "```ignore")]
+    /// use esperanto_text::{System, Transliterator};
+    ///
+    /// let t = Transliterator::new(System::X, System::Utf8);
+    /// let mut reports = Vec::new();
+    /// t.convert_with_progress("cxu", |done, total| reports.push((done, total)));
+    /// assert_eq!(reports, vec![(0, 3), (3, 3)]);
+    /// ```
+    pub fn convert_with_progress<F>(&self, s: &str, mut progress: F) -> String
+    where
+        F: FnMut(usize, usize),
+    {
+        let total = s.len();
+        let result = self.convert_chunked(s, self.estimate_capacity(s), None, |done| {
+            progress(done, total);
+            Ok::<(), Infallible>(())
+        });
+        match result {
+            Ok(result) => result,
+            Err(never) => match never {},
+        }
+    }
+
+    /// Convert a single string with both progress reports and cancellation
+    pub fn convert_cancellable_with_progress<F>(
+        &self,
+        s: &str,
+        token: &CancelToken,
+        mut progress: F,
+    ) -> Result<String, Error>
+    where
+        F: FnMut(usize, usize),
+    {
+        let total = s.len();
+        self.convert_chunked(s, self.estimate_capacity(s), None, |done| {
+            if token.is_cancelled() {
+                return Err(Error::Cancelled);
+            }
+            progress(done, total);
+            Ok(())
+        })
+    }
+
+    /// Convert `s` in chunks, calling `checkpoint` with the number of bytes
+    /// done before each chunk and once more at the end
+    fn convert_chunked<E, F>(
+        &self,
+        s: &str,
+        capacity: usize,
+        mut stats: Option<&mut RuleStats>,
+        mut checkpoint: F,
+    ) -> Result<String, E>
+    where
+        F: FnMut(usize) -> Result<(), E>,
+    {
+        let decoder = match self.decoder() {
+            Some(d) => d,
+            None => {
+                checkpoint(0)?;
+                checkpoint(s.len())?;
+                return Ok(s.to_owned());
+            }
+        };
+        let mut result = String::with_capacity(capacity);
+        let mut start = 0;
+        while start < s.len() {
+            checkpoint(start)?;
+            let end = self.end_of_chunk(s, start);
+            self.convert_range(decoder, s, start, end, &mut result, stats.as_deref_mut());
+            start = end;
+        }
+        checkpoint(s.len())?;
+        Ok(result)
+    }
+
+    /// Where to end a chunk of `s` that begins at `start`
+    ///
+    /// When sentences are scored on their own, the chunk ends before its
+    /// last sentence so that it is scored whole with the next chunk. Only a
+    /// sentence longer than a whole chunk is scored in parts.
+    fn end_of_chunk(&self, s: &str, start: usize) -> usize {
+        let end = chunk_end(s, start);
+        if end == s.len() {
+            return end;
+        }
+        start + self.whole_sentences(&s[start..end]).unwrap_or(end - start)
+    }
+
+    /// The length of `s` up to the start of its last sentence, which may
+    /// carry on past the end of `s`, if sentences are scored on their own
+    ///
+    /// Otherwise this is the length of `s`, or `None` if `s` is a single
+    /// sentence that may not have ended.
+    fn whole_sentences(&self, s: &str) -> Option<usize> {
+        if self.options.esperanto_only.is_none() {
+            return Some(s.len());
+        }
+        split_sentences(s)
+            .last()
+            .map(|last| last.start)
+            .filter(|&start| start > 0)
+    }
+
+    /// Convert `s[start..end]`, appending to `dst` and adding to `stats` if
+    /// there are any
+    ///
+    /// The rest of `s` is still consulted for the casing of digraphs.
+    fn convert_range(
+        &self,
+        decoder: &Decoder,
+        s: &str,
+        start: usize,
+        end: usize,
+        dst: &mut String,
+        mut stats: Option<&mut RuleStats>,
+    ) {
+        let threshold = match self.options.esperanto_only {
+            Some(threshold) => threshold,
+            None => return self.convert_lines(decoder, s, start, end, dst, stats),
+        };
+        // Sentences that don't look like Esperanto are copied unchanged, as
+        // is the space between sentences
+        let mut pos = start;
+        for range in split_sentences(&s[start..end]) {
+            let (sentence_start, sentence_end) = (start + range.start, start + range.end);
+            dst.push_str(&s[pos..sentence_start]);
+            if esperanto_likelihood(&s[sentence_start..sentence_end]) >= threshold {
+                let stats = stats.as_deref_mut();
+                self.convert_lines(decoder, s, sentence_start, sentence_end, dst, stats);
+            } else {
+                dst.push_str(&s[sentence_start..sentence_end]);
+            }
+            pos = sentence_end;
+        }
+        dst.push_str(&s[pos..end]);
+    }
+
+    /// Convert `s[start..end]` line by line, skipping lines with no matches
+    fn convert_lines(
+        &self,
+        decoder: &Decoder,
+        s: &str,
+        start: usize,
+        end: usize,
+        dst: &mut String,
+        mut stats: Option<&mut RuleStats>,
+    ) {
+        let mut line_start = start;
+        for line in s[start..end].split_inclusive('\n') {
+            let line_end = line_start + line.len();
+            if decoder.may_match(line) {
+                self.convert_line(decoder, s, line_start, line_end, dst, stats.as_deref_mut());
+            } else {
+                dst.push_str(line);
+            }
+            line_start = line_end;
+        }
+    }
+
+    /// Convert `s[start..end]`, a range with at least one possible match
+    fn convert_line(
+        &self,
+        decoder: &Decoder,
+        s: &str,
+        start: usize,
+        end: usize,
+        dst: &mut String,
+        mut stats: Option<&mut RuleStats>,
+    ) {
+        if !self.options.mime_words || !s[start..end].contains("=?") {
+            return self.convert_escaped(decoder, s, start, end, dst, stats);
+        }
+        // Each encoded word is decoded, converted on its own and encoded again
+        let mut pos = start;
+        for (range, word) in encoded_words(&s[start..end]) {
+            let (word_start, word_end) = (start + range.start, start + range.end);
+            self.convert_escaped(decoder, s, pos, word_start, dst, stats.as_deref_mut());
+            match word.decode() {
+                Some(text) => {
+                    let converted = self.convert_counting(&text, stats.as_deref_mut());
+                    dst.push_str(&word.encode(&converted))
+                }
+                None => dst.push_str(&s[word_start..word_end]),
+            }
+            pos = word_end;
+        }
+        self.convert_escaped(decoder, s, pos, end, dst, stats);
+    }
+
+    /// Convert `s[start..end]`, which contains no encoded words, treating
+    /// percent escapes as the options say
+    fn convert_escaped(
+        &self,
+        decoder: &Decoder,
+        s: &str,
+        start: usize,
+        end: usize,
+        dst: &mut String,
+        mut stats: Option<&mut RuleStats>,
+    ) {
+        let chunk = &s[start..end];
+        let spans = match self.options.percent_escapes {
+            _ if !chunk.contains('%') => {
+                return self.convert_text(decoder, s, start, end, dst, stats)
+            }
+            PercentEscapes::Literal => {
+                return self.convert_text(decoder, s, start, end, dst, stats)
+            }
+            PercentEscapes::Keep => percent::escapes(chunk),
+            PercentEscapes::Convert => percent::segments(chunk),
+        };
+        let mut pos = start;
+        for range in spans {
+            let (span_start, span_end) = (start + range.start, start + range.end);
+            self.convert_text(decoder, s, pos, span_start, dst, stats.as_deref_mut());
+            let span = &s[span_start..span_end];
+            match percent::decode(span) {
+                Some(text) if self.options.percent_escapes == PercentEscapes::Convert => {
+                    let converted = self.convert_counting(&text, stats.as_deref_mut());
+                    dst.push_str(&percent::encode(&converted))
+                }
+                _ => dst.push_str(span),
+            }
+            pos = span_end;
+        }
+        self.convert_text(decoder, s, pos, end, dst, stats);
+    }
+
+    /// Convert `s[start..end]`, which contains no encoded text to look inside
+    fn convert_text(
+        &self,
+        decoder: &Decoder,
+        s: &str,
+        start: usize,
+        end: usize,
+        dst: &mut String,
+        mut stats: Option<&mut RuleStats>,
+    ) {
+        let chunk = &s[start..end];
+        if self.options.has_invisibles() && chunk.chars().any(|c| self.options.is_invisible(c)) {
+            return self.convert_line_invisible(decoder, chunk, dst, stats);
+        }
+        let skipped = self.skipped_spans(chunk);
+        let mut literal = false;
+        let mut last = 0;
+        for m in decoder.find_iter(chunk) {
+            self.push_literal(&chunk[last..m.start()], &mut literal, dst);
+            let found = &chunk[m.start()..m.end()];
+            if overlaps(&skipped, m.start(), m.end())
+                || self.is_protected_name(s, start + m.start(), found)
+            {
+                if let Some(stats) = stats.as_deref_mut() {
+                    stats.skipped += 1;
+                }
+                dst.push_str(found);
+                literal = true;
+            } else {
+                let tail = &s[start + m.end()..];
+                let matched = decoder.rules[m.pattern()];
+                let rule = self.rule_at(matched, s, start + m.start(), found);
+                self.record(stats.as_deref_mut(), matched, rule, found);
+                self.emit(rule, found, tail, &mut literal, dst);
+            }
+            last = m.end();
+        }
+        self.push_literal(&chunk[last..], &mut literal, dst);
+    }
+
+    /// Convert a line that contains invisible characters to be matched across
+    fn convert_line_invisible(
+        &self,
+        decoder: &Decoder,
+        line: &str,
+        dst: &mut String,
+        mut stats: Option<&mut RuleStats>,
+    ) {
+        // Match against the line with the invisible characters taken out,
+        // remembering where each remaining byte came from
+        let mut visible = String::with_capacity(line.len());
+        let mut offsets = Vec::with_capacity(line.len());
+        for (i, c) in line.char_indices() {
+            if !self.options.is_invisible(c) {
+                visible.push(c);
+                offsets.extend(i..i + c.len_utf8());
+            }
+        }
+        let skipped = self.skipped_spans(&visible);
+        let mut literal = false;
+        let mut last = 0;
+        for m in decoder.find_iter(&visible) {
+            let start = offsets[m.start()];
+            let end = offsets[m.end() - 1] + 1;
+            self.push_invisible(&line[last..start], &mut literal, dst);
+            let found = &visible[m.start()..m.end()];
+            let matched = decoder.rules[m.pattern()];
+            match self.rule_at(matched, &visible, m.start(), found) {
+                Rule::Keep => {
+                    self.record(stats.as_deref_mut(), matched, Rule::Keep, found);
+                    self.push_invisible(&line[start..end], &mut literal, dst)
+                }
+                _ if overlaps(&skipped, m.start(), m.end())
+                    || self.is_protected_name(&visible, m.start(), found) =>
+                {
+                    if let Some(stats) = stats.as_deref_mut() {
+                        stats.skipped += 1;
+                    }
+                    self.push_invisible(&line[start..end], &mut literal, dst)
+                }
+                rule => {
+                    self.record(stats.as_deref_mut(), matched, rule, found);
+                    self.emit(rule, found, &visible[m.end()..], &mut literal, dst);
+                    // Anything invisible that was inside the match goes after it
+                    for c in line[start..end].chars() {
+                        if self.options.invisible(c) == Invisible::Transparent {
+                            dst.push(c);
+                        }
+                    }
+                }
+            }
+            last = end;
+        }
+        self.push_invisible(&line[last..], &mut literal, dst);
+    }
+
+    /// The rule to apply to `found` at byte `pos` of `s`
+    ///
+    /// An h-system "au" that joins two parts of a compound word, as in
+    /// "praulo", is left as it is, as is any h-system digraph or "au" in a
+    /// word that the lexicon recognises.
+    #[cfg_attr(not(feature = "h-system"), allow(unused_variables))]
+    fn rule_at(&self, rule: Rule, s: &str, pos: usize, found: &str) -> Rule {
+        match rule {
+            _ if !self.converts_letter(rule) => Rule::Keep,
+            Rule::Letter(_)
+                if self.options.from == System::Apostrophe
+                    && found.ends_with('\'')
+                    && !s[pos + found.len()..]
+                        .chars()
+                        .next()
+                        .is_some_and(is_word_char) =>
+            {
+                Rule::Keep
+            }
+            #[cfg(feature = "h-system")]
+            _ if self.options.strict => rule,
+            #[cfg(feature = "h-system")]
+            Rule::Breve if found.eq_ignore_ascii_case("au") && splits_au(s, pos) => Rule::Keep,
+            #[cfg(feature = "h-system")]
+            Rule::Letter(_) | Rule::Breve
+                if self.options.from == System::H
+                    && found.is_ascii()
+                    && found.len() == 2
+                    && self.in_lexicon(s, pos, rule) =>
+            {
+                Rule::Keep
+            }
+            _ => rule,
+        }
+    }
+
+    /// Whether the letter that `rule` produces is one the options convert
+    fn converts_letter(&self, rule: Rule) -> bool {
+        let letters = self.options.letters;
+        match rule {
+            Rule::Letter(letter) => letters.has(letter),
+            Rule::Breve => letters.has(Letter::U),
+            Rule::EscapedHh | Rule::PlainH(_) => letters.has(Letter::H),
+            _ => true,
+        }
+    }
+
+    /// Whether the lexicon, if one is set, recognises the word at byte `pos`
+    /// as the match for `rule` is written
+    ///
+    /// With the `big-dict` feature and no lexicon set, a digraph that the
+    /// exception list didn't leave alone is kept if the root list can only
+    /// build the word as written.
+    #[cfg(feature = "h-system")]
+    #[cfg_attr(not(feature = "big-dict"), allow(unused_variables))]
+    fn in_lexicon(&self, s: &str, pos: usize, rule: Rule) -> bool {
+        let word = || s[word_at(s, pos)].to_lowercase();
+        match &self.options.lexicon {
+            Some(lexicon) => lexicon.0.contains_root(&word()),
+            #[cfg(feature = "big-dict")]
+            None => matches!(rule, Rule::Letter(_)) && Dictionary::is_literal(&word()) == Some(true),
+            #[cfg(not(feature = "big-dict"))]
+            None => false,
+        }
+    }
+
+    /// Whether converting each word by itself gives the same result as
+    /// converting the whole text
+    ///
+    /// Links, names, sentences, escapes and encoded spans all depend on the
+    /// text around a word.
+    pub(crate) fn is_word_local(&self) -> bool {
+        let options = &self.options;
+        !options.skip_links
+            && !options.skip_social
+            && !options.protect_proper_nouns
+            && options.protected.is_empty()
+            && options.esperanto_only.is_none()
+            && options.escape.is_none()
+            && !options.mime_words
+            && options.percent_escapes == PercentEscapes::Literal
+            && !options.has_invisibles()
+    }
+
+    /// Byte ranges of `line` that the options say must not be converted
+    fn skipped_spans(&self, line: &str) -> Vec<Range<usize>> {
+        let mut spans = Vec::new();
+        if self.options.skip_links {
+            spans.extend(link_spans(line));
+        }
+        if self.options.skip_social {
+            spans.extend(social_spans(line));
+        }
+        spans
+    }
+
+    /// Whether `found` at byte `pos` of `s` is part of a name to leave alone
+    ///
+    /// Only ASCII digraphs are left alone, since a hatted letter that is
+    /// already in UTF-8 can't belong to a foreign name.
+    fn is_protected_name(&self, s: &str, pos: usize, found: &str) -> bool {
+        self.options.protect_proper_nouns && found.is_ascii() && in_proper_noun(s, pos)
+    }
+
+    /// Append `s`, leaving out any invisible characters that are to be removed
+    fn push_invisible(&self, s: &str, literal: &mut bool, dst: &mut String) {
+        if s.chars()
+            .any(|c| self.options.invisible(c) == Invisible::Remove)
+        {
+            let kept: String = s
+                .chars()
+                .filter(|c| self.options.invisible(*c) != Invisible::Remove)
+                .collect();
+            self.push_literal(&kept, literal, dst);
+        } else {
+            self.push_literal(s, literal, dst);
+        }
+    }
+
+    /// Append text that passes through unconverted, escaping it if required
+    ///
+    /// `literal` tracks whether the end of `dst` is unconverted text, since
+    /// only a letter from the input can be misread as the start of a digraph.
+    fn push_literal(&self, s: &str, literal: &mut bool, dst: &mut String) {
+        if self.options.to == System::Cyrillic {
+            dst.extend(s.chars().map(to_cyrillic));
+            *literal |= !s.is_empty();
+            return;
+        }
+        let escape = match self.escape() {
+            Some(e) => e,
+            None => {
+                dst.push_str(s);
+                *literal |= !s.is_empty();
+                return;
+            }
+        };
+        for c in s.chars() {
+            if c == escape || (*literal && self.misread(dst, c)) {
+                dst.push(escape);
+            }
+            dst.push(c);
+            *literal = true;
+        }
+    }
+
+    /// The escape character, if output is to be escaped
+    fn escape(&self) -> Option<char> {
+        self.options
+            .escape
+            .filter(|_| self.options.to != System::Utf8)
+    }
+
+    /// Whether appending `next` to `dst` would complete a digraph that the
+    /// output system would read as something else
+    fn misread(&self, dst: &str, next: char) -> bool {
+        let prev = match dst.chars().next_back() {
+            Some(c) => c.to_ascii_lowercase(),
+            None => return false,
+        };
+        let next = next.to_ascii_lowercase();
+        #[cfg(feature = "h-system")]
+        if self.options.to == System::H && prev == 'a' && next == 'u' {
+            return true;
+        }
+        match self.options.to {
+            System::PostfixCaret if prev == 'u' && next == '~' => return true,
+            System::PrefixCaret => return prev == '^' && "cghjsu".contains(next),
+            _ => (),
+        }
+        digraph_markers(self.options.to)
+            .is_some_and(|(bases, marker)| next == marker && bases.contains(prev))
+    }
+
+    /// Append the output for one match
+    fn emit(&self, rule: Rule, found: &str, tail: &str, literal: &mut bool, dst: &mut String) {
+        match rule {
+            Rule::Keep => self.push_literal(found, literal, dst),
+            Rule::Escaped => {
+                // The escape comes after the letter it keeps apart, or after
+                // another escape, and matched in either case
+                let escape = self.options.escape.unwrap_or_default();
+                let position = found
+                    .char_indices()
+                    .skip(1)
+                    .find(|(_, c)| *c == escape || c.eq_ignore_ascii_case(&escape));
+                match position {
+                    Some((i, c)) => {
+                        let unescaped = [&found[..i], &found[i + c.len_utf8()..]].concat();
+                        self.push_literal(&unescaped, literal, dst);
+                    }
+                    None => self.push_literal(found, literal, dst),
+                }
+            }
+            Rule::EscapedHh => {
+                let mut chars = found.chars();
+                if let Some(base) = chars.next() {
+                    self.push_literal(base.encode_utf8(&mut [0; 4]), literal, dst);
+                }
+                let upper = chars.nth(1).is_some_and(char::is_uppercase);
+                self.emit(
+                    Rule::Letter(Letter::H),
+                    if upper { "H" } else { "h" },
+                    tail,
+                    literal,
+                    dst,
+                );
+            }
+            Rule::Letter(letter) => {
+                let upper = html::numeric_is_upper(found)
+                    .or_else(|| unicode_escape::escape_is_upper(found))
+                    .unwrap_or_else(|| found.chars().any(char::is_uppercase));
+                // Only "ĥ" written as "hh" can follow a letter and make a digraph
+                if letter == Letter::H
+                    && *literal
+                    && self.escape().is_some()
+                    && self.misread(dst, letter.base(upper))
+                {
+                    dst.extend(self.escape());
+                }
+                encode(&self.options, letter, upper, tail, dst);
+                *literal = false;
+            }
+            #[cfg(feature = "h-system")]
+            Rule::PlainH(i) => {
+                // Convert the root's digraphs as usual, and its marked "h"
+                let marks = PLAIN_H_ROOTS[i].trim_start_matches('^').as_bytes();
+                let bytes = found.as_bytes();
+                let mut j = 0;
+                while j < bytes.len() {
+                    let digraph = marks.get(j + 1) == Some(&b'h')
+                        && bytes[j + 1].eq_ignore_ascii_case(&b'h')
+                        && b"cghjs".contains(&bytes[j].to_ascii_lowercase());
+                    if marks[j] == b'H' {
+                        let rest = [&found[j + 1..], tail].concat();
+                        self.emit(Rule::Letter(Letter::H), &found[j..=j], &rest, literal, dst);
+                        j += 1;
+                        continue;
+                    }
+                    let (rule, len) = if digraph {
+                        (Rule::Letter(base_letter(&found[j..])), 2)
+                    } else {
+                        (Rule::Keep, 1)
+                    };
+                    self.emit(rule, &found[j..j + len], tail, literal, dst);
+                    j += len;
+                }
+            }
+            #[cfg(not(feature = "h-system"))]
+            Rule::PlainH(_) => self.push_literal(found, literal, dst),
+            Rule::Plain(c) => {
+                let upper = found.chars().any(char::is_uppercase);
+                let c = if upper { c.to_ascii_uppercase() } else { c };
+                self.push_literal(c.encode_utf8(&mut [0; 4]), literal, dst);
+            }
+            Rule::Breve => {
+                let mut chars = found.chars();
+                if let Some(a) = chars.next() {
+                    self.push_literal(a.encode_utf8(&mut [0; 4]), literal, dst);
+                }
+                let upper = chars.next().is_some_and(char::is_uppercase);
+                encode(&self.options, Letter::U, upper, tail, dst);
+                *literal = false;
+            }
+        }
+    }
+
+    /// Convert a string, writing the output to `writer` a chunk at a time
+    ///
+    /// This avoids holding a second copy of a very large input in memory,
+    /// for example when it has been memory-mapped from a file.
+    pub fn convert_to_writer<W: Write>(&self, s: &str, mut writer: W) -> io::Result<()> {
+        self.write_range(s, 0, &mut writer)
+    }
+
+    /// Convert `s[start..]` a chunk at a time, writing the output to `writer`
+    ///
+    /// The text before `start` was converted already, but is still consulted
+    /// as the context of what follows.
+    fn write_range<W: Write>(&self, s: &str, mut start: usize, writer: &mut W) -> io::Result<()> {
+        let decoder = match self.decoder() {
+            Some(d) => d,
+            None => return writer.write_all(&s.as_bytes()[start..]),
+        };
+        let mut buf = String::new();
+        while start < s.len() {
+            let end = self.end_of_chunk(s, start);
+            buf.clear();
+            self.convert_range(decoder, s, start, end, &mut buf, None);
+            writer.write_all(buf.as_bytes())?;
+            start = end;
+        }
+        Ok(())
+    }
+
+    /// Convert a string into a buffer allocated from `alloc`
+    ///
+    /// The output is the UTF-8 bytes of the converted text, since there is no
+    /// `String` that takes an allocator. Only a small scratch buffer is
+    /// allocated elsewhere while converting.
+    ///
+    /// ```
+    /// use allocator_api2::alloc::Global;
+    /// use esperanto_text::{System, Transliterator};
+    ///
+    /// let t = Transliterator::new(System::X, System::Utf8);
+    /// let bytes = t.convert_in("sxangxo", Global);
+    /// assert_eq!(std::str::from_utf8(&bytes), Ok("ŝanĝo"));
+    /// ```
+    #[cfg(feature = "allocator-api2")]
+    pub fn convert_in<A: Allocator>(&self, s: &str, alloc: A) -> AllocVec<u8, A> {
+        let mut dst = AllocVec::with_capacity_in(self.estimate_capacity(s), alloc);
+        let decoder = match self.decoder() {
+            Some(d) => d,
+            None => {
+                dst.extend_from_slice(s.as_bytes());
+                return dst;
+            }
+        };
+        let mut buf = String::new();
+        let mut start = 0;
+        while start < s.len() {
+            let end = self.end_of_chunk(s, start);
+            buf.clear();
+            self.convert_range(decoder, s, start, end, &mut buf, None);
+            dst.extend_from_slice(buf.as_bytes());
+            start = end;
+        }
+        dst
+    }
+
+    /// Convert UTF-16 text, as used by Windows and JavaScript strings
+    ///
+    /// Characters outside the Basic Multilingual Plane are read from their
+    /// surrogate pairs, so they never split a digraph or affect its casing
+    /// any differently than in UTF-8. Unpaired surrogates are kept as they
+    /// are and treated like U+FFFD while converting.
+    ///
+    #[cfg_attr(feature = "x-system", doc = "```")]
+    #[cfg_attr(not(feature = "x-system"), doc = "```ignore")]
+    /// use esperanto_text::{System, Transliterator};
+    ///
+    /// let t = Transliterator::new(System::X, System::Utf8);
+    /// let input: Vec<u16> = "SXI 🙂 cxu".encode_utf16().collect();
+    /// let output = t.convert_utf16(&input);
+    /// assert_eq!(String::from_utf16(&output).unwrap(), "ŜI 🙂 ĉu");
+    /// ```
+    pub fn convert_utf16(&self, s: &[u16]) -> Vec<u16> {
+        let mut text = String::with_capacity(s.len());
+        // What each U+FFFD in `text` stands for, if it was an unpaired surrogate
+        let mut unpaired = Vec::new();
+        for c in char::decode_utf16(s.iter().copied()) {
+            match c {
+                Ok(c) => {
+                    if c == char::REPLACEMENT_CHARACTER {
+                        unpaired.push(None);
+                    }
+                    text.push(c);
+                }
+                Err(e) => {
+                    unpaired.push(Some(e.unpaired_surrogate()));
+                    text.push(char::REPLACEMENT_CHARACTER);
+                }
+            }
+        }
+        let mut unpaired = unpaired.into_iter();
+        let mut out = Vec::with_capacity(s.len());
+        let mut buf = [0; 2];
+        for c in self.convert_cow(&text).chars() {
+            if c == char::REPLACEMENT_CHARACTER {
+                if let Some(Some(surrogate)) = unpaired.next() {
+                    out.push(surrogate);
+                    continue;
+                }
+            }
+            out.extend_from_slice(c.encode_utf16(&mut buf));
+        }
+        out
+    }
+
+    /// Convert text read from `reader`, writing the output to `writer` as it goes
+    ///
+    /// Input is read in windows that end just after whitespace, or before a
+    /// sentence when sentences are scored on their own, so only a small part
+    /// of it is in memory at once. The end of each window is kept as context
+    /// for the next, so that a name starting a window mid-sentence is still
+    /// seen as one. An error of kind `InvalidData` is returned if the input
+    /// is not valid UTF-8.
+    pub fn convert_stream<R: Read, W: Write>(
+        &self,
+        mut reader: R,
+        mut writer: W,
+    ) -> io::Result<()> {
+        // The first `context` bytes of `pending` have been written already
+        let mut pending: Vec<u8> = Vec::new();
+        let mut context = 0;
+        let mut chunk = vec![0; CHUNK_SIZE];
+        loop {
+            let n = match reader.read(&mut chunk) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            // Only the new bytes need searching for the end of a window, since
+            // anything already pending was held back on purpose
+            let split = chunk[..n]
+                .iter()
+                .rposition(u8::is_ascii_whitespace)
+                .map(|i| pending.len() + i + 1);
+            pending.extend_from_slice(&chunk[..n]);
+            if let Some(split) = split {
+                let window = utf8(&pending[..split])?;
+                // A sentence is held back until it ends, unless it's too long
+                let end = match self.whole_sentences(&window[context..]) {
+                    Some(len) => context + len,
+                    None if split - context < CHUNK_SIZE => continue,
+                    None => split,
+                };
+                self.write_range(&window[..end], context, &mut writer)?;
+                let keep = lookback_start(&window[..end]);
+                pending.drain(..keep);
+                context = end - keep;
+            }
+        }
+        self.write_range(utf8(&pending)?, context, &mut writer)?;
+        writer.flush()
+    }
+
+    /// Convert every string from an iterator
+    pub fn convert_all<I, S>(&self, items: I) -> Vec<String>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        items
+            .into_iter()
+            .map(|s| self.convert(s.as_ref()))
+            .collect()
+    }
+
+    /// Convert every string in a slice, spreading the work across threads
+    #[cfg(feature = "rayon")]
+    pub fn par_convert_all<S>(&self, items: &[S]) -> Vec<String>
+    where
+        S: AsRef<str> + Sync,
+    {
+        use rayon::prelude::*;
+        items.par_iter().map(|s| self.convert(s.as_ref())).collect()
+    }
+
+    /// Convert one large string, splitting it into chunks across threads
+    ///
+    /// The input is only split just after whitespace, where no pattern can be
+    /// matching, so the result is identical to [`convert`](#method.convert).
+    #[cfg(feature = "rayon")]
+    pub fn par_convert(&self, s: &str) -> String {
+        use rayon::prelude::*;
+        let decoder = match self.decoder() {
+            Some(d) => d,
+            None => return s.to_owned(),
+        };
+        let mut ranges = Vec::new();
+        let mut start = 0;
+        while start < s.len() {
+            let end = self.end_of_chunk(s, start);
+            ranges.push((start, end));
+            start = end;
+        }
+        let chunks: Vec<String> = ranges
+            .par_iter()
+            .map(|&(start, end)| {
+                // Every chunk after the first follows whitespace, which casing
+                // treats the same as the start of the text
+                let mut dst = String::with_capacity(end - start);
+                self.convert_range(decoder, s, start, end, &mut dst, None);
+                dst
+            })
+            .collect();
+        chunks.concat()
+    }
+}
+
+/// Whether `found` is exactly one UTF-8 hatted letter written in `form`
+fn is_in_form(found: &str, form: UnicodeForm) -> bool {
+    let mut chars = found.chars();
+    match form {
+        UnicodeForm::Nfc => {
+            let first = chars.next().and_then(Letter::from_hatted);
+            first.is_some() && chars.next().is_none()
+        }
+        // The matcher only finds decomposed letters with the right diacritic
+        UnicodeForm::Nfd => {
+            let mark = chars.nth(1);
+            matches!(mark, Some('\u{302}') | Some('\u{306}')) && chars.next().is_none()
+        }
+    }
+}
+
+fn utf8(bytes: &[u8]) -> io::Result<&str> {
+    std::str::from_utf8(bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Approximate number of bytes converted between checkpoints
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Where to end a chunk of input that begins at `start`
+///
+/// Chunks end just after whitespace, where no pattern can be matching, so
+/// converting them separately gives the same result as converting the whole.
+pub(crate) fn chunk_end(s: &str, start: usize) -> usize {
+    let target = start + CHUNK_SIZE;
+    if target >= s.len() {
+        return s.len();
+    }
+    s.as_bytes()[target..]
+        .iter()
+        .position(|b| b.is_ascii_whitespace())
+        .map_or(s.len(), |i| target + i + 1)
+}
+
+/// Append a hatted letter to `dst` in the target system
+///
+/// `tail` is the input following the letter, which is used along with the
+/// output so far to decide between "Cx" and "CX" for a capital.
+fn encode(options: &Options, letter: Letter, upper: bool, tail: &str, dst: &mut String) {
+    if letter == Letter::H && options.modern_k && modern_k_at(dst, tail) {
+        dst.push(if upper { 'K' } else { 'k' });
+        return;
+    }
+    if let Some(replacement) = options.mapped(letter) {
+        if !upper {
+            dst.push_str(replacement);
+        } else if replacement.chars().count() > 1 && !is_title(options.casing, dst, tail) {
+            dst.push_str(&replacement.to_uppercase());
+        } else {
+            let mut chars = replacement.chars();
+            dst.extend(chars.next().into_iter().flat_map(char::to_uppercase));
+            dst.push_str(chars.as_str());
+        }
+        return;
+    }
+    if letter == Letter::U && options.w_for_breve && options.to != System::Utf8 {
+        dst.push(if upper { 'W' } else { 'w' });
+        return;
+    }
+    match options.to {
+        System::Utf8 => match options.output_form {
+            UnicodeForm::Nfc => dst.push(letter.hatted(upper)),
+            UnicodeForm::Nfd => {
+                dst.push(letter.base(upper));
+                dst.push(letter.combining());
+            }
+        },
+        #[cfg(feature = "x-system")]
+        System::X => encode_ascii(options.casing, letter, upper, Some('x'), tail, dst),
+        #[cfg(feature = "h-system")]
+        System::H if letter == Letter::U || letter == Letter::H && options.plain_h => {
+            encode_ascii(options.casing, letter, upper, None, tail, dst)
+        }
+        #[cfg(feature = "h-system")]
+        System::H => encode_ascii(options.casing, letter, upper, Some('h'), tail, dst),
+        System::PostfixCaret => {
+            dst.push(letter.base(upper));
+            dst.push('^');
+        }
+        System::PrefixCaret => {
+            dst.push('^');
+            dst.push(letter.base(upper));
+        }
+        System::Apostrophe => {
+            dst.push(letter.base(upper));
+            dst.push('\'');
+        }
+        System::Reduced => dst.push(letter.base(upper)),
+        System::Html => html::push_reference(letter, upper, dst),
+        System::Cyrillic => dst.push(to_cyrillic(letter.hatted(upper))),
+        System::UnicodeEscape => {
+            unicode_escape::push_escape(letter, upper, options.braced_escapes, dst)
+        }
+        System::Latex => {
+            dst.push_str(if letter == Letter::U { "\\u{" } else { "\\^{" });
+            if letter == Letter::J && !upper {
+                dst.push_str("\\j");
+            } else {
+                dst.push(letter.base(upper));
+            }
+            dst.push('}');
+        }
+    }
+}
+
+/// Append the base letter and a suffix marking its diacritic, if any
+#[cfg(any(feature = "x-system", feature = "h-system"))]
+fn encode_ascii(
+    casing: CasingPolicy,
+    letter: Letter,
+    upper: bool,
+    suffix: Option<char>,
+    tail: &str,
+    dst: &mut String,
+) {
+    let title = upper && is_title(casing, dst, tail);
+    dst.push(letter.base(upper));
+    if let Some(suffix) = suffix {
+        dst.push(if upper && !title {
+            suffix.to_ascii_uppercase()
+        } else {
+            suffix
+        });
+    }
+}
+
+/// Whether a capital written with several letters is in title case under
+/// `casing`, given the output so far and the input still to come
+fn is_title(casing: CasingPolicy, before: &str, after: &str) -> bool {
+    match casing {
+        CasingPolicy::MatchContext => title_case(before, after),
+        CasingPolicy::AlwaysTitle => true,
+        CasingPolicy::AlwaysUpper => false,
+    }
+}
+
+/// Whether a capital digraph should be written "Cx" rather than "CX"
+///
+/// The rest of the word around the capital is examined: the output so far
+/// in `before` and the input still to come in `after`. Only a word whose
+/// other letters are all capitals is written in all caps, so "ĈU?" becomes
+/// "CXU?" while "Ĉ" on its own and "McĈarlz" keep a lowercase suffix.
+fn title_case(before: &str, after: &str) -> bool {
+    let word_before = before.chars().rev().take_while(|c| is_word_char(*c));
+    let word_after = after.chars().take_while(|c| is_word_char(*c));
+    let mut capitals = false;
+    for c in word_before.chain(word_after) {
+        if c.is_lowercase() {
+            return true;
+        }
+        capitals |= c.is_uppercase();
+    }
+    !capitals
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "x-system")]
+    use crate::letter::LetterSet;
+
+    #[cfg(feature = "x-system")]
+    #[test]
+    fn test_same_system_is_unchanged() {
+        let t = Transliterator::new(System::X, System::X);
+        assert_eq!(t.convert("cxu ĉu"), "cxu ĉu");
+    }
+
+    #[cfg(feature = "h-system")]
+    #[test]
+    fn test_reuse_for_many_items() {
+        let t = Transliterator::new(System::Utf8, System::H);
+        assert_eq!(
+            t.convert_all(["ĉu", "ŝi", "ĵaŭdo"]),
+            vec!["chu", "shi", "jhaudo"]
+        );
+    }
+
+    #[cfg(all(feature = "h-system", feature = "rayon"))]
+    #[test]
+    fn test_parallel_matches_sequential() {
+        let t = Transliterator::new(System::H, System::Utf8);
+        let items: Vec<String> = (0..1000).map(|i| format!("chu {} senchava", i)).collect();
+        assert_eq!(t.par_convert_all(&items), t.convert_all(&items));
+    }
+
+    #[cfg(all(feature = "x-system", feature = "h-system"))]
+    #[test]
+    fn test_chunked_matches_whole() {
+        let t = Transliterator::new(System::H, System::X);
+        let input = "Chiuj senchavaj ideoj. ".repeat(10_000);
+        let expected = "Cxiuj senchavaj ideoj. ".repeat(10_000);
+        assert!(input.len() > CHUNK_SIZE * 2);
+        assert_eq!(t.convert(&input), expected);
+    }
+
+    #[cfg(feature = "x-system")]
+    #[test]
+    fn test_sentences_kept_whole_across_chunks() {
+        let t = Pipeline::new().from(System::X).only_esperanto(0.5).build();
+        // The chunk boundary falls after "Sxi kaj li estas", which on its
+        // own looks like Esperanto
+        let esperanto = "Cxu vi vidas la hundon? ".repeat(CHUNK_SIZE / 24);
+        let quoted = "Sxi kaj li estas tie, but the auxiliary unit was checked.";
+        let input = format!("{}{} Cxu bone?", esperanto, quoted);
+        let expected = format!("{}{} Ĉu bone?", t.convert(&esperanto), quoted);
+        assert_eq!(t.convert(&input), expected);
+        let mut streamed = Vec::new();
+        t.convert_stream(input.as_bytes(), &mut streamed).unwrap();
+        assert_eq!(String::from_utf8(streamed).unwrap(), expected);
+    }
+
+    #[cfg(feature = "x-system")]
+    #[test]
+    fn test_cancelled_part_way() {
+        let t = Transliterator::new(System::X, System::Utf8);
+        let input = "cxu ".repeat(100_000);
+        let token = CancelToken::new();
+        let mut chunks = 0;
+        let result = t.convert_chunked(&input, input.len(), None, |_| {
+            chunks += 1;
+            if chunks == 3 {
+                token.cancel();
+            }
+            if token.is_cancelled() {
+                Err(Error::Cancelled)
+            } else {
+                Ok(())
+            }
+        });
+        assert_eq!(result, Err(Error::Cancelled));
+        assert_eq!(chunks, 3);
+    }
+
+    #[cfg(feature = "x-system")]
+    #[test]
+    fn test_progress_is_monotonic() {
+        let t = Transliterator::new(System::Utf8, System::X);
+        let input = "ĉu ".repeat(100_000);
+        let mut reports = Vec::new();
+        t.convert_with_progress(&input, |done, total| reports.push((done, total)));
+        assert!(reports.len() > 2);
+        assert!(reports.windows(2).all(|w| w[0].0 < w[1].0));
+        assert!(reports.iter().all(|(_, total)| *total == input.len()));
+        assert_eq!(reports.last(), Some(&(input.len(), input.len())));
+    }
+
+    #[cfg(all(feature = "x-system", feature = "rayon"))]
+    #[test]
+    fn test_par_convert_matches_convert() {
+        let t = Transliterator::new(System::Utf8, System::X);
+        let input = "ĈIUJ ŜANĜOJ\nĈiuj ŝanĝoj\n".repeat(20_000);
+        assert!(input.len() > CHUNK_SIZE * 4);
+        assert_eq!(t.par_convert(&input), t.convert(&input));
+    }
+
+    #[cfg(feature = "h-system")]
+    #[test]
+    fn test_lines_without_triggers_skipped() {
+        let t = Transliterator::new(System::H, System::Utf8);
+        let input = "Plain line\nchiuj taugaj\n\nno letters of note\nSENCHAVA";
+        assert_eq!(
+            t.convert(input),
+            "Plain line\nĉiuj taŭgaj\n\nno letters of note\nSENCHAVA"
+        );
+        let decoder = t.decoder().unwrap();
+        assert!(!decoder.may_match("Plain line"));
+        assert!(decoder.may_match("taugaj"));
+        assert!(decoder.may_match("ĉiuj"));
+    }
+
+    #[cfg(all(feature = "x-system", feature = "h-system"))]
+    #[test]
+    fn test_convert_cow_borrows_when_unchanged() {
+        let t = Transliterator::new(System::H, System::Utf8);
+        assert!(matches!(t.convert_cow("plain"), Cow::Borrowed(_)));
+        assert!(matches!(t.convert_cow("senchava"), Cow::Borrowed(_)));
+        assert!(matches!(t.convert_cow("ĉiuj"), Cow::Borrowed(_)));
+        assert!(matches!(t.convert_cow("ĉiuj chiuj"), Cow::Owned(_)));
+        assert_eq!(t.convert_cow("ĉiuj chiuj"), "ĉiuj ĉiuj");
+        let t = Transliterator::new(System::Utf8, System::X);
+        assert!(matches!(t.convert_cow("ĉiuj"), Cow::Owned(_)));
+    }
+
+    #[cfg(feature = "h-system")]
+    #[test]
+    fn test_capacity_covers_h_system_growth() {
+        let t = Transliterator::new(System::H, System::Utf8);
+        let input = "Chiuj estas senchavaj kaj taugaj ideoj. ".repeat(100);
+        let estimate = t.estimate_capacity(&input);
+        let output = t.convert(&input);
+        assert!(output.len() > input.len());
+        assert!(output.capacity() <= estimate);
+    }
+
+    #[cfg(feature = "x-system")]
+    #[test]
+    fn test_capacity_hint_is_respected() {
+        let t = Transliterator::new(System::X, System::Utf8);
+        let output = t.convert_with_capacity_hint("cxu", 1000);
+        assert_eq!(output, "ĉu");
+        assert!(output.capacity() >= 1000);
+    }
+
+    #[cfg(feature = "h-system")]
+    #[test]
+    fn test_stream_matches_convert() {
+        let t = Transliterator::new(System::H, System::Utf8);
+        let input = "Chiuj senchavaj ideoj, TAUGAJ.\n".repeat(10_000);
+        let mut output = Vec::new();
+        t.convert_stream(input.as_bytes(), &mut output).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), t.convert(&input));
+
+        let mut output = Vec::new();
+        t.convert_to_writer(&input, &mut output).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), t.convert(&input));
+    }
+
+    /// A reader that returns at most four bytes at a time
+    #[cfg(all(feature = "x-system", feature = "h-system"))]
+    struct ShortReads<'a>(&'a [u8]);
+
+    #[cfg(all(feature = "x-system", feature = "h-system"))]
+    impl Read for ShortReads<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = buf.len().min(4).min(self.0.len());
+            buf[..n].copy_from_slice(&self.0[..n]);
+            self.0 = &self.0[n..];
+            Ok(n)
+        }
+    }
+
+    #[cfg(all(feature = "x-system", feature = "h-system"))]
+    #[test]
+    fn test_stream_keeps_context_across_short_reads() {
+        let pipelines = [
+            Pipeline::new().from(System::H).protect_proper_nouns(true),
+            Pipeline::new().from(System::X).only_esperanto(0.5),
+        ];
+        let input = "Mi vidis Charles kaj Sasha hierau. \"Sxi kaj li estas tie, but the \
+                     auxiliary unit was checked.\" Cxu bone?";
+        for pipeline in pipelines.iter() {
+            let t = pipeline.clone().build();
+            let mut output = Vec::new();
+            t.convert_stream(ShortReads(input.as_bytes()), &mut output)
+                .unwrap();
+            assert_eq!(String::from_utf8(output).unwrap(), t.convert(input));
+        }
+    }
+
+    #[cfg(all(feature = "h-system", feature = "allocator-api2"))]
+    #[test]
+    fn test_convert_in_matches_convert() {
+        let t = Transliterator::new(System::H, System::Utf8);
+        let text = "Chu shi estas senchava? Jes, hierau. ".repeat(3000);
+        let bytes = t.convert_in(&text, allocator_api2::alloc::Global);
+        assert_eq!(std::str::from_utf8(&bytes), Ok(t.convert(&text).as_str()));
+    }
+
+    #[cfg(feature = "x-system")]
+    #[test]
+    fn test_convert_cow_nfd_output() {
+        let t = Pipeline::new()
+            .from(System::X)
+            .output_form(UnicodeForm::Nfd)
+            .build();
+        assert!(matches!(t.convert_cow("c\u{302}u"), Cow::Borrowed(_)));
+        assert_eq!(t.convert_cow("cxu"), "c\u{302}u");
+        assert_eq!(t.convert_cow("ĉu"), "c\u{302}u");
+    }
+
+    #[cfg(feature = "x-system")]
+    #[test]
+    fn test_casing_uses_whole_word() {
+        let t = Transliterator::new(System::Utf8, System::X);
+        assert_eq!(t.convert("ĈU? JES, ŜI! EĤ. Ĉ"), "CXU? JES, SXI! EHX. Cx");
+        assert_eq!(t.convert("McĈarlz"), "McCxarlz");
+    }
+
+    #[cfg(feature = "h-system")]
+    #[test]
+    fn test_casing_policies() {
+        let input = "Ĉiuj ŜIPOJ";
+        let with = |casing| Pipeline::new().to(System::H).casing(casing).build();
+        assert_eq!(
+            with(CasingPolicy::MatchContext).convert(input),
+            "Chiuj SHIPOJ"
+        );
+        assert_eq!(
+            with(CasingPolicy::AlwaysTitle).convert(input),
+            "Chiuj ShIPOJ"
+        );
+        assert_eq!(
+            with(CasingPolicy::AlwaysUpper).convert(input),
+            "CHiuj SHIPOJ"
+        );
+    }
+
+    #[cfg(feature = "h-system")]
+    #[test]
+    fn test_soft_hyphens() {
+        let input = "sen\u{AD}chava c\u{AD}hu\u{AD}ri";
+        let literal = Transliterator::new(System::H, System::Utf8);
+        assert_eq!(literal.convert(input), "sen\u{AD}ĉava c\u{AD}hu\u{AD}ri");
+        let with = |handling| {
+            Pipeline::new()
+                .from(System::H)
+                .soft_hyphens(handling)
+                .build()
+        };
+        let transparent = with(Invisible::Transparent);
+        assert_eq!(
+            transparent.convert(input),
+            "sen\u{AD}chava ĉ\u{AD}u\u{AD}ri"
+        );
+        assert_eq!(with(Invisible::Remove).convert(input), "senchava ĉuri");
+        assert!(matches!(
+            transparent.convert_cow("c\u{AD}hu"),
+            Cow::Owned(_)
+        ));
+    }
+
+    #[cfg(feature = "x-system")]
+    #[test]
+    fn test_stream_rejects_invalid_utf8() {
+        let t = Transliterator::new(System::X, System::Utf8);
+        let err = t.convert_stream(&b"cxu \xff"[..], Vec::new()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[cfg(all(feature = "x-system", feature = "h-system"))]
+    #[test]
+    fn test_x_system_to_h_system() {
+        let t = Transliterator::new(System::X, System::H);
+        assert_eq!(t.convert("Cxiuj EHXO ĉu"), "Chiuj EHHO chu");
+    }
+
+    #[cfg(feature = "x-system")]
+    #[test]
+    fn test_reduced() {
+        let t = Transliterator::new(System::Utf8, System::Reduced);
+        assert_eq!(t.convert("EĤOŜANĜO c\u{302}u"), "EHOSANGO cu");
+        let t = Transliterator::new(System::X, System::Reduced);
+        assert_eq!(t.convert("sxangxo"), "sango");
+    }
+
+    #[test]
+    fn test_html() {
+        let t = Transliterator::new(System::Utf8, System::Html);
+        assert_eq!(t.convert("<p>Ĉu ŝi?</p>"), "<p>&#264;u &#349;i?</p>");
+        let t = Transliterator::new(System::Html, System::Utf8);
+        assert_eq!(
+            t.convert("&#264;u &#X15D;i &Jcirc;a&ubreve;de? &amp; &#x11C;"),
+            "Ĉu ŝi Ĵaŭde? &amp; Ĝ"
+        );
+        assert_eq!(t.convert_cow("&#265;u"), "ĉu");
+    }
+
+    #[test]
+    fn test_latex() {
+        let t = Transliterator::new(System::Utf8, System::Latex);
+        assert_eq!(t.convert("Ĵaŭde ĵus"), "\\^{J}a\\u{u}de \\^{\\j}us");
+        let t = Transliterator::new(System::Latex, System::Utf8);
+        assert_eq!(
+            t.convert("\\^{C}iu\\^{\\j}a\\u{u}de \\^sipo \\emph{la\\u u}"),
+            "Ĉiuĵaŭde ŝipo \\emph{laŭ}"
+        );
+    }
+
+    #[test]
+    fn test_unicode_escape() {
+        let t = Transliterator::new(System::Utf8, System::UnicodeEscape);
+        assert_eq!(t.convert("\"Ĉu ŝi?\""), "\"\\u0108u \\u015di?\"");
+        let t = Transliterator::new(System::UnicodeEscape, System::Utf8);
+        assert_eq!(
+            t.convert("\\u0108u \\u015Di \\u{135}a\\u{016D}de\\n"),
+            "Ĉu ŝi ĵaŭde\\n"
+        );
+    }
+
+    #[cfg(all(feature = "x-system", feature = "h-system"))]
+    #[test]
+    fn test_cyrillic() {
+        let t = Transliterator::new(System::Utf8, System::Cyrillic);
+        assert_eq!(t.convert("Ĉiuĵaŭde, Ĝis!"), "Чиужаўде, Џис!");
+        let t = Transliterator::new(System::X, System::Cyrillic);
+        assert_eq!(t.convert("SXANGXO"), "ШАНЏО");
+        let t = Transliterator::new(System::Cyrillic, System::H);
+        assert_eq!(t.convert("Шанџо кай һеймо"), "Shangho kaj hejmo");
+        let t = Transliterator::new(System::Cyrillic, System::Utf8);
+        assert_eq!(t.convert_cow("ЧИУ? 123"), "ĈIU? 123");
+    }
+
+    #[cfg(feature = "x-system")]
+    #[test]
+    fn test_mime_encoded_words() {
+        let t = Pipeline::new().from(System::X).mime_encoded_words(true).build();
+        assert_eq!(
+            t.convert("Subject: =?ISO-8859-3?Q?cxu_=E6i?= =?utf-8?B?Y3h1?= sxi"),
+            "Subject: =?ISO-8859-3?Q?=E6u_=E6i?= =?utf-8?B?xIl1?= ŝi"
+        );
+        assert_eq!(t.convert("=?koi8-r?Q?cxu?= =?x"), "=?koi8-r?Q?cxu?= =?x");
+    }
+
+    #[cfg(feature = "h-system")]
+    #[test]
+    fn test_stats_skip_sentences_left_alone() {
+        let t = Pipeline::new().from(System::H).only_esperanto(0.5).build();
+        let mut stats = RuleStats::default();
+        let s = "The shop was cheap and the ship was shiny.";
+        assert_eq!(t.convert_with_stats(s, &mut stats), s);
+        assert_eq!(stats, RuleStats::default());
+    }
+
+    #[cfg(feature = "x-system")]
+    #[test]
+    fn test_stats_match_escapes() {
+        let t = Pipeline::new().from(System::X).escape('\\').build();
+        let mut stats = RuleStats::default();
+        assert_eq!(t.convert_with_stats("c\\xu cxu", &mut stats), "cxu ĉu");
+        assert_eq!(stats.digraphs, 1);
+
+        let t = Pipeline::new()
+            .from(System::X)
+            .percent_escapes(PercentEscapes::Keep)
+            .build();
+        let mut stats = RuleStats::default();
+        assert_eq!(t.convert_with_stats("%CCxu", &mut stats), "%CCxu");
+        assert_eq!(stats.digraphs, 0);
+
+        let t = Pipeline::new()
+            .from(System::X)
+            .percent_escapes(PercentEscapes::Convert)
+            .mime_encoded_words(true)
+            .build();
+        let mut stats = RuleStats::default();
+        assert_eq!(
+            t.convert_with_stats("?q=%63%78u =?utf-8?Q?sxi?=", &mut stats),
+            "?q=%C4%89u =?utf-8?Q?=C5=9Di?="
+        );
+        assert_eq!(stats.digraphs, 2);
+    }
+
+    #[cfg(feature = "x-system")]
+    #[test]
+    fn test_only_esperanto() {
+        let t = Pipeline::new().from(System::X).only_esperanto(0.5).build();
+        let s = "Ni legis la libron. The box was auxiliary.\nCxu vi sxatis gxin?";
+        assert_eq!(
+            t.convert(s),
+            "Ni legis la libron. The box was auxiliary.\nĈu vi ŝatis ĝin?"
+        );
+        assert_eq!(t.convert_cow("The flux was sxown."), "The flux was sxown.");
+    }
+
+    #[cfg(feature = "x-system")]
+    #[test]
+    fn test_letters() {
+        let t = Pipeline::new()
+            .to(System::X)
+            .letters(LetterSet::CIRCUMFLEX)
+            .build();
+        assert_eq!(t.convert("Ŝi vizitis Eŭropon"), "Sxi vizitis Eŭropon");
+        let t = Pipeline::new().from(System::X).letters(LetterSet::U).build();
+        assert_eq!(t.convert("Sxi vizitis Euxropon"), "Sxi vizitis Eŭropon");
+        let t = Pipeline::new().from(System::X).letters(LetterSet::NONE).build();
+        assert!(!t.changes("cxu"));
+    }
+
+    #[cfg(feature = "x-system")]
+    #[test]
+    fn test_convert_utf16() {
+        let t = Transliterator::new(System::Utf8, System::X);
+        let mut input: Vec<u16> = "Ĉ\u{FFFD}Ŝ𝄞ĝ".encode_utf16().collect();
+        input.insert(2, 0xD800);
+        let mut expected: Vec<u16> = "Cx\u{FFFD}Sx𝄞gx".encode_utf16().collect();
+        expected.insert(3, 0xD800);
+        assert_eq!(t.convert_utf16(&input), expected);
+        assert!(t.convert_utf16(&[]).is_empty());
+    }
+
+    #[cfg(feature = "x-system")]
+    #[test]
+    fn test_percent_escapes() {
+        let s = "/%EAu/sxipo?q=%63%78u";
+        let t = Pipeline::new().from(System::X).build();
+        assert_eq!(t.convert(s), "/%EAu/ŝipo?q=%63%78u");
+        let t = Pipeline::new()
+            .from(System::X)
+            .percent_escapes(PercentEscapes::Convert)
+            .build();
+        assert_eq!(t.convert(s), "/%EAu/ŝipo?q=%C4%89u");
+        let t = Pipeline::new()
+            .to(System::Cyrillic)
+            .percent_escapes(PercentEscapes::Keep)
+            .build();
+        assert_eq!(t.convert("%C4%89u"), "%C4%89у");
+    }
+
+    #[test]
+    fn test_apostrophe() {
+        let t = Transliterator::new(System::Utf8, System::Apostrophe);
+        assert_eq!(t.convert("Ĉu ŝi ĵaŭdas? Ankaŭ"), "C'u s'i j'au'das? Ankau'");
+        let t = Transliterator::new(System::Apostrophe, System::Utf8);
+        assert_eq!(
+            t.convert("C'u s'i j'au'das? Ankau', l' pac' kaj dank'"),
+            "Ĉu ŝi ĵaŭdas? Ankaŭ, l' pac' kaj dank'"
+        );
+    }
+
+    #[test]
+    fn test_prefix_caret() {
+        let t = Transliterator::new(System::Utf8, System::PrefixCaret);
+        assert_eq!(t.convert("Ĉu ŝi ĵaŭdas?"), "^Cu ^si ^ja^udas?");
+        let t = Transliterator::new(System::PrefixCaret, System::Utf8);
+        assert_eq!(t.convert("^Cu s^i ^ja^udas? ^_^"), "Ĉu ŝi ĵaŭdas? ^_^");
+    }
+
+    #[cfg(feature = "x-system")]
+    #[test]
+    fn test_postfix_caret() {
+        let t = Transliterator::new(System::Utf8, System::PostfixCaret);
+        assert_eq!(t.convert("Ĉu ŝi ĵaŭdas? EĤO"), "C^u s^i j^au^das? EH^O");
+        let t = Transliterator::new(System::PostfixCaret, System::Utf8);
+        assert_eq!(
+            t.convert("C^u s^i j^au~das? EH^O ^_^"),
+            "Ĉu ŝi ĵaŭdas? EĤO ^_^"
+        );
+        let t = Transliterator::new(System::PostfixCaret, System::X);
+        assert_eq!(t.convert("g^i"), "gxi");
+    }
+}
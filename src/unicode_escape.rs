@@ -0,0 +1,56 @@
+//! Unicode escape sequences for the hatted letters, as in source code
+
+use crate::letter::Letter;
+
+/// Every escape read as a hatted letter, in both the JSON `\u0109` and the
+/// Rust `\u{109}` styles
+///
+/// Hex digits are only given in lowercase, since patterns match regardless
+/// of ASCII case.
+pub(crate) fn escapes() -> impl Iterator<Item = (String, Letter)> {
+    Letter::ALL.iter().flat_map(|&letter| {
+        [false, true].iter().flat_map(move |&upper| {
+            let code = u32::from(letter.hatted(upper));
+            vec![
+                (format!("\\u{:04x}", code), letter),
+                (format!("\\u{{{:04x}}}", code), letter),
+                (format!("\\u{{{:x}}}", code), letter),
+            ]
+        })
+    })
+}
+
+/// Whether the escape `found` is for a capital, or `None` if it isn't one
+pub(crate) fn escape_is_upper(found: &str) -> Option<bool> {
+    let hex = found.strip_prefix("\\u")?;
+    let hex = hex
+        .strip_prefix('{')
+        .and_then(|h| h.strip_suffix('}'))
+        .unwrap_or(hex);
+    let code = u32::from_str_radix(hex, 16).ok()?;
+    let (_, upper) = Letter::from_hatted(char::from_u32(code)?)?;
+    Some(upper)
+}
+
+/// Append the escape for a hatted letter, braced as in Rust if `braced`
+pub(crate) fn push_escape(letter: Letter, upper: bool, braced: bool, dst: &mut String) {
+    let code = u32::from(letter.hatted(upper));
+    if braced {
+        dst.push_str(&format!("\\u{{{:04x}}}", code));
+    } else {
+        dst.push_str(&format!("\\u{:04x}", code));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_is_upper() {
+        assert_eq!(escape_is_upper("\\u0109"), Some(false));
+        assert_eq!(escape_is_upper("\\u{015C}"), Some(true));
+        assert_eq!(escape_is_upper("\\u{11d}"), Some(false));
+        assert_eq!(escape_is_upper("\\u0041"), None);
+    }
+}